@@ -1,5 +1,59 @@
-use engine::App;
+use std::env;
+use std::process::ExitCode;
 
-fn main() {
-    App::new(800, 600);
-}
\ No newline at end of file
+use engine::{App, Config, EditCommand, EditHistory, EditSession, PresetLibrary, PresetSource};
+
+//custicle presets list | apply <name> | clone <name> [<new name>]
+fn presets_command(args: &[String]) -> Result<(), String> {
+    let library = PresetLibrary::new(PresetLibrary::default_profile_dir());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["list"] => {
+            for preset in library.list() {
+                let source = match &preset.source {
+                    PresetSource::Builtin => "built-in".to_string(),
+                    PresetSource::Profile(path) => path.display().to_string()
+                };
+                println!("{:<16} {} ({source})", preset.name, preset.description);
+            }
+            Ok(())
+        },
+        //becomes the crosshair of the edit session (undoable in the editor)
+        ["apply", name] => {
+            let preset = library.load(name)?;
+            let session_path = EditSession::default_path().ok_or("no state directory (set $XDG_STATE_HOME or $HOME)")?;
+            let mut session = EditSession::load(&session_path)
+                .unwrap_or_else(|| EditSession { crosshair: Default::default(), history: EditHistory::default() });
+            let before = session.crosshair.clone();
+            preset.apply_to(&mut session.crosshair);
+            session.history.record(EditCommand::diff(&before, &session.crosshair));
+            session.history.end_step();
+            EditSession::save(&session_path, &session.crosshair, &session.history);
+            println!("applied preset {name} (shown on the next start)");
+            Ok(())
+        },
+        ["clone", name, rest @ ..] if rest.len() <= 1 => {
+            let new_name = rest.first().map_or_else(|| library.unused_name(name), |new_name| new_name.to_string());
+            let path = library.clone_preset(name, &new_name)?;
+            println!("cloned preset {name} to {}", path.display());
+            Ok(())
+        },
+        _ => Err("usage: custicle presets list | apply <name> | clone <name> [<new name>]".to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(("presets", args)) = args.split_first().map(|(command, args)| (command.as_str(), args)) {
+        return match presets_command(args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                println!("{err}");
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    App::new(800, 600, Config::from_env());
+    ExitCode::SUCCESS
+}
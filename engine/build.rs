@@ -0,0 +1,55 @@
+//compiles the glsl shaders in ../shaders to spir-v at build time
+//and generates a table that embeds them with include_bytes!
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[path = "src/shader_compiler.rs"]
+#[allow(dead_code)]
+mod shader_compiler;
+
+use shader_compiler::ShaderStage;
+
+fn main() {
+    let shader_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../shaders");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    println!("cargo::rerun-if-changed={}", shader_dir.display());
+    println!("cargo::rerun-if-changed=src/shader_compiler.rs");
+
+    let mut sources: Vec<PathBuf> = fs::read_dir(&shader_dir)
+        .expect("failed reading shader directory")
+        .map(|entry| entry.expect("failed reading shader directory entry").path())
+        .filter(|path| ShaderStage::from_path(path).is_some())
+        .collect();
+    //stable order -> reproducible generated file
+    sources.sort();
+
+    let mut table = String::from("pub(crate) const EMBEDDED_SHADERS: &[(&str, &[u8])] = &[\n");
+    for source_path in sources.iter() {
+        println!("cargo::rerun-if-changed={}", source_path.display());
+
+        let stage = ShaderStage::from_path(source_path).unwrap();
+        let file_name = source_path.file_name().unwrap().to_string_lossy();
+        let source = fs::read_to_string(source_path)
+            .unwrap_or_else(|err| panic!("failed reading {file_name}: {err}"));
+
+        let spirv = shader_compiler::compile_glsl(&file_name, &source, stage)
+            .unwrap_or_else(|diagnostics| panic!("failed compiling {file_name}:\n{diagnostics}"));
+
+        let spv_name = shader_compiler::spirv_file_name(source_path).unwrap();
+        let spv_path = out_dir.join(&spv_name);
+        let bytes: Vec<u8> = spirv.iter().flat_map(|word| word.to_le_bytes()).collect();
+        fs::write(&spv_path, bytes)
+            .unwrap_or_else(|err| panic!("failed writing {spv_name}: {err}"));
+
+        table.push_str(&format!(
+            "    ({spv_name:?}, include_bytes!({:?})),\n",
+            spv_path.display().to_string()
+        ));
+    }
+    table.push_str("];\n");
+
+    fs::write(out_dir.join("embedded_shaders.rs"), table)
+        .expect("failed writing embedded shader table");
+}
@@ -0,0 +1,134 @@
+use std::thread;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, Window};
+use x11rb::rust_connection::RustConnection;
+
+//client area of the focused window in root window (screen) coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusedWindow {
+    //owning process (none if the window doesn't set _NET_WM_PID)
+    pub pid: Option<u32>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32
+}
+
+//the focused top-level window as announced by the window manager
+//(_NET_ACTIVE_WINDOW on the root window, EWMH)
+pub(crate) struct X11ActiveWindow {
+    connection: RustConnection,
+    root: Window,
+    net_active_window: Atom,
+    net_wm_pid: Atom
+}
+impl X11ActiveWindow {
+    //fails without an x11 display (wayland only)
+    pub(crate) fn connect() -> Result<Self, String> {
+        let (connection, screen_num) = x11rb::connect(None).map_err(|err| format!("no x11 display: {err}"))?;
+        let root = connection.setup().roots[screen_num].root;
+        let intern = |name: &[u8]| -> Result<Atom, String> {
+            Ok(connection
+                .intern_atom(false, name)
+                .map_err(|err| err.to_string())?
+                .reply()
+                .map_err(|err| err.to_string())?
+                .atom)
+        };
+        let net_active_window = intern(b"_NET_ACTIVE_WINDOW")?;
+        let net_wm_pid = intern(b"_NET_WM_PID")?;
+        Ok(Self { connection, root, net_active_window, net_wm_pid })
+    }
+
+    //none if no window is focused or the window manager doesn't tell
+    pub(crate) fn window(&self) -> Option<Window> {
+        self.cardinal(self.root, self.net_active_window, AtomEnum::WINDOW.into()).filter(|&window| window != x11rb::NONE)
+    }
+
+    //process that owns the focused window (_NET_WM_PID, set by most toolkits)
+    pub(crate) fn pid(&self) -> Option<u32> {
+        self.cardinal(self.window()?, self.net_wm_pid, AtomEnum::CARDINAL.into())
+    }
+
+    //position and size of the window's client area (none if it is gone)
+    fn focused_window(&self, window: Window) -> Option<FocusedWindow> {
+        let geometry = self.connection.get_geometry(window).ok()?.reply().ok()?;
+        //relative to its parent, which is the window manager's frame
+        let origin = self.connection.translate_coordinates(window, self.root, 0, 0).ok()?.reply().ok()?;
+        Some(FocusedWindow {
+            pid: self.cardinal(window, self.net_wm_pid, AtomEnum::CARDINAL.into()),
+            x: origin.dst_x.into(),
+            y: origin.dst_y.into(),
+            width: geometry.width.into(),
+            height: geometry.height.into()
+        })
+    }
+
+    //calls `on_change` from a background thread whenever another window
+    //gets focus or the focused one is moved / resized (none: nothing focused)
+    pub(crate) fn listen(self, on_change: impl Fn(Option<FocusedWindow>) + Send + 'static) -> Result<(), String> {
+        //_NET_ACTIVE_WINDOW changes
+        let root_events = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+        self.connection
+            .change_window_attributes(self.root, &root_events)
+            .map_err(|err| err.to_string())?
+            .check()
+            .map_err(|err| format!("failed watching the root window: {err:?}"))?;
+
+        thread::Builder::new()
+            .name("x11 focus".to_string())
+            .spawn(move || {
+                let mut tracked = None;
+                let mut reported = None;
+                let mut refresh = |tracked: &mut Option<Window>| {
+                    let window = self.window();
+                    if window != *tracked {
+                        //configure notifications (moves arrive as synthetic ones from the window manager)
+                        let structure_events = ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY);
+                        if let Some(window) = window {
+                            let _ = self.connection.change_window_attributes(window, &structure_events);
+                        }
+                        //the old window may be gone already
+                        if let Some(old) = tracked.take() {
+                            let _ = self.connection.change_window_attributes(old, &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT));
+                        }
+                        *tracked = window;
+                        let _ = self.connection.flush();
+                    }
+                    let focused = window.and_then(|window| self.focused_window(window));
+                    if focused != reported {
+                        reported = focused;
+                        on_change(focused);
+                    }
+                };
+
+                refresh(&mut tracked);
+                loop {
+                    match self.connection.wait_for_event() {
+                        Ok(Event::PropertyNotify(event)) if event.window == self.root && event.atom == self.net_active_window => refresh(&mut tracked),
+                        Ok(Event::ConfigureNotify(event)) if Some(event.window) == tracked => refresh(&mut tracked),
+                        Ok(Event::DestroyNotify(event)) if Some(event.window) == tracked => refresh(&mut tracked),
+                        Ok(_) => (),
+                        Err(err) => {
+                            println!("x11 focus connection failed: {err}");
+                            return
+                        }
+                    }
+                }
+            })
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    //first 32 bit value of a window property
+    fn cardinal(&self, window: Window, property: Atom, kind: Atom) -> Option<u32> {
+        self.connection
+            .get_property(false, window, property, kind, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?
+            .value32()?
+            .next()
+    }
+}
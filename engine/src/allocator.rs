@@ -0,0 +1,469 @@
+use std::ptr::NonNull;
+use ash::{Device, Instance};
+use ash::vk::{
+    self, Buffer, DeviceMemory, DeviceSize, Image, MappedMemoryRange, MemoryAllocateInfo, MemoryMapFlags, MemoryPropertyFlags, MemoryRequirements, PhysicalDevice, PhysicalDeviceMemoryProperties
+};
+
+//size of the device memory blocks sub-allocations are placed in
+//(larger requests get a dedicated block)
+const BLOCK_SIZE: DeviceSize = 32 * 1024 * 1024;
+
+//what the memory is used for, decides the memory type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MemoryUsage {
+    //only accessed by the gpu (render targets, static buffers)
+    GpuOnly,
+    //transient attachments (tilers can keep them in on-chip memory)
+    GpuLazy,
+    //written by the cpu every frame, read by the gpu (uniforms, staging)
+    Upload,
+    //written by the gpu, read back by the cpu (screenshots, thumbnails)
+    Readback
+}
+impl MemoryUsage {
+    fn required(self) -> MemoryPropertyFlags {
+        match self {
+            Self::GpuOnly | Self::GpuLazy => MemoryPropertyFlags::DEVICE_LOCAL,
+            Self::Upload | Self::Readback => MemoryPropertyFlags::HOST_VISIBLE
+        }
+    }
+
+    fn preferred(self) -> MemoryPropertyFlags {
+        match self {
+            Self::GpuOnly => MemoryPropertyFlags::empty(),
+            Self::GpuLazy => MemoryPropertyFlags::LAZILY_ALLOCATED,
+            Self::Upload => MemoryPropertyFlags::HOST_COHERENT | MemoryPropertyFlags::DEVICE_LOCAL,
+            Self::Readback => MemoryPropertyFlags::HOST_COHERENT | MemoryPropertyFlags::HOST_CACHED
+        }
+    }
+}
+
+//part of a memory block handed out by the allocator
+//(has to be returned with Allocator::free)
+#[derive(Debug)]
+pub(crate) struct Allocation {
+    id: u64,
+    block_index: usize,
+    memory: DeviceMemory,
+    offset: DeviceSize,
+    size: DeviceSize,
+    //start of the allocation if the block is host visible (persistently mapped)
+    mapped: Option<NonNull<u8>>,
+    //memory type needs explicit flushes / invalidates
+    non_coherent: bool
+}
+impl Allocation {
+    //cpu view of host visible memory (none for device local memory)
+    pub(crate) fn mapped_slice_mut(&mut self) -> Option<&mut [u8]> {
+        let mapped = self.mapped?;
+        //the block stays mapped as long as the allocation is alive
+        Some(unsafe { std::slice::from_raw_parts_mut(mapped.as_ptr(), self.size as usize) })
+    }
+
+    //copies `data` to the start of the allocation (host visible memory only)
+    pub(crate) fn write<T: Copy>(&mut self, data: &[T]) {
+        let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data)) };
+        let mapped = self
+            .mapped_slice_mut()
+            .expect("writing to an allocation that is not host visible!");
+        mapped[..bytes.len()].copy_from_slice(bytes);
+    }
+}
+//mapped pointers are only dereferenced through &mut Allocation
+unsafe impl Send for Allocation {}
+
+//a single vkAllocateMemory, sub-allocated first-fit
+struct MemoryBlock {
+    memory: DeviceMemory,
+    memory_type_index: u32,
+    size: DeviceSize,
+    mapped: Option<NonNull<u8>>,
+    //only holds a single allocation of its whole size
+    dedicated: bool,
+    //unused ranges (offset, size), sorted by offset and coalesced
+    free_ranges: Vec<(DeviceSize, DeviceSize)>,
+    //live allocations (id, name, size) for the leak report
+    allocations: Vec<(u64, &'static str, DeviceSize)>
+}
+impl MemoryBlock {
+    fn sub_allocate(&mut self, size: DeviceSize, alignment: DeviceSize) -> Option<DeviceSize> {
+        let (index, offset) = self.free_ranges
+            .iter()
+            .enumerate()
+            .find_map(|(index, &(range_offset, range_size))| {
+                let offset = range_offset.next_multiple_of(alignment);
+                (offset + size <= range_offset + range_size).then_some((index, offset))
+            })?;
+
+        //split the free range into the padding before and the rest after
+        let (range_offset, range_size) = self.free_ranges.remove(index);
+        let after = (offset + size, range_offset + range_size - offset - size);
+        if after.1 > 0 {
+            self.free_ranges.insert(index, after);
+        }
+        if offset > range_offset {
+            self.free_ranges.insert(index, (range_offset, offset - range_offset));
+        }
+        Some(offset)
+    }
+
+    fn release(&mut self, offset: DeviceSize, size: DeviceSize) {
+        let index = self.free_ranges.partition_point(|&(range_offset, _)| range_offset < offset);
+        self.free_ranges.insert(index, (offset, size));
+
+        //merge with the following and the preceding range
+        if index + 1 < self.free_ranges.len() && offset + size == self.free_ranges[index + 1].0 {
+            self.free_ranges[index].1 += self.free_ranges[index + 1].1;
+            self.free_ranges.remove(index + 1);
+        }
+        if index > 0 {
+            let (previous_offset, previous_size) = self.free_ranges[index - 1];
+            if previous_offset + previous_size == offset {
+                self.free_ranges[index - 1].1 += self.free_ranges[index].1;
+                self.free_ranges.remove(index);
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+}
+
+//sub-allocates device memory from large blocks, so the amount of
+//vkAllocateMemory calls stays far below maxMemoryAllocationCount
+pub(crate) struct Allocator {
+    memory_properties: PhysicalDeviceMemoryProperties,
+    //linear and optimal resources must not share a "page" of this size,
+    //every allocation is aligned to it to keep them apart
+    buffer_image_granularity: DeviceSize,
+    //flush / invalidate ranges of non-coherent memory are aligned to it
+    non_coherent_atom_size: DeviceSize,
+    //freed blocks leave a none behind (keeps block indices stable)
+    blocks: Vec<Option<MemoryBlock>>,
+    next_id: u64
+}
+impl Allocator {
+    pub(crate) fn new(instance: &Instance, physical_device: &PhysicalDevice) -> Self {
+        let (memory_properties, limits) = unsafe {
+            (
+                instance.get_physical_device_memory_properties(*physical_device),
+                instance.get_physical_device_properties(*physical_device).limits
+            )
+        };
+        Self {
+            memory_properties,
+            buffer_image_granularity: limits.buffer_image_granularity.max(1),
+            non_coherent_atom_size: limits.non_coherent_atom_size.max(1),
+            blocks: Vec::new(),
+            next_id: 0
+        }
+    }
+
+    //first memory type allowed by type_bits with all required flags,
+    //preferring one that has the preferred flags as well
+    fn find_memory_type(&self, type_bits: u32, required: MemoryPropertyFlags, preferred: MemoryPropertyFlags) -> Option<u32> {
+        let memory_types = &self.memory_properties.memory_types[..self.memory_properties.memory_type_count as usize];
+        let find = |flags: MemoryPropertyFlags| {
+            memory_types
+                .iter()
+                .enumerate()
+                .position(|(index, memory_type)| {
+                    type_bits & (1 << index) != 0 && memory_type.property_flags.contains(flags)
+                })
+                .map(|index| index as u32)
+        };
+
+        find(required | preferred).or_else(|| find(required))
+    }
+
+    fn property_flags(&self, memory_type_index: u32) -> MemoryPropertyFlags {
+        self.memory_properties.memory_types[memory_type_index as usize].property_flags
+    }
+
+    fn allocate_block(&mut self, logical_device: &Device, memory_type_index: u32, size: DeviceSize, dedicated: bool) -> Result<usize, vk::Result> {
+        let memory_allocate_info = MemoryAllocateInfo {
+            allocation_size: size,
+            memory_type_index,
+            ..Default::default()
+        };
+        let memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None)? };
+
+        let mapped = if self.property_flags(memory_type_index).contains(MemoryPropertyFlags::HOST_VISIBLE) {
+            let mapped = unsafe { logical_device.map_memory(memory, 0, vk::WHOLE_SIZE, MemoryMapFlags::empty()) };
+            match mapped {
+                Ok(mapped) => NonNull::new(mapped as *mut u8),
+                Err(err) => {
+                    unsafe { logical_device.free_memory(memory, None) };
+                    return Err(err)
+                }
+            }
+        } else {
+            None
+        };
+
+        let block = MemoryBlock {
+            memory,
+            memory_type_index,
+            size,
+            mapped,
+            dedicated,
+            free_ranges: vec![(0, size)],
+            allocations: Vec::new()
+        };
+        //reuse the slot of a freed block
+        match self.blocks.iter().position(Option::is_none) {
+            Some(index) => {
+                self.blocks[index] = Some(block);
+                Ok(index)
+            },
+            None => {
+                self.blocks.push(Some(block));
+                Ok(self.blocks.len() - 1)
+            }
+        }
+    }
+
+    //`name` identifies the allocation in the leak report
+    pub(crate) fn allocate(&mut self, logical_device: &Device, requirements: MemoryRequirements, usage: MemoryUsage, name: &'static str) -> Result<Allocation, vk::Result> {
+        let memory_type_index = self
+            .find_memory_type(requirements.memory_type_bits, usage.required(), usage.preferred())
+            .ok_or(vk::Result::ERROR_FEATURE_NOT_PRESENT)?;
+        let alignment = requirements.alignment.max(self.buffer_image_granularity);
+        let size = requirements.size;
+
+        //existing block with enough free space, else a new one
+        let existing = self.blocks
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, block)| Some((index, block.as_mut()?)))
+            .filter(|(_, block)| block.memory_type_index == memory_type_index && !block.dedicated)
+            .find_map(|(index, block)| Some((index, block.sub_allocate(size, alignment)?)));
+        let (block_index, offset) = match existing {
+            Some(found) => found,
+            None => {
+                let dedicated = size > BLOCK_SIZE / 2;
+                let block_size = if dedicated { size } else { BLOCK_SIZE };
+                let block_index = self.allocate_block(logical_device, memory_type_index, block_size, dedicated)?;
+                let offset = self.blocks[block_index]
+                    .as_mut()
+                    .unwrap()
+                    .sub_allocate(size, alignment)
+                    .expect("fresh memory block too small!");
+                (block_index, offset)
+            }
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let non_coherent = !self.property_flags(memory_type_index).contains(MemoryPropertyFlags::HOST_COHERENT);
+        let block = self.blocks[block_index].as_mut().unwrap();
+        block.allocations.push((id, name, size));
+
+        Ok(Allocation {
+            id,
+            block_index,
+            memory: block.memory,
+            offset,
+            size,
+            mapped: block.mapped.map(|mapped| unsafe { NonNull::new_unchecked(mapped.as_ptr().add(offset as usize)) }),
+            non_coherent
+        })
+    }
+
+    //allocates and binds memory for an image
+    pub(crate) fn allocate_image(&mut self, logical_device: &Device, image: Image, usage: MemoryUsage, name: &'static str) -> Result<Allocation, vk::Result> {
+        let requirements = unsafe { logical_device.get_image_memory_requirements(image) };
+        let allocation = self.allocate(logical_device, requirements, usage, name)?;
+        if let Err(err) = unsafe { logical_device.bind_image_memory(image, allocation.memory, allocation.offset) } {
+            self.free(logical_device, allocation);
+            return Err(err)
+        }
+        Ok(allocation)
+    }
+
+    //allocates and binds memory for a buffer
+    pub(crate) fn allocate_buffer(&mut self, logical_device: &Device, buffer: Buffer, usage: MemoryUsage, name: &'static str) -> Result<Allocation, vk::Result> {
+        let requirements = unsafe { logical_device.get_buffer_memory_requirements(buffer) };
+        let allocation = self.allocate(logical_device, requirements, usage, name)?;
+        if let Err(err) = unsafe { logical_device.bind_buffer_memory(buffer, allocation.memory, allocation.offset) } {
+            self.free(logical_device, allocation);
+            return Err(err)
+        }
+        Ok(allocation)
+    }
+
+    pub(crate) fn free(&mut self, logical_device: &Device, allocation: Allocation) {
+        let block = self.blocks[allocation.block_index]
+            .as_mut()
+            .expect("freeing an allocation of a released memory block!");
+        block.allocations.retain(|(id, _, _)| *id != allocation.id);
+        block.release(allocation.offset, allocation.size);
+
+        //give empty blocks back to the driver
+        if block.is_empty() {
+            let block = self.blocks[allocation.block_index].take().unwrap();
+            unsafe { logical_device.free_memory(block.memory, None) };
+        }
+    }
+
+    fn mapped_range(&self, allocation: &Allocation) -> MappedMemoryRange<'static> {
+        let block_size = self.blocks[allocation.block_index].as_ref().map_or(0, |block| block.size);
+        let offset = allocation.offset - allocation.offset % self.non_coherent_atom_size;
+        let end = (allocation.offset + allocation.size)
+            .next_multiple_of(self.non_coherent_atom_size)
+            .min(block_size);
+        MappedMemoryRange {
+            memory: allocation.memory,
+            offset,
+            size: end - offset,
+            ..Default::default()
+        }
+    }
+
+    //makes cpu writes visible to the gpu (no-op for coherent memory)
+    pub(crate) fn flush(&self, logical_device: &Device, allocation: &Allocation) -> Result<(), vk::Result> {
+        if !allocation.non_coherent || allocation.mapped.is_none() {
+            return Ok(())
+        }
+        unsafe { logical_device.flush_mapped_memory_ranges(&[self.mapped_range(allocation)]) }
+    }
+
+    //makes gpu writes visible to the cpu (no-op for coherent memory)
+    pub(crate) fn invalidate(&self, logical_device: &Device, allocation: &Allocation) -> Result<(), vk::Result> {
+        if !allocation.non_coherent || allocation.mapped.is_none() {
+            return Ok(())
+        }
+        unsafe { logical_device.invalidate_mapped_memory_ranges(&[self.mapped_range(allocation)]) }
+    }
+
+    //frees all blocks, reporting allocations that were never freed
+    pub(crate) fn destroy(&mut self, logical_device: &Device) {
+        let leaked: Vec<_> = self.blocks
+            .iter()
+            .flatten()
+            .flat_map(|block| block.allocations.iter())
+            .collect();
+        if !leaked.is_empty() {
+            let leaked_bytes: DeviceSize = leaked.iter().map(|(_, _, size)| size).sum();
+            println!("memory leak: {} allocation(s) ({leaked_bytes} bytes) were never freed:", leaked.len());
+            for (id, name, size) in leaked {
+                println!("  #{id} {name}: {size} bytes");
+            }
+        }
+
+        //freeing mapped memory implicitly unmaps it
+        for block in self.blocks.drain(..).flatten() {
+            unsafe { logical_device.free_memory(block.memory, None) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vk::MemoryType;
+
+    fn block(size: DeviceSize) -> MemoryBlock {
+        MemoryBlock {
+            memory: DeviceMemory::null(),
+            memory_type_index: 0,
+            size,
+            mapped: None,
+            dedicated: false,
+            free_ranges: vec![(0, size)],
+            allocations: Vec::new()
+        }
+    }
+
+    #[test]
+    fn sub_allocations_are_aligned_and_packed() {
+        let mut block = block(1024);
+        assert_eq!(block.sub_allocate(100, 1), Some(0));
+        assert_eq!(block.sub_allocate(100, 256), Some(256));
+        //the padding in front of an aligned allocation stays usable
+        assert_eq!(block.free_ranges, vec![(100, 156), (356, 668)]);
+        assert_eq!(block.sub_allocate(100, 4), Some(100));
+        assert_eq!(block.free_ranges, vec![(200, 56), (356, 668)]);
+    }
+
+    #[test]
+    fn sub_allocation_fails_when_nothing_fits() {
+        let mut block = block(1024);
+        assert_eq!(block.sub_allocate(1000, 1), Some(0));
+        assert_eq!(block.sub_allocate(16, 1), Some(1000));
+        //8 bytes are left, but not at an offset aligned to 16
+        assert_eq!(block.sub_allocate(8, 16), None);
+        assert_eq!(block.sub_allocate(8, 8), Some(1016));
+        assert!(block.free_ranges.is_empty());
+        assert_eq!(block.sub_allocate(1, 1), None);
+    }
+
+    #[test]
+    fn first_fit() {
+        let mut block = block(300);
+        let offsets: Vec<_> = (0..3).map(|_| block.sub_allocate(100, 1).unwrap()).collect();
+        assert_eq!(offsets, vec![0, 100, 200]);
+        block.release(0, 100);
+        block.release(200, 100);
+        assert_eq!(block.sub_allocate(50, 1), Some(0));
+    }
+
+    #[test]
+    fn released_ranges_are_coalesced() {
+        let mut block = block(400);
+        for _ in 0..4 {
+            block.sub_allocate(100, 1).unwrap();
+        }
+        block.release(100, 100);
+        block.release(300, 100);
+        assert_eq!(block.free_ranges, vec![(100, 100), (300, 100)]);
+        //merges with the following and the preceding range
+        block.release(200, 100);
+        assert_eq!(block.free_ranges, vec![(100, 300)]);
+        block.release(0, 100);
+        assert_eq!(block.free_ranges, vec![(0, 400)]);
+        assert_eq!(block.sub_allocate(400, 1), Some(0));
+    }
+
+    fn allocator(flags: &[MemoryPropertyFlags]) -> Allocator {
+        let mut memory_properties = PhysicalDeviceMemoryProperties {
+            memory_type_count: flags.len() as u32,
+            ..Default::default()
+        };
+        for (memory_type, &property_flags) in memory_properties.memory_types.iter_mut().zip(flags) {
+            *memory_type = MemoryType { property_flags, heap_index: 0 };
+        }
+        Allocator { memory_properties, buffer_image_granularity: 1, non_coherent_atom_size: 64, blocks: Vec::new(), next_id: 0 }
+    }
+
+    #[test]
+    fn memory_type_prefers_preferred_flags() {
+        let allocator = allocator(&[
+            MemoryPropertyFlags::DEVICE_LOCAL,
+            MemoryPropertyFlags::HOST_VISIBLE,
+            MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT
+        ]);
+        let (required, preferred) = (MemoryUsage::Upload.required(), MemoryUsage::Upload.preferred());
+        //a type with the preferred flags wins over the first one with the required flags
+        assert_eq!(allocator.find_memory_type(0b111, required, MemoryPropertyFlags::HOST_COHERENT), Some(2));
+        //no type has all preferred flags, falls back to the required ones
+        assert_eq!(allocator.find_memory_type(0b111, required, preferred), Some(1));
+        //type_bits of the resource exclude types
+        assert_eq!(allocator.find_memory_type(0b001, required, preferred), None);
+        assert_eq!(allocator.find_memory_type(0b111, MemoryUsage::GpuOnly.required(), MemoryUsage::GpuOnly.preferred()), Some(0));
+    }
+
+    #[test]
+    fn mapped_ranges_are_atom_aligned() {
+        let mut allocator = allocator(&[MemoryPropertyFlags::HOST_VISIBLE]);
+        allocator.blocks.push(Some(block(1000)));
+        let allocation = Allocation { id: 0, block_index: 0, memory: DeviceMemory::null(), offset: 100, size: 10, mapped: None, non_coherent: true };
+        let range = allocator.mapped_range(&allocation);
+        assert_eq!((range.offset, range.size), (64, 64));
+        //clamped to the end of the block
+        let allocation = Allocation { offset: 980, ..allocation };
+        let range = allocator.mapped_range(&allocation);
+        assert_eq!((range.offset, range.size), (960, 40));
+    }
+}
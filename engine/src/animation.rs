@@ -0,0 +1,254 @@
+use std::fmt;
+
+use crate::crosshair::{Color, Crosshair};
+use crate::easing::Easing;
+use crate::property::{Property, PropertyValue};
+
+//what happens after the last keyframe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Looping {
+    //the last value stays
+    #[default]
+    Once,
+    //starts over at the first keyframe
+    Repeat,
+    //plays backwards, then forwards again
+    PingPong
+}
+impl Looping {
+    pub const ALL: [Self; 3] = [Self::Once, Self::Repeat, Self::PingPong];
+
+    //name used in files
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Once => "once",
+            Self::Repeat => "repeat",
+            Self::PingPong => "ping-pong"
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|looping| looping.name() == name.trim())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    //seconds from the start of the track
+    pub time: f32,
+    pub value: PropertyValue,
+    //transition from the previous keyframe to this one
+    pub easing: Easing
+}
+
+//keyframes of a single numeric or color property
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub property: Property,
+    pub looping: Looping,
+    //sorted by time, at least one
+    keyframes: Vec<Keyframe>
+}
+impl Track {
+    pub fn new(property: Property, looping: Looping, keyframes: Vec<Keyframe>) -> Result<Self, String> {
+        let name = property.name();
+        let default = property.get(&Crosshair::default());
+        if !matches!(default, PropertyValue::Number(_) | PropertyValue::Color(_)) {
+            return Err(format!("{name} can't be animated (only numbers and colors)"))
+        }
+        let kind = std::mem::discriminant(&default);
+        if keyframes.is_empty() {
+            return Err(format!("track {name} has no keyframes"))
+        }
+        if keyframes.iter().any(|keyframe| std::mem::discriminant(&keyframe.value) != kind) {
+            return Err(format!("track {name} has values of the wrong kind"))
+        }
+        if keyframes.iter().any(|keyframe| !keyframe.time.is_finite() || keyframe.time < 0.0) || keyframes.windows(2).any(|pair| pair[0].time > pair[1].time) {
+            return Err(format!("keyframe times of track {name} have to be ascending and not negative"))
+        }
+        Ok(Self { property, looping, keyframes })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    //time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    //position in the track `seconds` after the animation started
+    fn local_time(&self, seconds: f32) -> f32 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return 0.0
+        }
+        match self.looping {
+            Looping::Once => seconds.clamp(0.0, duration),
+            Looping::Repeat => seconds.rem_euclid(duration),
+            Looping::PingPong => {
+                let time = seconds.rem_euclid(2.0 * duration);
+                if time > duration { 2.0 * duration - time } else { time }
+            }
+        }
+    }
+
+    //value `seconds` after the animation started
+    pub fn value_at(&self, seconds: f32) -> PropertyValue {
+        let time = self.local_time(seconds);
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        match (self.keyframes.get(next.wrapping_sub(1)), self.keyframes.get(next)) {
+            (Some(previous), Some(next)) => {
+                let progress = next.easing.apply((time - previous.time) / (next.time - previous.time));
+                interpolate(previous.value, next.value, progress)
+            },
+            (Some(keyframe), None) | (None, Some(keyframe)) => keyframe.value,
+            (None, None) => unreachable!("tracks have keyframes")
+        }
+    }
+
+    //the value still changes after `seconds` (frames have to be rendered)
+    pub fn is_active(&self, seconds: f32) -> bool {
+        let changes = self.keyframes.windows(2).any(|pair| pair[0].value != pair[1].value);
+        changes && (self.looping != Looping::Once || seconds < self.duration())
+    }
+
+    //"<property> <looping> <seconds>=<value>[:<easing>] ..."
+    //(easings default to linear)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut words = text.split_whitespace();
+        let (Some(name), Some(looping)) = (words.next(), words.next()) else {
+            return Err(format!("expected \"<property> <looping> <seconds>=<value>[:<easing>] ...\": {text:?}"))
+        };
+        let property = Property::parse(name).ok_or_else(|| format!("unknown property {name:?}"))?;
+        let looping = Looping::parse(looping).ok_or_else(|| format!("unknown looping {looping:?} (once, repeat or ping-pong)"))?;
+        let keyframes = words.map(|word| {
+            let (time, rest) = word.split_once('=').ok_or_else(|| format!("expected \"<seconds>=<value>[:<easing>]\": {word:?}"))?;
+            let (value, easing) = match rest.split_once(':') {
+                Some((value, easing)) => (value, Easing::parse(easing).ok_or_else(|| format!("unknown easing {easing:?}"))?),
+                None => (rest, Easing::default())
+            };
+            Ok(Keyframe {
+                time: time.parse().map_err(|_| format!("invalid time {time:?}"))?,
+                value: property.parse_value(value).ok_or_else(|| format!("invalid value for {name}: {value:?}"))?,
+                easing
+            })
+        }).collect::<Result<_, String>>()?;
+        Self::new(property, looping, keyframes)
+    }
+}
+//file representation (see parse)
+impl fmt::Display for Track {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.property.name(), self.looping.name())?;
+        for keyframe in &self.keyframes {
+            write!(f, " {}={}:{}", keyframe.time, keyframe.value, keyframe.easing)?;
+        }
+        Ok(())
+    }
+}
+
+//values of different kinds can't be mixed (the first one is kept)
+fn interpolate(from: PropertyValue, to: PropertyValue, progress: f32) -> PropertyValue {
+    let lerp = |from: f32, to: f32| from + (to - from) * progress;
+    match (from, to) {
+        (PropertyValue::Number(from), PropertyValue::Number(to)) => PropertyValue::Number(lerp(from, to)),
+        (PropertyValue::Color(from), PropertyValue::Color(to)) => PropertyValue::Color(Color::rgba(
+            lerp(from.r, to.r),
+            lerp(from.g, to.g),
+            lerp(from.b, to.b),
+            lerp(from.a, to.a)
+        )),
+        (from, _) => from
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: PropertyValue) -> f32 {
+        match value {
+            PropertyValue::Number(number) => number,
+            other => panic!("expected a number, got {other:?}")
+        }
+    }
+
+    fn assert_near(value: PropertyValue, expected: f32) {
+        let value = number(value);
+        assert!((value - expected).abs() < 1e-4, "{value} != {expected}");
+    }
+
+    #[test]
+    fn once_clamps() {
+        let track = Track::parse("gap once 1=0 3=10").unwrap();
+        assert_near(track.value_at(-1.0), 0.0);
+        assert_near(track.value_at(0.5), 0.0);
+        assert_near(track.value_at(2.0), 5.0);
+        assert_near(track.value_at(3.0), 10.0);
+        assert_near(track.value_at(100.0), 10.0);
+        assert!(track.is_active(2.0));
+        assert!(!track.is_active(3.0));
+    }
+
+    #[test]
+    fn repeat_starts_over() {
+        let track = Track::parse("gap repeat 0=0 2=10").unwrap();
+        assert_near(track.value_at(1.0), 5.0);
+        assert_near(track.value_at(3.0), 5.0);
+        assert_near(track.value_at(4.5), 2.5);
+        assert!(track.is_active(100.0));
+    }
+
+    #[test]
+    fn ping_pong_plays_backwards() {
+        let track = Track::parse("gap ping-pong 0=0 2=10").unwrap();
+        assert_near(track.value_at(1.0), 5.0);
+        assert_near(track.value_at(2.0), 10.0);
+        assert_near(track.value_at(3.0), 5.0);
+        assert_near(track.value_at(4.0), 0.0);
+        assert_near(track.value_at(5.0), 5.0);
+    }
+
+    #[test]
+    fn easings_shape_the_transition() {
+        for easing in Easing::ALL {
+            let track = Track::parse(&format!("gap once 0=0 1=10:{easing}")).unwrap();
+            assert_near(track.value_at(0.25), 10.0 * easing.apply(0.25));
+            assert_near(track.value_at(1.0), 10.0);
+        }
+        //the easing of a keyframe is the transition into it
+        let track = Track::parse("gap once 0=0 1=10:ease-in 2=0").unwrap();
+        assert_near(track.value_at(0.5), 2.5);
+        assert_near(track.value_at(1.5), 5.0);
+    }
+
+    #[test]
+    fn easing_defaults_to_the_default() {
+        let track = Track::parse("gap once 0=0 1=10").unwrap();
+        assert_eq!(track.keyframes()[1].easing, Easing::default());
+    }
+
+    #[test]
+    fn colors_are_interpolated() {
+        let track = Track::parse("color once 0=0,0,0,1 1=1,0.5,0,1").unwrap();
+        assert_eq!(track.value_at(0.5), PropertyValue::Color(Color::rgba(0.5, 0.25, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let track = Track::parse("opacity ping-pong 0=1 0.8=0.3:smooth").unwrap();
+        assert_eq!(Track::parse(&track.to_string()).unwrap(), track);
+    }
+
+    #[test]
+    fn invalid_tracks() {
+        assert!(Track::parse("gap once").is_err());
+        assert!(Track::parse("gap once 1=0 0=1").is_err());
+        assert!(Track::parse("gap once -1=0").is_err());
+        assert!(Track::parse("gap sometimes 0=0").is_err());
+        assert!(Track::parse("gap once 0=0:bouncy").is_err());
+        assert!(Track::parse("shape once 0=cross").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+use crate::fault::ScheduledFault;
+use crate::hotkey::Hotkey;
+use crate::mode::Mode;
+use crate::msaa::MsaaSamples;
+use crate::renderer::PresentModePreference;
+use crate::shaders;
+use crate::validation::{self, ValidationConfig};
+
+//runtime options gathered from the command line and the environment
+//(command line arguments take precedence over environment variables)
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub validation: ValidationConfig,
+    //directory with .spv files overriding the embedded shaders
+    pub shader_dir: Option<PathBuf>,
+    //directory with glsl sources to watch and hot-reload (development)
+    pub hot_reload_dir: Option<PathBuf>,
+    //requested anti-aliasing (capped by device limits)
+    pub msaa: MsaaSamples,
+    //swapchain present mode
+    pub present_mode: PresentModePreference,
+    //on-screen frame time graph
+    pub show_stats: bool,
+    //csv file every frame's timings are written to
+    pub stats_csv: Option<PathBuf>,
+    //makes the renderer fail after some frames (tests device-lost recovery)
+    pub inject_fault: Option<ScheduledFault>,
+    //mode the overlay starts in
+    pub start_mode: Mode,
+    //global key combination switching between edit and play mode
+    pub hotkey: Hotkey,
+    //cover the focused game window instead of staying where the window manager put us
+    pub follow_window: bool
+}
+impl Config {
+    pub fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::from_args(&args, |key| std::env::var(key).ok())
+    }
+
+    pub fn from_args(args: &[String], env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        //"CUSTICLE_VALIDATION=" is the same as not setting it
+        let env = |key: &str| env(key).filter(|value| !value.trim().is_empty());
+
+        if let Some(value) = env(validation::VALIDATION_ENV_VAR) {
+            match ValidationConfig::parse(&value) {
+                Ok(validation) => config.validation = validation,
+                Err(err) => println!("ignoring {}: {err}", validation::VALIDATION_ENV_VAR)
+            }
+        }
+
+        if let Some(value) = env(shaders::SHADER_DIR_ENV_VAR) {
+            config.shader_dir = Some(PathBuf::from(value));
+        }
+
+        for arg in args {
+            let (key, value) = match arg.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (arg.as_str(), None)
+            };
+
+            match key {
+                "--validation" => match ValidationConfig::parse(value.unwrap_or("")) {
+                    Ok(validation) => config.validation = validation,
+                    Err(err) => println!("ignoring {arg}: {err}")
+                },
+                "--no-validation" => config.validation = ValidationConfig::default(),
+                "--shader-dir" => match value {
+                    Some(value) => config.shader_dir = Some(PathBuf::from(value)),
+                    None => println!("ignoring {arg}: expected --shader-dir=<path>")
+                },
+                "--hot-reload" => {
+                    config.hot_reload_dir = Some(PathBuf::from(value.unwrap_or(shaders::SOURCE_SHADER_DIR)));
+                },
+                "--msaa" => match value.and_then(MsaaSamples::parse) {
+                    Some(msaa) => config.msaa = msaa,
+                    None => println!("ignoring {arg}: expected --msaa=off|2|4|8")
+                },
+                "--present-mode" => match value.and_then(PresentModePreference::parse) {
+                    Some(present_mode) => config.present_mode = present_mode,
+                    None => println!("ignoring {arg}: expected --present-mode=auto|fifo|fifo_relaxed|mailbox|immediate")
+                },
+                "--stats" => config.show_stats = true,
+                "--stats-csv" => match value {
+                    Some(value) => config.stats_csv = Some(PathBuf::from(value)),
+                    None => println!("ignoring {arg}: expected --stats-csv=<path>")
+                },
+                "--inject-fault" => match value.and_then(ScheduledFault::parse) {
+                    Some(inject_fault) => config.inject_fault = Some(inject_fault),
+                    None => println!("ignoring {arg}: expected --inject-fault=device-lost|surface-lost[@frames]")
+                },
+                "--mode" => match value.and_then(Mode::parse) {
+                    Some(mode) => config.start_mode = mode,
+                    None => println!("ignoring {arg}: expected --mode=edit|play")
+                },
+                "--follow-window" => config.follow_window = true,
+                "--hotkey" => match Hotkey::parse(value.unwrap_or("")) {
+                    Ok(hotkey) => config.hotkey = hotkey,
+                    Err(err) => println!("ignoring {arg}: {err}")
+                },
+                _ => println!("ignoring unknown argument: {arg}")
+            }
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::ValidationFeature;
+
+    fn config(args: &[&str], env: &[(&str, &str)]) -> Config {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Config::from_args(&args, |key| env.iter().find(|(name, _)| *name == key).map(|(_, value)| value.to_string()))
+    }
+
+    #[test]
+    fn validation_is_off_by_default() {
+        assert!(!config(&[], &[]).validation.enabled);
+    }
+
+    #[test]
+    fn validation_from_env() {
+        let validation = config(&[], &[(validation::VALIDATION_ENV_VAR, "gpu,sync")]).validation;
+        assert!(validation.enabled);
+        assert_eq!(validation.features, vec![ValidationFeature::GpuAssisted, ValidationFeature::Synchronization]);
+        assert!(!config(&[], &[(validation::VALIDATION_ENV_VAR, "off")]).validation.enabled);
+    }
+
+    #[test]
+    fn empty_env_is_unset() {
+        assert!(!config(&[], &[(validation::VALIDATION_ENV_VAR, "")]).validation.enabled);
+        assert!(!config(&[], &[(validation::VALIDATION_ENV_VAR, "  ")]).validation.enabled);
+        assert_eq!(config(&[], &[(shaders::SHADER_DIR_ENV_VAR, "")]).shader_dir, None);
+    }
+
+    #[test]
+    fn arguments_override_env() {
+        let env = [(validation::VALIDATION_ENV_VAR, "1")];
+        assert!(!config(&["--no-validation"], &env).validation.enabled);
+        let validation = config(&["--validation=bp"], &env).validation;
+        assert_eq!(validation.features, vec![ValidationFeature::BestPractices]);
+
+        let env = [(validation::VALIDATION_ENV_VAR, "off")];
+        assert!(config(&["--validation"], &env).validation.enabled);
+
+        let env = [(shaders::SHADER_DIR_ENV_VAR, "/from/env")];
+        assert_eq!(config(&["--shader-dir=/from/args"], &env).shader_dir, Some(PathBuf::from("/from/args")));
+        assert_eq!(config(&[], &env).shader_dir, Some(PathBuf::from("/from/env")));
+    }
+
+    #[test]
+    fn invalid_values_keep_the_env() {
+        let env = [(validation::VALIDATION_ENV_VAR, "sync")];
+        let validation = config(&["--validation=nonsense"], &env).validation;
+        assert_eq!(validation.features, vec![ValidationFeature::Synchronization]);
+        //an invalid env value is ignored as well
+        assert!(!config(&[], &[(validation::VALIDATION_ENV_VAR, "nonsense")]).validation.enabled);
+    }
+
+    #[test]
+    fn later_arguments_win() {
+        assert!(config(&["--no-validation", "--validation"], &[]).validation.enabled);
+        assert_eq!(config(&["--msaa=2", "--msaa=8"], &[]).msaa, MsaaSamples::parse("8").unwrap());
+    }
+}
@@ -0,0 +1,525 @@
+//crosshair description and its geometry in physical pixels
+use crate::animation::Track;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32
+}
+impl Color {
+    pub const BLACK: Self = Self::rgba(0.0, 0.0, 0.0, 1.0);
+    pub const GREEN: Self = Self::rgba(0.0, 1.0, 0.0, 1.0);
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    //same saturation and brightness, hue turned by `degrees`
+    //(grays, black and white stay as they are)
+    pub fn hue_shifted(self, degrees: f32) -> Self {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let chroma = max - min;
+        if chroma <= 0.0 {
+            return self
+        }
+        //hue in sixths of the color wheel
+        let hue = if max == self.r {
+            ((self.g - self.b) / chroma).rem_euclid(6.0)
+        } else if max == self.g {
+            (self.b - self.r) / chroma + 2.0
+        } else {
+            (self.r - self.g) / chroma + 4.0
+        };
+        let hue = (hue + degrees / 60.0).rem_euclid(6.0);
+        let second = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, second, 0.0),
+            1 => (second, chroma, 0.0),
+            2 => (0.0, chroma, second),
+            3 => (0.0, second, chroma),
+            4 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second)
+        };
+        Self::rgba(r + min, g + min, b + min, self.a)
+    }
+}
+
+//side that receives the extra pixel when a span can't be
+//centered exactly (odd thickness on an even axis or vice versa)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtraPixelSide {
+    //left / top
+    Before,
+    //right / bottom
+    #[default]
+    After
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    //sub-pixel geometry exactly around the screen center (anti-aliased)
+    Smooth,
+    //all edges on the physical pixel grid, sizes rounded to whole pixels
+    PixelPerfect { extra_pixel: ExtraPixelSide }
+}
+impl Default for RenderMode {
+    fn default() -> Self {
+        Self::PixelPerfect { extra_pixel: ExtraPixelSide::default() }
+    }
+}
+
+//arrangement of the arms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Shape {
+    //four arms around the center
+    #[default]
+    Cross,
+    //cross without the top arm
+    T,
+    //two diagonal arms below the center ("^")
+    Chevron
+}
+
+//all sizes are in physical pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crosshair {
+    pub shape: Shape,
+    //length of each arm
+    pub arm_length: f32,
+    //width of an arm across its direction
+    pub arm_thickness: f32,
+    //distance between the center block and the start of each arm
+    pub gap: f32,
+    pub color: Color,
+    //border drawn around every element
+    pub outline: bool,
+    pub outline_thickness: f32,
+    pub outline_color: Color,
+    //square dot in the center
+    pub dot: bool,
+    pub dot_size: f32,
+    //ring around the center, as thick as the arms
+    pub circle: bool,
+    //distance from the center to the middle of the ring
+    pub circle_radius: f32,
+    pub render_mode: RenderMode,
+    //shift from the screen center (rounded to whole pixels when pixel perfect)
+    pub offset_x: f32,
+    pub offset_y: f32,
+    //clockwise turn around the center in degrees (drawn by the vertex shader)
+    pub rotation: f32,
+    //multiplies the alpha of all colors
+    pub opacity: f32,
+    //turns the hue of all colors (degrees)
+    pub hue_shift: f32,
+    //keyframe tracks, at most one per property
+    pub animation: Vec<Track>,
+    //frames per second while a track is animated
+    //(none: the monitor's refresh rate)
+    pub animation_rate: Option<f32>
+}
+impl Default for Crosshair {
+    fn default() -> Self {
+        Self {
+            shape: Shape::default(),
+            arm_length: 8.0,
+            arm_thickness: 2.0,
+            gap: 4.0,
+            color: Color::GREEN,
+            outline: true,
+            outline_thickness: 1.0,
+            outline_color: Color::BLACK,
+            dot: false,
+            dot_size: 2.0,
+            circle: false,
+            circle_radius: 10.0,
+            render_mode: RenderMode::default(),
+            offset_x: 0.0,
+            offset_y: 0.0,
+            rotation: 0.0,
+            opacity: 1.0,
+            hue_shift: 0.0,
+            animation: Vec::new(),
+            animation_rate: None
+        }
+    }
+}
+
+//axis aligned rectangle in physical pixels (origin top-left)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub color: Color
+}
+impl Rect {
+    fn expanded(&self, amount: f32, color: Color) -> Self {
+        Self {
+            x: self.x - amount,
+            y: self.y - amount,
+            width: self.width + 2.0 * amount,
+            height: self.height + 2.0 * amount,
+            color
+        }
+    }
+}
+
+//start of a span of `size` pixels centered on an axis of `axis_len` pixels
+//(both whole numbers, the result is a whole number as well)
+//
+//the exact start is (axis_len - size) / 2, which lies on a pixel boundary
+//iff axis_len and size have the same parity; otherwise it is off by half
+//a pixel and the extra pixel is assigned to the requested side
+pub fn snap_centered_span(axis_len: u32, size: u32, extra_pixel: ExtraPixelSide) -> i64 {
+    let free = axis_len as i64 - size as i64;
+    match extra_pixel {
+        //span moves towards the start -> extra pixel before the center
+        ExtraPixelSide::Before => free.div_euclid(2),
+        ExtraPixelSide::After => free.div_euclid(2) + free.rem_euclid(2)
+    }
+}
+
+//lines through the exact screen center (edit mode), two pixels wide on
+//even axes so they never lean to one side
+pub fn center_guide(width: u32, height: u32) -> Vec<Rect> {
+    const GUIDE_COLOR: Color = Color::rgba(1.0, 0.0, 1.0, 0.5);
+    let thickness = |axis_len: u32| if axis_len.is_multiple_of(2) { 2 } else { 1 };
+    let (vertical, horizontal) = (thickness(width), thickness(height));
+    vec![
+        Rect {
+            x: snap_centered_span(width, vertical, ExtraPixelSide::After) as f32,
+            y: 0.0,
+            width: vertical as f32,
+            height: height as f32,
+            color: GUIDE_COLOR
+        },
+        Rect {
+            x: 0.0,
+            y: snap_centered_span(height, horizontal, ExtraPixelSide::After) as f32,
+            width: width as f32,
+            height: horizontal as f32,
+            color: GUIDE_COLOR
+        }
+    ]
+}
+
+impl Crosshair {
+    //rects to draw (back to front) for a swapchain of the given extent
+    pub fn geometry(&self, width: u32, height: u32) -> Vec<Rect> {
+        let (rects, offset_x, offset_y) = match self.render_mode {
+            RenderMode::Smooth => (self.smooth_geometry(width, height), self.offset_x, self.offset_y),
+            RenderMode::PixelPerfect { extra_pixel } => (self.snapped_geometry(width, height, extra_pixel), self.offset_x.round(), self.offset_y.round())
+        };
+        let opacity = self.opacity.clamp(0.0, 1.0);
+        rects
+            .into_iter()
+            .map(|rect| {
+                let color = rect.color.hue_shifted(self.hue_shift);
+                Rect { x: rect.x + offset_x, y: rect.y + offset_y, color: Color { a: color.a * opacity, ..color }, ..rect }
+            })
+            .collect()
+    }
+
+    //point the crosshair turns around (the middle of its center block)
+    pub fn center(&self, width: u32, height: u32) -> (f32, f32) {
+        match self.render_mode {
+            RenderMode::Smooth => (width as f32 / 2.0 + self.offset_x, height as f32 / 2.0 + self.offset_y),
+            RenderMode::PixelPerfect { extra_pixel } => {
+                let thickness = (self.arm_thickness.round().max(0.0) as u32).max(1);
+                let center = |axis_len: u32, offset: f32| snap_centered_span(axis_len, thickness, extra_pixel) as f32 + thickness as f32 / 2.0 + offset.round();
+                (center(width, self.offset_x), center(height, self.offset_y))
+            }
+        }
+    }
+
+    //the crosshair `seconds` after its animation started
+    //(tracks replace the values of their properties)
+    pub fn at_time(&self, seconds: f32) -> Self {
+        let mut crosshair = self.clone();
+        for track in &self.animation {
+            track.property.set(&mut crosshair, track.value_at(seconds));
+        }
+        crosshair
+    }
+
+    //a track still changes after `seconds`
+    pub fn is_animated_at(&self, seconds: f32) -> bool {
+        self.animation.iter().any(|track| track.is_active(seconds))
+    }
+
+    //elements around a center block starting at (center_x, center_y)
+    //of size thickness x thickness
+    fn elements(&self, center_x: f32, center_y: f32, thickness: f32, length: f32, gap: f32, dot: Option<(f32, f32, f32)>) -> Vec<Rect> {
+        let color = self.color;
+        let mut rects = Vec::with_capacity(5);
+
+        if length > 0.0 {
+            match self.shape {
+                Shape::Cross | Shape::T => {
+                    //left, right, top, bottom
+                    rects.push(Rect { x: center_x - gap - length, y: center_y, width: length, height: thickness, color });
+                    rects.push(Rect { x: center_x + thickness + gap, y: center_y, width: length, height: thickness, color });
+                    if self.shape == Shape::Cross {
+                        rects.push(Rect { x: center_x, y: center_y - gap - length, width: thickness, height: length, color });
+                    }
+                    rects.push(Rect { x: center_x, y: center_y + thickness + gap, width: thickness, height: length, color });
+                },
+                //a staircase of thickness x thickness blocks, one pixel per step
+                Shape::Chevron => for step in 1..=length as u32 {
+                    let offset = gap + step as f32;
+                    rects.push(Rect { x: center_x - offset, y: center_y + offset, width: thickness, height: thickness, color });
+                    rects.push(Rect { x: center_x + offset, y: center_y + offset, width: thickness, height: thickness, color });
+                }
+            }
+        }
+        if let Some((dot_x, dot_y, dot_size)) = dot {
+            rects.push(Rect { x: dot_x, y: dot_y, width: dot_size, height: dot_size, color });
+        }
+
+        rects
+    }
+
+    //outlines go underneath all elements, so they never cover the fill
+    fn with_outlines(&self, rects: Vec<Rect>, outline_thickness: f32) -> Vec<Rect> {
+        if !self.outline || outline_thickness <= 0.0 {
+            return rects
+        }
+
+        let mut outlined: Vec<Rect> = rects
+            .iter()
+            .map(|rect| rect.expanded(outline_thickness, self.outline_color))
+            .collect();
+        outlined.extend(rects);
+        outlined
+    }
+
+    fn smooth_geometry(&self, width: u32, height: u32) -> Vec<Rect> {
+        let thickness = self.arm_thickness.max(0.0);
+        let center_x = width as f32 / 2.0 - thickness / 2.0;
+        let center_y = height as f32 / 2.0 - thickness / 2.0;
+
+        let dot_size = self.dot_size.max(0.0);
+        let dot = self.dot.then(|| (
+            width as f32 / 2.0 - dot_size / 2.0,
+            height as f32 / 2.0 - dot_size / 2.0,
+            dot_size
+        ));
+
+        let mut rects = self.elements(center_x, center_y, thickness, self.arm_length.max(0.0), self.gap.max(0.0), dot);
+        if self.circle {
+            rects.extend(ring(width as f32 / 2.0, height as f32 / 2.0, self.circle_radius.max(0.0), thickness, self.color, false));
+        }
+        self.with_outlines(rects, self.outline_thickness.max(0.0))
+    }
+
+    fn snapped_geometry(&self, width: u32, height: u32, extra_pixel: ExtraPixelSide) -> Vec<Rect> {
+        let whole = |value: f32| value.round().max(0.0) as u32;
+        //an arm is always at least one pixel thick
+        let thickness = whole(self.arm_thickness).max(1);
+        let length = whole(self.arm_length);
+        let gap = whole(self.gap);
+
+        let center_x = snap_centered_span(width, thickness, extra_pixel) as f32;
+        let center_y = snap_centered_span(height, thickness, extra_pixel) as f32;
+
+        let dot_size = whole(self.dot_size).max(1);
+        let dot = self.dot.then(|| (
+            snap_centered_span(width, dot_size, extra_pixel) as f32,
+            snap_centered_span(height, dot_size, extra_pixel) as f32,
+            dot_size as f32
+        ));
+
+        let mut rects = self.elements(center_x, center_y, thickness as f32, length as f32, gap as f32, dot);
+        if self.circle {
+            //around the center block, like the arms
+            let half = thickness as f32 / 2.0;
+            rects.extend(ring(center_x + half, center_y + half, whole(self.circle_radius) as f32, thickness as f32, self.color, true));
+        }
+        self.with_outlines(rects, whole(self.outline_thickness) as f32)
+    }
+}
+
+//ring around (center_x, center_y) as one or two spans per pixel row
+//(snapped: only whole pixels whose center lies inside the ring)
+fn ring(center_x: f32, center_y: f32, radius: f32, thickness: f32, color: Color, snap: bool) -> Vec<Rect> {
+    let outer = radius + thickness / 2.0;
+    let inner = (radius - thickness / 2.0).max(0.0);
+    //half width of a circle at vertical distance dy (none outside)
+    let half_width = |circle_radius: f32, dy: f32| (dy.abs() < circle_radius).then(|| (circle_radius * circle_radius - dy * dy).sqrt());
+
+    let span = |y: f32, from: f32, to: f32| {
+        let (from, to) = if snap { ((from - 0.5).ceil(), (to + 0.5).floor()) } else { (from, to) };
+        (to > from).then_some(Rect { x: from, y, width: to - from, height: 1.0, color })
+    };
+
+    let mut rects = Vec::new();
+    for row in (center_y - outer).floor() as i64..(center_y + outer).ceil() as i64 {
+        let y = row as f32;
+        let dy = y + 0.5 - center_y;
+        let Some(outer_half) = half_width(outer, dy) else {
+            continue
+        };
+        match half_width(inner, dy) {
+            Some(inner_half) => {
+                rects.extend(span(y, center_x - outer_half, center_x - inner_half));
+                rects.extend(span(y, center_x + inner_half, center_x + outer_half));
+            },
+            None => rects.extend(span(y, center_x - outer_half, center_x + outer_half))
+        }
+    }
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIDES: [ExtraPixelSide; 2] = [ExtraPixelSide::Before, ExtraPixelSide::After];
+
+    fn pixel_perfect(extra_pixel: ExtraPixelSide) -> Crosshair {
+        Crosshair { render_mode: RenderMode::PixelPerfect { extra_pixel }, outline: false, ..Default::default() }
+    }
+
+    fn assert_whole(rects: &[Rect]) {
+        for rect in rects {
+            assert!([rect.x, rect.y, rect.width, rect.height].iter().all(|value| value.fract() == 0.0), "{rect:?}");
+        }
+    }
+
+    //free pixels before and after a span of `size` pixels starting at `start`
+    fn margins(axis_len: u32, size: u32, start: i64) -> (i64, i64) {
+        (start, axis_len as i64 - start - size as i64)
+    }
+
+    #[test]
+    fn span_on_matching_parity_is_exact() {
+        for extra_pixel in SIDES {
+            assert_eq!(snap_centered_span(1920, 2, extra_pixel), 959);
+            assert_eq!(snap_centered_span(1081, 3, extra_pixel), 539);
+        }
+    }
+
+    #[test]
+    fn span_on_mismatched_parity_puts_the_extra_pixel_on_the_requested_side() {
+        assert_eq!(snap_centered_span(1920, 3, ExtraPixelSide::Before), 958);
+        assert_eq!(snap_centered_span(1920, 3, ExtraPixelSide::After), 959);
+        assert_eq!(snap_centered_span(1081, 2, ExtraPixelSide::Before), 539);
+        assert_eq!(snap_centered_span(1081, 2, ExtraPixelSide::After), 540);
+    }
+
+    #[test]
+    fn span_margins_differ_by_at_most_one() {
+        for axis_len in [1080, 1081, 1920, 1921] {
+            for size in [1, 2, 3, 4] {
+                let (before, after) = margins(axis_len, size, snap_centered_span(axis_len, size, ExtraPixelSide::Before));
+                assert!(before == after || before + 1 == after, "{axis_len} {size}: {before} {after}");
+                let (before, after) = margins(axis_len, size, snap_centered_span(axis_len, size, ExtraPixelSide::After));
+                assert!(before == after || before == after + 1, "{axis_len} {size}: {before} {after}");
+            }
+        }
+    }
+
+    #[test]
+    fn span_larger_than_the_axis() {
+        assert_eq!(snap_centered_span(3, 6, ExtraPixelSide::Before), -2);
+        assert_eq!(snap_centered_span(3, 6, ExtraPixelSide::After), -1);
+    }
+
+    #[test]
+    fn snapped_arms_are_symmetric() {
+        for (width, height) in [(1920, 1080), (1921, 1081), (1920, 1081)] {
+            for thickness in [1.0, 2.0, 3.0] {
+                for extra_pixel in SIDES {
+                    let crosshair = Crosshair { arm_thickness: thickness, ..pixel_perfect(extra_pixel) };
+                    let rects = crosshair.geometry(width, height);
+                    assert_whole(&rects);
+                    let [left, right, top, bottom] = rects[..] else {
+                        panic!("expected four arms: {rects:?}")
+                    };
+                    let center_x = snap_centered_span(width, thickness as u32, extra_pixel) as f32;
+                    let center_y = snap_centered_span(height, thickness as u32, extra_pixel) as f32;
+                    //same gap on every side of the center block
+                    assert_eq!(center_x - (left.x + left.width), right.x - (center_x + thickness));
+                    assert_eq!(center_y - (top.y + top.height), bottom.y - (center_y + thickness));
+                    assert_eq!((left.y, right.y, top.x, bottom.x), (center_y, center_y, center_x, center_x));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fractional_sizes_are_rounded() {
+        let crosshair = Crosshair { arm_thickness: 2.4, arm_length: 7.6, gap: 3.5, ..pixel_perfect(ExtraPixelSide::After) };
+        let rects = crosshair.geometry(1920, 1080);
+        assert_whole(&rects);
+        assert_eq!((rects[0].width, rects[0].height), (8.0, 2.0));
+    }
+
+    #[test]
+    fn snapped_dot_is_centered() {
+        for (width, height) in [(1920, 1080), (1921, 1081)] {
+            for dot_size in [1.0, 2.0, 3.0] {
+                for extra_pixel in SIDES {
+                    let crosshair = Crosshair { dot: true, dot_size, arm_length: 0.0, ..pixel_perfect(extra_pixel) };
+                    let rects = crosshair.geometry(width, height);
+                    assert_whole(&rects);
+                    let [dot] = rects[..] else {
+                        panic!("expected the dot only: {rects:?}")
+                    };
+                    let size = dot_size as u32;
+                    assert_eq!(dot.x as i64, snap_centered_span(width, size, extra_pixel));
+                    assert_eq!(dot.y as i64, snap_centered_span(height, size, extra_pixel));
+                    assert_eq!((dot.width, dot.height), (dot_size, dot_size));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn snapped_ring_is_whole_and_symmetric() {
+        for (width, height) in [(1920, 1080), (1921, 1081)] {
+            for thickness in [1.0, 2.0] {
+                for extra_pixel in SIDES {
+                    let crosshair = Crosshair { circle: true, circle_radius: 10.0, arm_length: 0.0, arm_thickness: thickness, ..pixel_perfect(extra_pixel) };
+                    let rects = crosshair.geometry(width, height);
+                    assert!(!rects.is_empty());
+                    assert_whole(&rects);
+                    //mirrored around the middle of the center block
+                    let (center_x, center_y) = crosshair.center(width, height);
+                    let mirrored = |rect: &Rect| (2.0 * center_x - rect.x - rect.width, 2.0 * center_y - rect.y - rect.height);
+                    for rect in &rects {
+                        let (x, y) = mirrored(rect);
+                        assert!(rects.iter().any(|other| (other.x, other.y, other.width) == (x, y, rect.width)), "{rect:?} isn't mirrored");
+                    }
+                    //every pixel center lies within the ring
+                    for rect in &rects {
+                        let dy = rect.y + 0.5 - center_y;
+                        for column in 0..rect.width as i64 {
+                            let dx = rect.x + column as f32 + 0.5 - center_x;
+                            let distance = (dx * dx + dy * dy).sqrt();
+                            assert!((distance - 10.0).abs() <= thickness / 2.0, "pixel at {distance} from the center");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_geometry_is_exactly_centered() {
+        let crosshair = Crosshair { render_mode: RenderMode::Smooth, outline: false, dot: true, arm_thickness: 1.5, dot_size: 3.0, ..Default::default() };
+        let rects = crosshair.geometry(1921, 1080);
+        let dot = rects.last().unwrap();
+        assert_eq!((dot.x + dot.width / 2.0, dot.y + dot.height / 2.0), (960.5, 540.0));
+        assert_eq!(crosshair.center(1921, 1080), (960.5, 540.0));
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use ash::Device;
+use ash::vk::{CommandPool, DescriptorPool, DescriptorSetLayout, Fence, Framebuffer, ImageView, RenderPass, Sampler, Semaphore, SwapchainKHR};
+
+use crate::allocator::Allocator;
+
+//everything needed to destroy a device resource
+pub(crate) struct DestroyContext<'a> {
+    pub(crate) logical_device: &'a Device,
+    pub(crate) allocator: &'a mut Allocator,
+    pub(crate) swapchain_loader: &'a ash::khr::swapchain::Device
+}
+
+//vulkan object owned by the renderer, destroyed through the deletion queue
+pub(crate) trait DeviceResource {
+    fn destroy(self, ctx: &mut DestroyContext);
+}
+
+impl DeviceResource for Framebuffer {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_framebuffer(self, None) };
+    }
+}
+impl DeviceResource for ImageView {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_image_view(self, None) };
+    }
+}
+impl DeviceResource for Semaphore {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_semaphore(self, None) };
+    }
+}
+impl DeviceResource for Fence {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_fence(self, None) };
+    }
+}
+impl DeviceResource for RenderPass {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_render_pass(self, None) };
+    }
+}
+//frees the command buffers allocated from it as well
+impl DeviceResource for CommandPool {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_command_pool(self, None) };
+    }
+}
+impl DeviceResource for Sampler {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_sampler(self, None) };
+    }
+}
+impl DeviceResource for DescriptorSetLayout {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_descriptor_set_layout(self, None) };
+    }
+}
+//frees the descriptor sets allocated from it as well
+impl DeviceResource for DescriptorPool {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_descriptor_pool(self, None) };
+    }
+}
+impl DeviceResource for SwapchainKHR {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.swapchain_loader.destroy_swapchain(self, None) };
+    }
+}
+impl<T: DeviceResource> DeviceResource for Option<T> {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        if let Some(resource) = self {
+            resource.destroy(ctx);
+        }
+    }
+}
+impl<T: DeviceResource> DeviceResource for Vec<T> {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        self.into_iter().for_each(|resource| resource.destroy(ctx));
+    }
+}
+
+type Deleter = Box<dyn FnOnce(&mut DestroyContext)>;
+
+//resources that may still be referenced by submitted frames
+//
+//every retired resource is tagged with the amount of frames submitted
+//so far, it is destroyed once all of those frames finished on the gpu
+//(no device_wait_idle needed when replacing resources mid-run)
+pub(crate) struct DeletionQueue<T = Deleter> {
+    //(frames submitted when retired, resource), in retirement order
+    pending: VecDeque<(u64, T)>
+}
+impl<T> Default for DeletionQueue<T> {
+    fn default() -> Self {
+        Self { pending: VecDeque::new() }
+    }
+}
+impl<T> DeletionQueue<T> {
+    fn push(&mut self, submitted_frames: u64, resource: T) {
+        self.pending.push_back((submitted_frames, resource));
+    }
+
+    //passes the resources no frame < completed_frames can use anymore to `destroy`
+    fn collect_with(&mut self, completed_frames: u64, mut destroy: impl FnMut(T)) {
+        while self.pending.front().is_some_and(|(submitted_frames, _)| *submitted_frames <= completed_frames) {
+            let (_, resource) = self.pending.pop_front().unwrap();
+            destroy(resource);
+        }
+    }
+}
+impl DeletionQueue {
+    pub(crate) fn retire(&mut self, submitted_frames: u64, resource: impl DeviceResource + 'static) {
+        self.push(submitted_frames, Box::new(move |ctx: &mut DestroyContext| resource.destroy(ctx)));
+    }
+
+    //destroys the resources no frame < completed_frames can use anymore
+    pub(crate) fn collect(&mut self, completed_frames: u64, ctx: &mut DestroyContext) {
+        self.collect_with(completed_frames, |deleter| deleter(ctx));
+    }
+
+    //destroys everything (the device has to be idle)
+    pub(crate) fn flush(&mut self, ctx: &mut DestroyContext) {
+        self.pending.drain(..).for_each(|(_, deleter)| deleter(ctx));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use super::*;
+
+    //stands in for a device resource, records its destruction
+    struct Counted {
+        id: u32,
+        destroyed: Rc<RefCell<Vec<u32>>>
+    }
+
+    fn queue(destroyed: &Rc<RefCell<Vec<u32>>>, retired: &[(u64, u32)]) -> DeletionQueue<Counted> {
+        let mut queue = DeletionQueue::default();
+        for &(submitted_frames, id) in retired {
+            queue.push(submitted_frames, Counted { id, destroyed: destroyed.clone() });
+        }
+        queue
+    }
+
+    fn collect(queue: &mut DeletionQueue<Counted>, completed_frames: u64) {
+        queue.collect_with(completed_frames, |resource| resource.destroyed.borrow_mut().push(resource.id));
+    }
+
+    #[test]
+    fn destroyed_once_its_frames_completed() {
+        let destroyed = Rc::new(RefCell::new(Vec::new()));
+        let mut queue = queue(&destroyed, &[(1, 10), (2, 20), (2, 21), (4, 40)]);
+
+        collect(&mut queue, 0);
+        assert!(destroyed.borrow().is_empty());
+        collect(&mut queue, 1);
+        assert_eq!(*destroyed.borrow(), [10]);
+        collect(&mut queue, 3);
+        assert_eq!(*destroyed.borrow(), [10, 20, 21]);
+        collect(&mut queue, 4);
+        assert_eq!(*destroyed.borrow(), [10, 20, 21, 40]);
+    }
+
+    #[test]
+    fn destroyed_exactly_once() {
+        let destroyed = Rc::new(RefCell::new(Vec::new()));
+        let mut queue = queue(&destroyed, &[(1, 10), (1, 11)]);
+        collect(&mut queue, 5);
+        collect(&mut queue, 6);
+        assert_eq!(*destroyed.borrow(), [10, 11]);
+    }
+
+    #[test]
+    fn resources_retired_later_wait_for_their_frames() {
+        let destroyed = Rc::new(RefCell::new(Vec::new()));
+        let mut queue = queue(&destroyed, &[(1, 10)]);
+        collect(&mut queue, 1);
+        queue.push(3, Counted { id: 30, destroyed: destroyed.clone() });
+        collect(&mut queue, 2);
+        assert_eq!(*destroyed.borrow(), [10]);
+        collect(&mut queue, 3);
+        assert_eq!(*destroyed.borrow(), [10, 30]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::easing::Easing;
+use crate::input_rules::{InputButton, InputEvent};
+
+//how far and how fast the gap opens and closes again (pixels, seconds)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadSettings {
+    //extra gap while a movement button is held
+    pub move_spread: f32,
+    //extra gap added by every shot
+    pub fire_spread: f32,
+    //upper limit of the extra gap
+    pub max_spread: f32,
+    //time to open up to the movement spread
+    pub expand_time: Duration,
+    pub expand_easing: Easing,
+    //time to close from any spread back to the resting one
+    pub recovery_time: Duration,
+    pub recovery_easing: Easing
+}
+impl Default for SpreadSettings {
+    fn default() -> Self {
+        Self {
+            move_spread: 4.0,
+            fire_spread: 3.0,
+            max_spread: 12.0,
+            expand_time: Duration::from_millis(100),
+            expand_easing: Easing::EaseOut,
+            recovery_time: Duration::from_millis(350),
+            recovery_easing: Easing::EaseOut
+        }
+    }
+}
+
+//extra gap of a dynamic crosshair
+//
+//the spread is a pure function of the time since the last input
+//(no per-frame integration), so it doesn't depend on the frame rate and
+//a simulated timeline of InputEvents always gives the same values
+#[derive(Debug, Clone, Default)]
+pub struct DynamicSpread {
+    pub settings: SpreadSettings,
+    move_buttons: Vec<InputButton>,
+    fire_buttons: Vec<InputButton>,
+    //movement buttons currently held
+    held: HashSet<InputButton>,
+    //spread right after the last input and when that was
+    //(none: no input yet, resting)
+    last_change: Option<(Instant, f32)>
+}
+impl DynamicSpread {
+    pub fn new(settings: SpreadSettings, move_buttons: Vec<InputButton>, fire_buttons: Vec<InputButton>) -> Self {
+        Self { settings, move_buttons, fire_buttons, ..Default::default() }
+    }
+
+    //buttons the input source has to report
+    pub fn buttons(&self) -> impl Iterator<Item = InputButton> + '_ {
+        self.move_buttons.iter().chain(&self.fire_buttons).copied()
+    }
+
+    pub fn is_moving(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    //spread the gap heads to (movement spread or none)
+    fn target(&self) -> f32 {
+        if self.is_moving() { self.settings.move_spread.min(self.settings.max_spread) } else { 0.0 }
+    }
+
+    //`event` happened at `at` (events have to arrive in order)
+    pub fn handle(&mut self, event: InputEvent, at: Instant) {
+        //the current value is the start of the next transition
+        let spread = self.spread(at);
+        match event {
+            InputEvent::Pressed(button) if self.fire_buttons.contains(&button) => {
+                let spread = (spread + self.settings.fire_spread).min(self.settings.max_spread);
+                self.last_change = Some((at, spread));
+            },
+            InputEvent::Pressed(button) if self.move_buttons.contains(&button) => {
+                //presses of a held button are repeats
+                if self.held.insert(button) {
+                    self.last_change = Some((at, spread));
+                }
+            },
+            InputEvent::Released(button) => {
+                if self.held.remove(&button) {
+                    self.last_change = Some((at, spread));
+                }
+            },
+            InputEvent::Pressed(_) => ()
+        }
+    }
+
+    //extra gap at `at` (pixels, never negative)
+    pub fn spread(&self, at: Instant) -> f32 {
+        let target = self.target();
+        let Some((since, start)) = self.last_change else {
+            return target
+        };
+        let elapsed = at.saturating_duration_since(since);
+        let (duration, easing) = self.transition(start);
+        let progress = if duration.is_zero() { 1.0 } else { easing.apply(elapsed.as_secs_f32() / duration.as_secs_f32()) };
+        start + (target - start) * progress
+    }
+
+    //duration and easing of a transition starting at `start`
+    fn transition(&self, start: f32) -> (Duration, Easing) {
+        if start < self.target() {
+            (self.settings.expand_time, self.settings.expand_easing)
+        } else {
+            (self.settings.recovery_time, self.settings.recovery_easing)
+        }
+    }
+
+    //the spread still changes after `at` (frames have to be rendered)
+    pub fn is_animating(&self, at: Instant) -> bool {
+        self.last_change.is_some_and(|(since, start)| {
+            start != self.target() && at.saturating_duration_since(since) < self.transition(start).0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: InputButton = InputButton::Key(0x77);
+    const D: InputButton = InputButton::Key(0x64);
+    const LMB: InputButton = InputButton::Mouse(1);
+
+    //linear transitions make the expected values easy to compute
+    fn spread() -> DynamicSpread {
+        let settings = SpreadSettings {
+            move_spread: 4.0,
+            fire_spread: 3.0,
+            max_spread: 8.0,
+            expand_time: Duration::from_millis(100),
+            expand_easing: Easing::Linear,
+            recovery_time: Duration::from_millis(400),
+            recovery_easing: Easing::Linear
+        };
+        DynamicSpread::new(settings, vec![W, D], vec![LMB])
+    }
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    fn assert_near(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-4, "{value} != {expected}");
+    }
+
+    #[test]
+    fn rests_without_input() {
+        let spread = spread();
+        let now = Instant::now();
+        assert_near(spread.spread(now), 0.0);
+        assert!(!spread.is_animating(now));
+    }
+
+    #[test]
+    fn movement_expands_and_recovers() {
+        let mut spread = spread();
+        let start = Instant::now();
+        spread.handle(InputEvent::Pressed(W), start);
+        assert!(spread.is_moving());
+        assert_near(spread.spread(ms(start, 50)), 2.0);
+        assert!(spread.is_animating(ms(start, 50)));
+        assert_near(spread.spread(ms(start, 100)), 4.0);
+        assert_near(spread.spread(ms(start, 1000)), 4.0);
+        assert!(!spread.is_animating(ms(start, 1000)));
+
+        spread.handle(InputEvent::Released(W), ms(start, 1000));
+        assert!(!spread.is_moving());
+        assert_near(spread.spread(ms(start, 1200)), 2.0);
+        assert_near(spread.spread(ms(start, 1400)), 0.0);
+        assert!(!spread.is_animating(ms(start, 1400)));
+    }
+
+    #[test]
+    fn second_movement_button_keeps_moving() {
+        let mut spread = spread();
+        let start = Instant::now();
+        spread.handle(InputEvent::Pressed(W), start);
+        spread.handle(InputEvent::Pressed(D), ms(start, 200));
+        spread.handle(InputEvent::Released(W), ms(start, 300));
+        assert!(spread.is_moving());
+        assert_near(spread.spread(ms(start, 500)), 4.0);
+    }
+
+    #[test]
+    fn shots_stack() {
+        let mut spread = spread();
+        let start = Instant::now();
+        spread.handle(InputEvent::Pressed(LMB), start);
+        assert_near(spread.spread(start), 3.0);
+        //half recovered (400ms for 3px)
+        spread.handle(InputEvent::Pressed(LMB), ms(start, 200));
+        assert_near(spread.spread(ms(start, 200)), 1.5 + 3.0);
+        //releasing the fire button isn't movement
+        spread.handle(InputEvent::Released(LMB), ms(start, 250));
+        assert_near(spread.spread(ms(start, 400)), 2.25);
+        assert_near(spread.spread(ms(start, 600)), 0.0);
+    }
+
+    #[test]
+    fn shots_are_clamped_to_the_maximum() {
+        let mut spread = spread();
+        let start = Instant::now();
+        for shot in 0..5 {
+            spread.handle(InputEvent::Pressed(LMB), ms(start, shot));
+        }
+        assert_near(spread.spread(ms(start, 4)), 8.0);
+    }
+
+    #[test]
+    fn recovers_from_firing_to_the_movement_spread() {
+        let mut spread = spread();
+        let start = Instant::now();
+        spread.handle(InputEvent::Pressed(W), start);
+        spread.handle(InputEvent::Pressed(LMB), ms(start, 100));
+        assert_near(spread.spread(ms(start, 100)), 7.0);
+        //recovery goes from 7 to 4 (the gap of a moving crosshair)
+        assert_near(spread.spread(ms(start, 300)), 5.5);
+        assert_near(spread.spread(ms(start, 500)), 4.0);
+    }
+
+    #[test]
+    fn movement_spread_is_clamped() {
+        let mut spread = spread();
+        spread.settings.max_spread = 2.0;
+        let start = Instant::now();
+        spread.handle(InputEvent::Pressed(W), start);
+        assert_near(spread.spread(ms(start, 500)), 2.0);
+    }
+}
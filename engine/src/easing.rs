@@ -0,0 +1,47 @@
+use std::fmt;
+
+//shape of a transition over normalized time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    //starts slow
+    EaseIn,
+    //ends slow
+    EaseOut,
+    //slow at both ends
+    Smooth
+}
+impl Easing {
+    pub const ALL: [Self; 4] = [Self::Linear, Self::EaseIn, Self::EaseOut, Self::Smooth];
+
+    //progress (0..=1) at normalized time `t` (clamped to 0..=1)
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::Smooth => t * t * (3.0 - 2.0 * t)
+        }
+    }
+
+    //name used in files
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::EaseIn => "ease-in",
+            Self::EaseOut => "ease-out",
+            Self::Smooth => "smooth"
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|easing| easing.name() == name.trim())
+    }
+}
+impl fmt::Display for Easing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
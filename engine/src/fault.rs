@@ -0,0 +1,74 @@
+use std::fmt;
+use ash::vk;
+
+//failure while creating the renderer or rendering a frame
+//(the renderer is unusable afterwards and has to be rebuilt)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderError {
+    //the gpu / driver was reset (e.g. a game crashed the driver)
+    DeviceLost,
+    //the window system surface went away
+    SurfaceLost,
+    //any other vulkan error
+    Vulkan(vk::Result)
+}
+impl From<vk::Result> for RenderError {
+    fn from(result: vk::Result) -> Self {
+        match result {
+            vk::Result::ERROR_DEVICE_LOST => Self::DeviceLost,
+            vk::Result::ERROR_SURFACE_LOST_KHR => Self::SurfaceLost,
+            result => Self::Vulkan(result)
+        }
+    }
+}
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeviceLost => write!(f, "device lost"),
+            Self::SurfaceLost => write!(f, "surface lost"),
+            Self::Vulkan(result) => write!(f, "vulkan error: {result}")
+        }
+    }
+}
+impl std::error::Error for RenderError {}
+
+//error a draw call can be made to fail with (fault injection)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    DeviceLost,
+    SurfaceLost
+}
+impl From<Fault> for RenderError {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => Self::DeviceLost,
+            Fault::SurfaceLost => Self::SurfaceLost
+        }
+    }
+}
+
+//fault that is raised once the renderer submitted `after_frames` frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledFault {
+    pub fault: Fault,
+    pub after_frames: u64
+}
+impl ScheduledFault {
+    //"device-lost" or "surface-lost", optionally followed by "@<frames>"
+    pub fn parse(value: &str) -> Option<Self> {
+        let (fault, after_frames) = match value.trim().split_once('@') {
+            Some((fault, after_frames)) => (fault, after_frames.trim().parse().ok()?),
+            None => (value.trim(), 0)
+        };
+        let fault = match fault.to_ascii_lowercase().replace('_', "-").as_str() {
+            "device-lost" => Fault::DeviceLost,
+            "surface-lost" => Fault::SurfaceLost,
+            _ => return None
+        };
+        Some(Self { fault, after_frames })
+    }
+
+    pub(crate) fn is_due(&self, submitted_frames: u64) -> bool {
+        submitted_frames >= self.after_frames
+    }
+}
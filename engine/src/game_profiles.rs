@@ -0,0 +1,315 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::active_window::X11ActiveWindow;
+use crate::helper;
+
+const REGISTRY_FILE_NAME: &str = "games.txt";
+//the kernel truncates /proc/<pid>/comm to 15 bytes
+const COMM_LEN: usize = 15;
+
+//running process as seen by a ProcessSource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    //executable name (/proc/<pid>/comm)
+    pub comm: String,
+    //arguments joined by spaces (/proc/<pid>/cmdline)
+    pub cmdline: String
+}
+
+//where the profile watcher learns about processes
+pub trait ProcessSource {
+    fn processes(&mut self) -> Vec<ProcessInfo>;
+    //process owning the focused window (none if unknown)
+    fn focused_pid(&mut self) -> Option<u32>;
+}
+
+//fixed processes set by the caller (tests, dry runs)
+#[derive(Debug, Clone, Default)]
+pub struct FakeProcesses {
+    pub processes: Vec<ProcessInfo>,
+    pub focused_pid: Option<u32>
+}
+impl ProcessSource for FakeProcesses {
+    fn processes(&mut self) -> Vec<ProcessInfo> {
+        self.processes.clone()
+    }
+
+    fn focused_pid(&mut self) -> Option<u32> {
+        self.focused_pid
+    }
+}
+
+//processes from a procfs mount, focus from x11 (if available)
+pub struct ProcFs {
+    root: PathBuf,
+    active_window: Option<X11ActiveWindow>
+}
+impl ProcFs {
+    //`root` is /proc or a directory laid out like it (<pid>/comm, <pid>/cmdline),
+    //nothing is focused
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, active_window: None }
+    }
+
+    //the running system, focus through _NET_ACTIVE_WINDOW
+    pub fn system() -> Self {
+        let active_window = X11ActiveWindow::connect()
+            .map_err(|err| println!("game focus unavailable: {err}"))
+            .ok();
+        Self { root: PathBuf::from("/proc"), active_window }
+    }
+
+    //none if the process doesn't exist (anymore)
+    pub fn process(&self, pid: u32) -> Option<ProcessInfo> {
+        Self::read_process(&self.root.join(pid.to_string()), pid)
+    }
+
+    fn read_process(dir: &Path, pid: u32) -> Option<ProcessInfo> {
+        let comm = fs::read_to_string(dir.join("comm")).ok()?;
+        //kernel threads have an empty cmdline
+        let cmdline = fs::read(dir.join("cmdline")).unwrap_or_default();
+        let cmdline = String::from_utf8_lossy(&cmdline)
+            .split('\0')
+            .filter(|arg| !arg.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(ProcessInfo { pid, comm: comm.trim_end().to_string(), cmdline })
+    }
+}
+impl ProcessSource for ProcFs {
+    fn processes(&mut self) -> Vec<ProcessInfo> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Vec::new()
+        };
+        //processes may exit while they are read
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let pid = entry.file_name().to_str()?.parse().ok()?;
+                Self::read_process(&entry.path(), pid)
+            })
+            .collect()
+    }
+
+    fn focused_pid(&mut self) -> Option<u32> {
+        self.active_window.as_ref()?.pid()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessMatch {
+    //executable name (compared like the kernel truncates it)
+    Comm(String),
+    //substring of the command line (e.g. a windows .exe under wine / proton)
+    Cmdline(String)
+}
+impl ProcessMatch {
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        match self {
+            Self::Comm(name) => process.comm == name[..name.floor_char_boundary(COMM_LEN)],
+            Self::Cmdline(part) => process.cmdline.contains(part.as_str())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRule {
+    pub matcher: ProcessMatch,
+    //preset / profile name (see PresetLibrary)
+    pub profile: String
+}
+
+//which profile belongs to which game
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileRegistry {
+    //earlier rules win when several games run
+    pub rules: Vec<GameRule>,
+    //applies while no game runs
+    pub default_profile: Option<String>
+}
+impl ProfileRegistry {
+    //"games.txt" in the user's config directory
+    pub fn default_path() -> Option<PathBuf> {
+        helper::config_dir().map(|dir| dir.join(REGISTRY_FILE_NAME))
+    }
+
+    //"comm <name> <profile>", "cmdline <text> <profile>" and "default <profile>" lines
+    //(the cmdline text can't contain whitespace)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut registry = Self::default();
+        for (index, line) in text.lines().enumerate().map(|(index, line)| (index + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["default", profile] => registry.default_profile = Some(profile.to_string()),
+                ["comm", name, profile] => registry.rules.push(GameRule { matcher: ProcessMatch::Comm(name.to_string()), profile: profile.to_string() }),
+                ["cmdline", part, profile] => registry.rules.push(GameRule { matcher: ProcessMatch::Cmdline(part.to_string()), profile: profile.to_string() }),
+                _ => return Err(format!("line {index}: expected \"comm|cmdline <pattern> <profile>\" or \"default <profile>\": {line:?}"))
+            }
+        }
+        Ok(registry)
+    }
+
+    //none if there is no registry (errors are printed)
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                println!("failed reading game profiles {}: {err}", path.display());
+                return None
+            }
+        };
+        match Self::parse(&text) {
+            Ok(registry) => Some(registry),
+            Err(err) => {
+                println!("ignoring game profiles {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    //the process matches a rule
+    pub fn is_game(&self, process: &ProcessInfo) -> bool {
+        self.rules.iter().any(|rule| rule.matcher.matches(process))
+    }
+
+    //the focused game's profile, else the one of the first rule with a
+    //running game, else the default
+    pub fn select(&self, processes: &[ProcessInfo], focused_pid: Option<u32>) -> Option<&str> {
+        let rule_for = |process: &ProcessInfo| self.rules.iter().find(|rule| rule.matcher.matches(process));
+        let focused = focused_pid
+            .and_then(|pid| processes.iter().find(|process| process.pid == pid))
+            .and_then(rule_for);
+        let running = || self.rules.iter().find(|rule| processes.iter().any(|process| rule.matcher.matches(process)));
+        focused
+            .or_else(running)
+            .map(|rule| rule.profile.as_str())
+            .or(self.default_profile.as_deref())
+    }
+}
+
+//polls a process source and reports when another profile should be active
+pub struct ProfileWatcher<S: ProcessSource = ProcFs> {
+    registry: ProfileRegistry,
+    source: S,
+    //profile selected by the last check
+    active: Option<String>,
+    next_poll: Instant
+}
+impl<S: ProcessSource> ProfileWatcher<S> {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(registry: ProfileRegistry, source: S) -> Self {
+        //the first poll happens right away
+        Self { registry, source, active: None, next_poll: Instant::now() }
+    }
+
+    //time the next poll is due (wake-up time for the event loop)
+    pub fn next_poll(&self) -> Instant {
+        self.next_poll
+    }
+
+    //rate limited check (nothing if polled too early)
+    pub fn poll(&mut self) -> Option<String> {
+        let now = Instant::now();
+        if now < self.next_poll {
+            return None
+        }
+        self.next_poll = now + Self::POLL_INTERVAL;
+        self.check()
+    }
+
+    //profile to switch to, if the selection changed since the last check
+    pub fn check(&mut self) -> Option<String> {
+        let processes = self.source.processes();
+        let focused_pid = self.source.focused_pid();
+        let selected = self.registry.select(&processes, focused_pid).map(str::to_string);
+        if selected.is_none() || selected == self.active {
+            return None
+        }
+        self.active = selected.clone();
+        selected
+    }
+
+    pub fn registry(&self) -> &ProfileRegistry {
+        &self.registry
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY: &str = "
+        comm cs2 counter-strike
+        cmdline Overwatch.exe overwatch
+        comm hl2_linux half-life
+        default desktop
+    ";
+
+    fn process(pid: u32, comm: &str, cmdline: &str) -> ProcessInfo {
+        ProcessInfo { pid, comm: comm.to_string(), cmdline: cmdline.to_string() }
+    }
+
+    fn watcher(processes: Vec<ProcessInfo>, focused_pid: Option<u32>) -> ProfileWatcher<FakeProcesses> {
+        ProfileWatcher::new(ProfileRegistry::parse(REGISTRY).unwrap(), FakeProcesses { processes, focused_pid })
+    }
+
+    #[test]
+    fn focused_game_wins_over_running_one() {
+        let processes = vec![process(1, "cs2", "cs2 -steam"), process(2, "hl2_linux", "hl2_linux -game tf")];
+        assert_eq!(watcher(processes.clone(), Some(2)).check().as_deref(), Some("half-life"));
+        //focus on something that isn't a game falls back to the running ones
+        assert_eq!(watcher(processes, Some(3)).check().as_deref(), Some("counter-strike"));
+    }
+
+    #[test]
+    fn first_rule_wins() {
+        //the process order doesn't matter, the rule order does
+        let processes = vec![process(1, "hl2_linux", ""), process(2, "wine64-preload", "Z:\\Overwatch.exe"), process(3, "cs2", "")];
+        assert_eq!(watcher(processes, None).check().as_deref(), Some("counter-strike"));
+    }
+
+    #[test]
+    fn falls_back_to_the_default() {
+        assert_eq!(watcher(vec![process(1, "bash", "bash")], None).check().as_deref(), Some("desktop"));
+        let registry = ProfileRegistry::parse("comm cs2 counter-strike").unwrap();
+        assert_eq!(registry.select(&[], None), None);
+    }
+
+    #[test]
+    fn matches_truncated_comm_names() {
+        let registry = ProfileRegistry::parse("comm SkyrimSpecialEdition skyrim").unwrap();
+        //the kernel keeps 15 bytes
+        assert_eq!(registry.select(&[process(1, "SkyrimSpecialEd", "")], None), Some("skyrim"));
+        assert_eq!(registry.select(&[process(1, "SkyrimSpecialEdition", "")], None), None);
+    }
+
+    #[test]
+    fn check_reports_changes_only() {
+        let mut watcher = watcher(vec![process(1, "cs2", "")], None);
+        assert_eq!(watcher.check().as_deref(), Some("counter-strike"));
+        assert_eq!(watcher.check(), None);
+        watcher.source_mut().processes.clear();
+        assert_eq!(watcher.check().as_deref(), Some("desktop"));
+        assert_eq!(watcher.check(), None);
+        watcher.source_mut().processes.push(process(2, "cs2", ""));
+        assert_eq!(watcher.check().as_deref(), Some("counter-strike"));
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let err = ProfileRegistry::parse("default desktop\ncomm cs2").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+    }
+}
@@ -1,5 +1,42 @@
+use std::env;
+use std::path::PathBuf;
+
 pub fn usize_into_u32(value: usize) -> u32 {
     value
         .try_into()
         .expect("failed converting usize into u32")
-}
\ No newline at end of file
+}
+
+//per-user directory for custicle: $<xdg_var>, ~/<home_fallback>
+//or %LOCALAPPDATA% on windows
+fn user_dir(xdg_var: &str, home_fallback: &str) -> Option<PathBuf> {
+    let non_empty = |key: &str| env::var_os(key).filter(|value| !value.is_empty());
+
+    let base = if cfg!(windows) {
+        non_empty("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        non_empty(xdg_var)
+            .map(PathBuf::from)
+            .or_else(|| non_empty("HOME").map(|home| PathBuf::from(home).join(home_fallback)))
+    };
+
+    base.map(|base| base.join("custicle"))
+}
+
+//per-user cache directory for custicle
+//($XDG_CACHE_HOME, ~/.cache or %LOCALAPPDATA%)
+pub fn cache_dir() -> Option<PathBuf> {
+    user_dir("XDG_CACHE_HOME", ".cache")
+}
+
+//per-user state directory for custicle (edit session)
+//($XDG_STATE_HOME, ~/.local/state or %LOCALAPPDATA%)
+pub fn state_dir() -> Option<PathBuf> {
+    user_dir("XDG_STATE_HOME", ".local/state")
+}
+
+//per-user config directory for custicle (profiles)
+//($XDG_CONFIG_HOME, ~/.config or %LOCALAPPDATA%)
+pub fn config_dir() -> Option<PathBuf> {
+    user_dir("XDG_CONFIG_HOME", ".config")
+}
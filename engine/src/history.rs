@@ -0,0 +1,450 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::crosshair::Crosshair;
+use crate::helper;
+use crate::property::{self, AnimationSettings, Property, PropertyValue};
+
+//most undo steps kept (older ones are dropped)
+pub const HISTORY_CAPACITY: usize = 100;
+
+const SESSION_FILE_NAME: &str = "edit_session.txt";
+
+//starts an animation command in the saved history
+const ANIMATION_KEYWORD: &str = "animation";
+
+//a single change of the crosshair
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditCommand {
+    Property { property: Property, before: PropertyValue, after: PropertyValue },
+    //tracks and rate, replaced as a whole
+    Animation { before: AnimationSettings, after: AnimationSettings }
+}
+impl EditCommand {
+    //one command per property that differs, plus one
+    //if the animation differs
+    pub fn diff(before: &Crosshair, after: &Crosshair) -> Vec<Self> {
+        let properties = Property::ALL
+            .into_iter()
+            .map(|property| Self::Property { property, before: property.get(before), after: property.get(after) });
+        let animation = Self::Animation { before: AnimationSettings::get(before), after: AnimationSettings::get(after) };
+        properties
+            .chain(std::iter::once(animation))
+            .filter(|command| !command.is_noop())
+            .collect()
+    }
+
+    fn is_noop(&self) -> bool {
+        match self {
+            Self::Property { before, after, .. } => before == after,
+            Self::Animation { before, after } => before == after
+        }
+    }
+
+    //both change the same part of the crosshair
+    fn same_target(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Property { property, .. }, Self::Property { property: other, .. }) => property == other,
+            (Self::Animation { .. }, Self::Animation { .. }) => true,
+            _ => false
+        }
+    }
+
+    //takes over the result of a later command of the same target
+    fn absorb(&mut self, later: Self) {
+        match (self, later) {
+            (Self::Property { after, .. }, Self::Property { after: later, .. }) => *after = later,
+            (Self::Animation { after, .. }, Self::Animation { after: later, .. }) => *after = later,
+            _ => unreachable!("absorbed a command of another target")
+        }
+    }
+
+    fn revert(&self, crosshair: &mut Crosshair) {
+        match self {
+            Self::Property { property, before, .. } => property.set(crosshair, *before),
+            Self::Animation { before, .. } => before.set(crosshair)
+        }
+    }
+
+    fn apply(&self, crosshair: &mut Crosshair) {
+        match self {
+            Self::Property { property, after, .. } => property.set(crosshair, *after),
+            Self::Animation { after, .. } => after.set(crosshair)
+        }
+    }
+
+    //"<property> <before> <after>", or "animation" followed by "before <line>"
+    //and "after <line>" lines (see AnimationSettings::write)
+    fn write(&self, text: &mut String) {
+        match self {
+            Self::Property { property, before, after } => text.push_str(&format!("{} {before} {after}\n", property.name())),
+            Self::Animation { before, after } => {
+                text.push_str(ANIMATION_KEYWORD);
+                text.push('\n');
+                for (prefix, animation) in [("before", before), ("after", after)] {
+                    for line in animation.write().lines() {
+                        text.push_str(&format!("{prefix} {line}\n"));
+                    }
+                }
+            }
+        }
+    }
+
+    //adds a line written by write to the step
+    fn read_line(step: &mut EditStep, line: &str) -> Result<(), String> {
+        if line == ANIMATION_KEYWORD {
+            step.push(Self::Animation { before: AnimationSettings::default(), after: AnimationSettings::default() });
+            return Ok(())
+        }
+        if let Some((prefix @ ("before" | "after"), rest)) = line.split_once(char::is_whitespace) {
+            let Some(Self::Animation { before, after }) = step.last_mut() else {
+                return Err(format!("{prefix} line outside of an animation command"))
+            };
+            return if prefix == "before" { before.read_line(rest) } else { after.read_line(rest) }
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(before), Some(after), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("expected \"<property> <before> <after>\": {line:?}"))
+        };
+        let property = Property::parse(name).ok_or_else(|| format!("unknown property {name:?}"))?;
+        let value = |value: &str| property.parse_value(value).ok_or_else(|| format!("invalid value for {name}: {value:?}"));
+        step.push(Self::Property { property, before: value(before)?, after: value(after)? });
+        Ok(())
+    }
+}
+
+//commands undone / redone together
+type EditStep = Vec<EditCommand>;
+
+//bounded undo / redo history of crosshair edits
+//
+//changes are recorded as they happen, a step stays open (and absorbs
+//further changes of the same properties) until end_step, so a whole
+//slider drag becomes a single step
+#[derive(Debug, Clone, Default)]
+pub struct EditHistory {
+    //oldest first
+    undo: VecDeque<EditStep>,
+    //next step to redo last
+    redo: Vec<EditStep>,
+    //the newest undo step still absorbs changes
+    open: bool
+}
+impl EditHistory {
+    pub fn record(&mut self, commands: Vec<EditCommand>) {
+        if commands.is_empty() {
+            return
+        }
+        self.redo.clear();
+
+        if self.open && let Some(step) = self.undo.back_mut() {
+            let absorbs = commands.iter().all(|command| step.iter().any(|recorded| recorded.same_target(command)));
+            if absorbs {
+                for command in commands {
+                    let recorded = step.iter_mut().find(|recorded| recorded.same_target(&command)).unwrap();
+                    recorded.absorb(command);
+                }
+                //dragged back to where it started
+                step.retain(|recorded| !recorded.is_noop());
+                if step.is_empty() {
+                    self.undo.pop_back();
+                    self.open = false;
+                }
+                return
+            }
+        }
+
+        self.push_undo(commands);
+        self.open = true;
+    }
+
+    //closes the open step (returns true if there was one)
+    pub fn end_step(&mut self) -> bool {
+        std::mem::take(&mut self.open)
+    }
+
+    fn push_undo(&mut self, step: EditStep) {
+        if self.undo.len() == HISTORY_CAPACITY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(step);
+    }
+
+    //reverts the newest step (false if there is nothing to undo)
+    pub fn undo(&mut self, crosshair: &mut Crosshair) -> bool {
+        self.end_step();
+        let Some(step) = self.undo.pop_back() else {
+            return false
+        };
+        for command in step.iter().rev() {
+            command.revert(crosshair);
+        }
+        self.redo.push(step);
+        true
+    }
+
+    //re-applies the last undone step (false if there is nothing to redo)
+    pub fn redo(&mut self, crosshair: &mut Crosshair) -> bool {
+        self.end_step();
+        let Some(step) = self.redo.pop() else {
+            return false
+        };
+        for command in step.iter() {
+            command.apply(crosshair);
+        }
+        self.push_undo(step);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    //"undo" / "redo" starts a step, followed by its commands (see EditCommand::write)
+    //(undo steps oldest first, redo steps from the bottom of the stack)
+    fn write(&self, text: &mut String) {
+        for (header, step) in self.undo.iter().map(|step| ("undo", step)).chain(self.redo.iter().map(|step| ("redo", step))) {
+            text.push_str(header);
+            text.push('\n');
+            for command in step {
+                command.write(text);
+            }
+        }
+    }
+}
+
+//crosshair and edit history, saved after every step so
+//an editing session survives a restart
+pub struct EditSession {
+    pub crosshair: Crosshair,
+    pub history: EditHistory
+}
+impl EditSession {
+    //file in the user's state directory (none if it can't be determined)
+    pub fn default_path() -> Option<PathBuf> {
+        helper::state_dir().map(|dir| dir.join(SESSION_FILE_NAME))
+    }
+
+    //"[crosshair]" section with "<property> <value>" lines and
+    //a "[history]" section (see EditHistory::write)
+    pub fn to_text(crosshair: &Crosshair, history: &EditHistory) -> String {
+        let mut text = String::from("[crosshair]\n");
+        text.push_str(&property::write_properties(crosshair));
+        text.push_str("[history]\n");
+        history.write(&mut text);
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut session = Self { crosshair: Crosshair::default(), history: EditHistory::default() };
+        let mut section = "";
+        //stack the current history step belongs to
+        let mut redo = false;
+
+        for (index, line) in text.lines().enumerate().map(|(index, line)| (index + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let result = match (section, line) {
+                (_, "[crosshair]" | "[history]") => {
+                    section = line;
+                    Ok(())
+                },
+                ("[crosshair]", line) => property::read_property(&mut session.crosshair, line),
+                ("[history]", "undo") => {
+                    redo = false;
+                    session.history.push_undo(Vec::new());
+                    Ok(())
+                },
+                ("[history]", "redo") => {
+                    redo = true;
+                    session.history.redo.push(Vec::new());
+                    Ok(())
+                },
+                ("[history]", line) => {
+                    let step = if redo { session.history.redo.last_mut() } else { session.history.undo.back_mut() };
+                    step.ok_or_else(|| "command outside of a step".to_string()).and_then(|step| EditCommand::read_line(step, line))
+                },
+                _ => Err(format!("unexpected {line:?}"))
+            };
+            result.map_err(|err| format!("line {index}: {err}"))?;
+        }
+
+        session.history.undo.retain(|step| !step.is_empty());
+        session.history.redo.retain(|step| !step.is_empty());
+        Ok(session)
+    }
+
+    //none if there is no saved session (errors are printed)
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                println!("failed reading edit session {}: {err}", path.display());
+                return None
+            }
+        };
+        match Self::parse(&text) {
+            Ok(session) => Some(session),
+            Err(err) => {
+                println!("discarding edit session {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    //write to temp file + rename (an interrupted save keeps the old session)
+    pub fn save(path: &Path, crosshair: &Crosshair, history: &EditHistory) {
+        let tmp_path = path.with_extension("tmp");
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp_path, Self::to_text(crosshair, history)))
+            .and_then(|_| fs::rename(&tmp_path, path));
+        if let Err(err) = result {
+            println!("failed saving edit session {}: {err}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Track;
+    use crate::crosshair::Color;
+
+    fn animated() -> Crosshair {
+        let mut crosshair = Crosshair { gap: 9.0, animation_rate: Some(30.0), ..Default::default() };
+        crosshair.animation.push(Track::parse("opacity repeat 0=1 1=0.5:smooth").unwrap());
+        crosshair
+    }
+
+    fn gap(value: f32) -> PropertyValue {
+        Property::Gap.get(&Crosshair { gap: value, ..Default::default() })
+    }
+
+    //records setting the gap of `crosshair`
+    fn set_gap(history: &mut EditHistory, crosshair: &mut Crosshair, value: f32) {
+        let before = crosshair.clone();
+        crosshair.gap = value;
+        history.record(EditCommand::diff(&before, crosshair));
+    }
+
+    #[test]
+    fn drag_is_one_step() {
+        let mut crosshair = Crosshair { gap: 1.0, ..Default::default() };
+        let mut history = EditHistory::default();
+        for value in [2.0, 3.0, 4.0] {
+            set_gap(&mut history, &mut crosshair, value);
+        }
+        assert!(history.end_step());
+
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0], vec![EditCommand::Property { property: Property::Gap, before: gap(1.0), after: gap(4.0) }]);
+        assert!(history.undo(&mut crosshair));
+        assert_eq!(crosshair.gap, 1.0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn drag_back_to_start_leaves_no_step() {
+        let mut crosshair = Crosshair { gap: 1.0, ..Default::default() };
+        let mut history = EditHistory::default();
+        set_gap(&mut history, &mut crosshair, 2.0);
+        set_gap(&mut history, &mut crosshair, 1.0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn oldest_steps_are_evicted() {
+        let mut crosshair = Crosshair::default();
+        let mut history = EditHistory::default();
+        for step in 1..=HISTORY_CAPACITY + 5 {
+            set_gap(&mut history, &mut crosshair, step as f32);
+            history.end_step();
+        }
+
+        assert_eq!(history.undo.len(), HISTORY_CAPACITY);
+        while history.undo(&mut crosshair) {}
+        //the first 5 steps are gone
+        assert_eq!(crosshair.gap, 5.0);
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut crosshair = Crosshair::default();
+        let mut history = EditHistory::default();
+        set_gap(&mut history, &mut crosshair, 2.0);
+        history.end_step();
+        assert!(history.undo(&mut crosshair));
+        assert!(history.can_redo());
+
+        set_gap(&mut history, &mut crosshair, 3.0);
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut crosshair));
+        assert_eq!(crosshair.gap, 3.0);
+    }
+
+    #[test]
+    fn session_round_trip() {
+        let mut crosshair = Crosshair::default();
+        let mut history = EditHistory::default();
+        set_gap(&mut history, &mut crosshair, 2.0);
+        history.end_step();
+        let before = crosshair.clone();
+        crosshair.rotation = 45.0;
+        crosshair.color = Color { r: 0.5, g: 0.0, b: 1.0, a: 1.0 };
+        history.record(EditCommand::diff(&before, &crosshair));
+        history.end_step();
+        assert!(history.undo(&mut crosshair));
+
+        let session = EditSession::parse(&EditSession::to_text(&crosshair, &history)).unwrap();
+        assert_eq!(session.crosshair, crosshair);
+        assert_eq!(session.history.undo, history.undo);
+        assert_eq!(session.history.redo, history.redo);
+    }
+
+    #[test]
+    fn malformed_session_is_rejected() {
+        assert!(EditSession::parse("[history]\nundo\ngap 1").err().unwrap().starts_with("line 3:"));
+        assert!(EditSession::parse("[history]\nundo\nwidth 1 2").is_err());
+        assert!(EditSession::parse("[history]\nundo\ngap 1 wide").is_err());
+        //command before its step
+        assert!(EditSession::parse("[history]\ngap 1 2").is_err());
+        assert!(EditSession::parse("[history]\nundo\nbefore animation_rate 30").is_err());
+    }
+
+    #[test]
+    fn undo_restores_replaced_animation() {
+        let mut crosshair = Crosshair::default();
+        let mut history = EditHistory::default();
+        //a profile switch / preset click replaces the whole crosshair
+        let before = std::mem::replace(&mut crosshair, animated());
+        history.record(EditCommand::diff(&before, &crosshair));
+        history.end_step();
+
+        assert!(history.undo(&mut crosshair));
+        assert_eq!(crosshair, Crosshair::default());
+        assert!(history.redo(&mut crosshair));
+        assert_eq!(crosshair, animated());
+    }
+
+    #[test]
+    fn animation_commands_survive_a_restart() {
+        let mut history = EditHistory::default();
+        history.record(EditCommand::diff(&Crosshair::default(), &animated()));
+        history.end_step();
+
+        let mut session = EditSession::parse(&EditSession::to_text(&animated(), &history)).unwrap();
+        assert_eq!(session.crosshair, animated());
+        assert!(session.history.undo(&mut session.crosshair));
+        assert_eq!(session.crosshair, Crosshair::default());
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::shader_compiler::ShaderStage;
+
+//watches a directory of glsl sources (*.vert / *.frag) by polling
+//their modification times (no platform specific watcher needed)
+pub(crate) struct ShaderWatcher {
+    dir: PathBuf,
+    //last seen modification time per shader source
+    modified: HashMap<PathBuf, SystemTime>,
+    //time of the last directory scan
+    last_poll: Instant
+}
+impl ShaderWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        println!("watching shaders in {}", dir.display());
+        let modified = Self::scan(&dir);
+        Self { dir, modified, last_poll: Instant::now() }
+    }
+
+    fn scan(dir: &Path) -> HashMap<PathBuf, SystemTime> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return HashMap::new()
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| ShaderStage::from_path(path).is_some())
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+                Some((path, modified))
+            })
+            .collect()
+    }
+
+    //time the next poll is due (wake-up time for the event loop)
+    pub(crate) fn next_poll(&self) -> Instant {
+        self.last_poll + Self::POLL_INTERVAL
+    }
+
+    //returns the sources that were added or modified since the last call
+    //(rate limited, returns nothing if polled too early)
+    pub(crate) fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < Self::POLL_INTERVAL {
+            return Vec::new()
+        }
+        self.last_poll = Instant::now();
+
+        let current = Self::scan(&self.dir);
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, modified)| self.modified.get(*path) != Some(*modified))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.sort();
+
+        self.modified = current;
+        changed
+    }
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::thread;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{ConnectionExt, GrabMode, Keycode, ModMask};
+
+//modifier keys that have to be held together with the hotkey
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HotkeyModifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_key: bool
+}
+
+//global key combination, e.g. "ctrl+shift+f12"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: HotkeyModifiers,
+    //x11 keysym of the (unshifted) key
+    pub keysym: u32
+}
+impl Default for Hotkey {
+    fn default() -> Self {
+        Self {
+            modifiers: HotkeyModifiers { ctrl: true, shift: true, ..Default::default() },
+            keysym: keysym_from_name("f12").unwrap()
+        }
+    }
+}
+impl Hotkey {
+    //modifiers and a key joined by '+': ctrl, shift, alt, super
+    //and a letter, digit, f1-f12 or a named key (insert, home, ...)
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut modifiers = HotkeyModifiers::default();
+        let mut keysym = None;
+        for part in value.split('+').map(|part| part.trim().to_ascii_lowercase()) {
+            match part.as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                "super" | "win" | "meta" => modifiers.super_key = true,
+                key => {
+                    if keysym.is_some() {
+                        return Err(format!("more than one key in {value:?}"))
+                    }
+                    keysym = Some(keysym_from_name(key).ok_or_else(|| format!("unknown key {key:?}"))?);
+                }
+            }
+        }
+        let keysym = keysym.ok_or_else(|| format!("no key in {value:?}"))?;
+        Ok(Self { modifiers, keysym })
+    }
+
+    fn mod_mask(&self) -> ModMask {
+        let mut mod_mask = ModMask::from(0u16);
+        if self.modifiers.ctrl {
+            mod_mask |= ModMask::CONTROL;
+        }
+        if self.modifiers.shift {
+            mod_mask |= ModMask::SHIFT;
+        }
+        if self.modifiers.alt {
+            mod_mask |= ModMask::M1;
+        }
+        if self.modifiers.super_key {
+            mod_mask |= ModMask::M4;
+        }
+        mod_mask
+    }
+}
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "ctrl"),
+            (self.modifiers.shift, "shift"),
+            (self.modifiers.alt, "alt"),
+            (self.modifiers.super_key, "super")
+        ];
+        for (_, name) in modifiers.iter().filter(|(held, _)| *held) {
+            write!(f, "{name}+")?;
+        }
+        write!(f, "{}", keysym_name(self.keysym))
+    }
+}
+
+//named keys (keysymdef.h), letters and digits map to their ascii code
+const NAMED_KEYSYMS: [(&str, u32); 14] = [
+    ("escape", 0xff1b),
+    ("tab", 0xff09),
+    ("pause", 0xff13),
+    ("scrolllock", 0xff14),
+    ("home", 0xff50),
+    ("left", 0xff51),
+    ("up", 0xff52),
+    ("right", 0xff53),
+    ("down", 0xff54),
+    ("pageup", 0xff55),
+    ("pagedown", 0xff56),
+    ("end", 0xff57),
+    ("insert", 0xff63),
+    ("delete", 0xffff)
+];
+//keysym of F1, F2..F12 follow
+const KEYSYM_F1: u32 = 0xffbe;
+
+pub(crate) fn keysym_from_name(name: &str) -> Option<u32> {
+    if let Some(&(_, keysym)) = NAMED_KEYSYMS.iter().find(|(named, _)| *named == name) {
+        return Some(keysym)
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|number| number.parse::<u32>().ok()) {
+        return (1..=12).contains(&number).then_some(KEYSYM_F1 + number - 1)
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) if char.is_ascii_lowercase() || char.is_ascii_digit() => Some(char as u32),
+        _ => None
+    }
+}
+
+pub(crate) fn keysym_name(keysym: u32) -> String {
+    if let Some((name, _)) = NAMED_KEYSYMS.iter().find(|(_, named)| *named == keysym) {
+        return name.to_string()
+    }
+    if (KEYSYM_F1..KEYSYM_F1 + 12).contains(&keysym) {
+        return format!("f{}", keysym - KEYSYM_F1 + 1)
+    }
+    char::from_u32(keysym).map_or_else(|| format!("{keysym:#x}"), String::from)
+}
+
+//grabs the hotkey on the x11 root window (works while any other window,
+//e.g. the game, has focus) and calls `on_press` from a background thread
+//
+//fails if there is no x11 display (wayland only) or another client
+//already grabbed the combination
+pub(crate) fn listen_x11(hotkey: Hotkey, on_press: impl Fn() + Send + 'static) -> Result<(), String> {
+    let (connection, screen_num) = x11rb::connect(None).map_err(|err| format!("no x11 display: {err}"))?;
+    let root = connection.setup().roots[screen_num].root;
+    let keycode = find_keycode(&connection, hotkey.keysym)?
+        .ok_or_else(|| format!("{} is not on the keyboard", keysym_name(hotkey.keysym)))?;
+
+    //the grab only matches exact modifier states: also grab the
+    //variants with caps lock (Lock) and num lock (usually M2) active
+    let mod_mask = hotkey.mod_mask();
+    for ignored in [0u16, ModMask::LOCK.into(), ModMask::M2.into(), u16::from(ModMask::LOCK) | u16::from(ModMask::M2)] {
+        connection
+            .grab_key(false, root, mod_mask | ModMask::from(ignored), keycode, GrabMode::ASYNC, GrabMode::ASYNC)
+            .map_err(|err| err.to_string())?
+            .check()
+            .map_err(|err| format!("failed grabbing {hotkey} (used by another application?): {err:?}"))?;
+    }
+
+    thread::Builder::new()
+        .name("x11 hotkey".to_string())
+        .spawn(move || {
+            //auto-repeat sends release + press with the same timestamp
+            let mut last_release = None;
+            loop {
+                match connection.wait_for_event() {
+                    Ok(Event::KeyPress(event)) if event.detail == keycode => {
+                        if last_release != Some(event.time) {
+                            on_press();
+                        }
+                    },
+                    Ok(Event::KeyRelease(event)) if event.detail == keycode => last_release = Some(event.time),
+                    Ok(_) => (),
+                    Err(err) => {
+                        println!("x11 hotkey connection failed: {err}");
+                        return
+                    }
+                }
+            }
+        })
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+//first keycode that produces `keysym` without modifiers
+pub(crate) fn find_keycode(connection: &impl Connection, keysym: u32) -> Result<Option<Keycode>, String> {
+    let setup = connection.setup();
+    let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+    let mapping = connection
+        .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+        .map_err(|err| err.to_string())?
+        .reply()
+        .map_err(|err| err.to_string())?;
+    let keysyms_per_keycode = usize::from(mapping.keysyms_per_keycode);
+    if keysyms_per_keycode == 0 {
+        return Ok(None)
+    }
+    Ok(mapping.keysyms
+        .chunks(keysyms_per_keycode)
+        .position(|keysyms| keysyms[0] == keysym)
+        .map(|index| min_keycode + index as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use x11rb::protocol::xproto::{KEY_PRESS_EVENT, KEY_RELEASE_EVENT};
+    use x11rb::protocol::xtest::ConnectionExt as _;
+    use x11rb::CURRENT_TIME;
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        let hotkey = Hotkey::parse("ctrl+shift+f12").unwrap();
+        assert_eq!(hotkey, Hotkey::default());
+        assert_eq!(hotkey.to_string(), "ctrl+shift+f12");
+
+        let hotkey = Hotkey::parse(" Super + Alt + Insert ").unwrap();
+        assert_eq!(hotkey.modifiers, HotkeyModifiers { alt: true, super_key: true, ..Default::default() });
+        assert_eq!(hotkey.to_string(), "alt+super+insert");
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        assert!(Hotkey::parse("f13").is_err());
+        assert!(Hotkey::parse("f0").is_err());
+        assert!(Hotkey::parse("ctrl+a+b").is_err());
+        assert!(Hotkey::parse("ctrl+shift").is_err());
+        assert!(Hotkey::parse("ctrl+enter").is_err());
+    }
+
+    #[test]
+    fn single_f_is_a_letter() {
+        assert_eq!(keysym_from_name("f"), Some('f' as u32));
+        assert_eq!(Hotkey::parse("alt+f").unwrap().to_string(), "alt+f");
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        let names = (1..=12).map(|number| format!("f{number}"))
+            .chain(NAMED_KEYSYMS.iter().map(|(name, _)| name.to_string()))
+            .chain(('a'..='z').chain('0'..='9').map(String::from));
+        for name in names {
+            assert_eq!(keysym_name(keysym_from_name(&name).unwrap()), name);
+        }
+    }
+
+    //xvfb-run cargo test -p engine -- --ignored hotkey
+    #[test]
+    #[ignore = "needs an x11 display"]
+    fn grabbed_key_is_reported() {
+        let hotkey = Hotkey::parse("f11").unwrap();
+        let (sender, receiver) = mpsc::channel();
+        listen_x11(hotkey, move || {
+            let _ = sender.send(());
+        }).unwrap();
+        //another client can't grab the same combination
+        assert!(listen_x11(hotkey, || ()).is_err());
+
+        let (connection, screen_num) = x11rb::connect(None).unwrap();
+        let root = connection.setup().roots[screen_num].root;
+        let keycode = find_keycode(&connection, hotkey.keysym).unwrap().unwrap();
+        for event in [KEY_PRESS_EVENT, KEY_RELEASE_EVENT] {
+            connection.xtest_fake_input(event, keycode, CURRENT_TIME, root, 0, 0, 0).unwrap().check().unwrap();
+        }
+        receiver.recv_timeout(Duration::from_secs(5)).expect("hotkey press wasn't reported");
+    }
+}
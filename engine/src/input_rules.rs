@@ -0,0 +1,392 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xinput::{self, ConnectionExt as _, XIEventMask};
+
+use crate::dynamic::{DynamicSpread, SpreadSettings};
+use crate::easing::Easing;
+use crate::helper;
+use crate::hotkey;
+
+const RULES_FILE_NAME: &str = "input.txt";
+//x11 button numbers (4 - 7 are the scroll wheel)
+const MOUSE_BUTTONS: [(&str, u8); 5] = [("lmb", 1), ("mmb", 2), ("rmb", 3), ("mouse4", 8), ("mouse5", 9)];
+
+//mouse button or key a rule reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputButton {
+    //x11 button number
+    Mouse(u8),
+    //x11 keysym (same names as the hotkey)
+    Key(u32)
+}
+impl InputButton {
+    //"lmb", "mmb", "rmb", "mouse4", "mouse5" or a key name ("f", "shift"...)
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.trim().to_ascii_lowercase();
+        if let Some(&(_, button)) = MOUSE_BUTTONS.iter().find(|(named, _)| *named == name) {
+            return Ok(Self::Mouse(button))
+        }
+        hotkey::keysym_from_name(&name)
+            .map(Self::Key)
+            .ok_or_else(|| format!("unknown button {name:?} (lmb, mmb, rmb, mouse4, mouse5 or a key)"))
+    }
+}
+impl fmt::Display for InputButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mouse(button) => match MOUSE_BUTTONS.iter().find(|(_, named)| named == button) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "button{button}")
+            },
+            Self::Key(keysym) => write!(f, "{}", hotkey::keysym_name(*keysym))
+        }
+    }
+}
+
+//global input (the click-through overlay receives none itself)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Pressed(InputButton),
+    Released(InputButton)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleAction {
+    //no crosshair while the button is held (the game shows its scope)
+    HideWhileHeld,
+    //every press hides / shows the crosshair
+    Toggle,
+    //this preset / profile replaces the crosshair while the button is held
+    SwapWhileHeld(String)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputRule {
+    pub button: InputButton,
+    pub action: RuleAction
+}
+
+//what the input rules make of the crosshair right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputState {
+    Normal,
+    Hidden,
+    //profile name
+    Swapped(String)
+}
+
+//hide / toggle / swap rules and the input state they react to
+//(driven by InputEvents, so any input source or a test can feed it)
+#[derive(Debug, Clone, Default)]
+pub struct InputRules {
+    rules: Vec<InputRule>,
+    //gap opening on movement / firing (none without move / fire buttons)
+    dynamic: Option<DynamicSpread>,
+    held: HashSet<InputButton>,
+    //a toggle rule switched the crosshair off
+    toggled_off: bool
+}
+impl InputRules {
+    pub fn new(rules: Vec<InputRule>) -> Self {
+        Self { rules, ..Default::default() }
+    }
+
+    //"input.txt" in the user's config directory
+    pub fn default_path() -> Option<PathBuf> {
+        helper::config_dir().map(|dir| dir.join(RULES_FILE_NAME))
+    }
+
+    //"hide <button>", "toggle <button>" and "swap <button> <profile>" lines,
+    //the dynamic crosshair is set up by "move <button>", "fire <button>",
+    //"spread move|fire|max <pixels>" and "expand|recover <seconds> <easing>" lines
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        let mut settings = SpreadSettings::default();
+        let mut move_buttons = Vec::new();
+        let mut fire_buttons = Vec::new();
+        for (index, line) in text.lines().enumerate().map(|(index, line)| (index + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let at_line = |err: String| format!("line {index}: {err}");
+            let (button, action) = match words.as_slice() {
+                ["hide", button] => (button, RuleAction::HideWhileHeld),
+                ["toggle", button] => (button, RuleAction::Toggle),
+                ["swap", button, profile] => (button, RuleAction::SwapWhileHeld(profile.to_string())),
+                ["move", button] => {
+                    move_buttons.push(InputButton::parse(button).map_err(at_line)?);
+                    continue
+                },
+                ["fire", button] => {
+                    fire_buttons.push(InputButton::parse(button).map_err(at_line)?);
+                    continue
+                },
+                ["spread", kind, pixels] => {
+                    let pixels = pixels.parse::<f32>().ok().filter(|pixels| *pixels >= 0.0).ok_or_else(|| at_line(format!("invalid spread {pixels:?}")))?;
+                    match *kind {
+                        "move" => settings.move_spread = pixels,
+                        "fire" => settings.fire_spread = pixels,
+                        "max" => settings.max_spread = pixels,
+                        _ => return Err(at_line(format!("expected \"spread move|fire|max <pixels>\": {line:?}")))
+                    }
+                    continue
+                },
+                [phase @ ("expand" | "recover"), seconds, easing] => {
+                    let time = seconds
+                        .parse::<f32>()
+                        .ok()
+                        .and_then(|seconds| Duration::try_from_secs_f32(seconds).ok())
+                        .ok_or_else(|| at_line(format!("invalid time {seconds:?}")))?;
+                    let easing = Easing::parse(easing).ok_or_else(|| at_line(format!("unknown easing {easing:?} (linear, ease-in, ease-out or smooth)")))?;
+                    if *phase == "expand" {
+                        (settings.expand_time, settings.expand_easing) = (time, easing);
+                    } else {
+                        (settings.recovery_time, settings.recovery_easing) = (time, easing);
+                    }
+                    continue
+                },
+                _ => return Err(at_line(format!("expected \"hide|toggle|move|fire <button>\" or \"swap <button> <profile>\": {line:?}")))
+            };
+            let button = InputButton::parse(button).map_err(at_line)?;
+            rules.push(InputRule { button, action });
+        }
+        let dynamic = (!move_buttons.is_empty() || !fire_buttons.is_empty())
+            .then(|| DynamicSpread::new(settings, move_buttons, fire_buttons));
+        Ok(Self { dynamic, ..Self::new(rules) })
+    }
+
+    //none if there are no rules (errors are printed)
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                println!("failed reading input rules {}: {err}", path.display());
+                return None
+            }
+        };
+        match Self::parse(&text) {
+            Ok(rules) => Some(rules),
+            Err(err) => {
+                println!("ignoring input rules {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    pub fn rules(&self) -> &[InputRule] {
+        &self.rules
+    }
+
+    //no rules and no dynamic crosshair: nothing reacts to input
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.dynamic.is_none()
+    }
+
+    pub fn dynamic(&self) -> Option<&DynamicSpread> {
+        self.dynamic.as_ref()
+    }
+
+    pub fn dynamic_mut(&mut self) -> Option<&mut DynamicSpread> {
+        self.dynamic.as_mut()
+    }
+
+    //buttons the input source has to report
+    pub fn buttons(&self) -> Vec<InputButton> {
+        let dynamic_buttons = self.dynamic.iter().flat_map(DynamicSpread::buttons);
+        let mut buttons = Vec::new();
+        for button in self.rules.iter().map(|rule| rule.button).chain(dynamic_buttons) {
+            if !buttons.contains(&button) {
+                buttons.push(button);
+            }
+        }
+        buttons
+    }
+
+    //profiles swapped in by the rules
+    pub fn swap_profiles(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().filter_map(|rule| match &rule.action {
+            RuleAction::SwapWhileHeld(profile) => Some(profile.as_str()),
+            _ => None
+        })
+    }
+
+    //returns true if the state changed
+    pub fn handle(&mut self, event: InputEvent) -> bool {
+        let before = self.state();
+        match event {
+            InputEvent::Pressed(button) => {
+                //presses of a held button are repeats
+                if self.held.insert(button) && self.rules.iter().any(|rule| rule.button == button && rule.action == RuleAction::Toggle) {
+                    self.toggled_off = !self.toggled_off;
+                }
+            },
+            InputEvent::Released(button) => {
+                self.held.remove(&button);
+            }
+        }
+        self.state() != before
+    }
+
+    //hiding wins over swapping, the first held swap rule wins over later ones
+    pub fn state(&self) -> InputState {
+        let held = |rule: &&InputRule| self.held.contains(&rule.button);
+        if self.toggled_off || self.rules.iter().filter(held).any(|rule| rule.action == RuleAction::HideWhileHeld) {
+            return InputState::Hidden
+        }
+        self.rules
+            .iter()
+            .filter(held)
+            .find_map(|rule| match &rule.action {
+                RuleAction::SwapWhileHeld(profile) => Some(InputState::Swapped(profile.clone())),
+                _ => None
+            })
+            .unwrap_or(InputState::Normal)
+    }
+}
+
+//reports presses and releases of `buttons` from every keyboard and mouse
+//(xinput2 raw events, nothing is grabbed so the game still gets them)
+//by calling `on_event` from a background thread
+pub(crate) fn listen_x11(buttons: &[InputButton], on_event: impl Fn(InputEvent) + Send + 'static) -> Result<(), String> {
+    let (connection, screen_num) = x11rb::connect(None).map_err(|err| format!("no x11 display: {err}"))?;
+    let root = connection.setup().roots[screen_num].root;
+    //raw events reach clients without a grab since 2.1
+    let version = connection
+        .xinput_xi_query_version(2, 2)
+        .map_err(|err| err.to_string())?
+        .reply()
+        .map_err(|err| format!("xinput2 unavailable: {err:?}"))?;
+    if (version.major_version, version.minor_version) < (2, 1) {
+        return Err(format!("xinput {}.{} is too old (2.1 needed)", version.major_version, version.minor_version))
+    }
+
+    let mut keys = HashMap::new();
+    for &button in buttons {
+        if let InputButton::Key(keysym) = button {
+            match hotkey::find_keycode(&connection, keysym)? {
+                Some(keycode) => {
+                    keys.insert(u32::from(keycode), button);
+                },
+                None => println!("{button} is not on the keyboard")
+            }
+        }
+    }
+    let mice: HashSet<InputButton> = buttons.iter().copied().filter(|button| matches!(button, InputButton::Mouse(_))).collect();
+
+    let mask = XIEventMask::RAW_KEY_PRESS | XIEventMask::RAW_KEY_RELEASE | XIEventMask::RAW_BUTTON_PRESS | XIEventMask::RAW_BUTTON_RELEASE;
+    let all_master_devices = u16::from(bool::from(xinput::Device::ALL_MASTER));
+    connection
+        .xinput_xi_select_events(root, &[xinput::EventMask { deviceid: all_master_devices, mask: vec![mask] }])
+        .map_err(|err| err.to_string())?
+        .check()
+        .map_err(|err| format!("failed selecting raw input: {err:?}"))?;
+
+    thread::Builder::new()
+        .name("x11 input".to_string())
+        .spawn(move || {
+            let mouse = |button: u32| u8::try_from(button).ok().map(InputButton::Mouse).filter(|button| mice.contains(button));
+            loop {
+                let event = match connection.wait_for_event() {
+                    Ok(Event::XinputRawKeyPress(event)) => keys.get(&event.detail).copied().map(InputEvent::Pressed),
+                    Ok(Event::XinputRawKeyRelease(event)) => keys.get(&event.detail).copied().map(InputEvent::Released),
+                    Ok(Event::XinputRawButtonPress(event)) => mouse(event.detail).map(InputEvent::Pressed),
+                    Ok(Event::XinputRawButtonRelease(event)) => mouse(event.detail).map(InputEvent::Released),
+                    Ok(_) => None,
+                    Err(err) => {
+                        println!("x11 input connection failed: {err}");
+                        return
+                    }
+                };
+                if let Some(event) = event {
+                    on_event(event);
+                }
+            }
+        })
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RMB: InputButton = InputButton::Mouse(3);
+    const MOUSE4: InputButton = InputButton::Mouse(8);
+    const MOUSE5: InputButton = InputButton::Mouse(9);
+
+    fn rules(text: &str) -> InputRules {
+        InputRules::parse(text).unwrap()
+    }
+
+    #[test]
+    fn hide_while_held() {
+        let mut rules = rules("hide rmb");
+        assert!(rules.handle(InputEvent::Pressed(RMB)));
+        assert_eq!(rules.state(), InputState::Hidden);
+        assert!(rules.handle(InputEvent::Released(RMB)));
+        assert_eq!(rules.state(), InputState::Normal);
+    }
+
+    #[test]
+    fn toggle_ignores_repeats() {
+        let mut rules = rules("toggle mouse4");
+        assert!(rules.handle(InputEvent::Pressed(MOUSE4)));
+        //key repeat while held
+        assert!(!rules.handle(InputEvent::Pressed(MOUSE4)));
+        assert!(!rules.handle(InputEvent::Released(MOUSE4)));
+        assert_eq!(rules.state(), InputState::Hidden);
+        rules.handle(InputEvent::Pressed(MOUSE4));
+        assert_eq!(rules.state(), InputState::Normal);
+    }
+
+    #[test]
+    fn hiding_wins_over_swapping() {
+        let mut rules = rules("swap rmb scope\nhide mouse5");
+        rules.handle(InputEvent::Pressed(RMB));
+        assert_eq!(rules.state(), InputState::Swapped("scope".to_string()));
+        rules.handle(InputEvent::Pressed(MOUSE5));
+        assert_eq!(rules.state(), InputState::Hidden);
+        rules.handle(InputEvent::Released(MOUSE5));
+        assert_eq!(rules.state(), InputState::Swapped("scope".to_string()));
+    }
+
+    #[test]
+    fn first_held_swap_wins() {
+        let mut rules = rules("swap rmb scope\nswap mouse4 sniper");
+        rules.handle(InputEvent::Pressed(MOUSE4));
+        rules.handle(InputEvent::Pressed(RMB));
+        assert_eq!(rules.state(), InputState::Swapped("scope".to_string()));
+        rules.handle(InputEvent::Released(RMB));
+        assert_eq!(rules.state(), InputState::Swapped("sniper".to_string()));
+    }
+
+    #[test]
+    fn unknown_buttons_change_nothing() {
+        let mut rules = rules("hide rmb");
+        assert!(!rules.handle(InputEvent::Pressed(MOUSE4)));
+        assert!(!rules.handle(InputEvent::Released(MOUSE4)));
+        assert_eq!(rules.state(), InputState::Normal);
+    }
+
+    #[test]
+    fn buttons_are_unique() {
+        let rules = rules("hide rmb\ntoggle rmb\nmove mouse4\nfire mouse4");
+        assert_eq!(rules.buttons(), vec![RMB, MOUSE4]);
+        assert!(rules.dynamic().is_some());
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let err = InputRules::parse("hide rmb\nhide nothing-like-this").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+        assert!(InputRules::parse("spread max -1").is_err());
+    }
+}
@@ -1,13 +1,84 @@
-use winit::window::Window;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use winit::window::{Window, WindowLevel};
 use winit::application::ApplicationHandler;
 use winit::event_loop::{ActiveEventLoop, EventLoop, ControlFlow};
-use winit::event::WindowEvent;
-use winit::dpi::LogicalSize;
+use winit::event::{KeyEvent, WindowEvent};
+use winit::keyboard::{Key, ModifiersState};
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
 
-use crate::renderer::Renderer;
+pub use crate::active_window::FocusedWindow;
+pub use crate::animation::{Keyframe, Looping, Track};
+pub use crate::config::Config;
+pub use crate::fault::{Fault, RenderError, ScheduledFault};
+pub use crate::game_profiles::{FakeProcesses, GameRule, ProcFs, ProcessInfo, ProcessMatch, ProcessSource, ProfileRegistry, ProfileWatcher};
+pub use crate::history::{EditCommand, EditHistory, EditSession, HISTORY_CAPACITY};
+pub use crate::dynamic::{DynamicSpread, SpreadSettings};
+pub use crate::easing::Easing;
+pub use crate::hotkey::{Hotkey, HotkeyModifiers};
+pub use crate::input_rules::{InputButton, InputEvent, InputRule, InputRules, InputState, RuleAction};
+pub use crate::mode::Mode;
+pub use crate::nudge::{KeyboardEditor, NUDGE_ELEMENTS};
+pub use crate::property::{AnimationSettings, Property, PropertyValue};
+pub use crate::crosshair::{Color, Crosshair, ExtraPixelSide, Rect, RenderMode, Shape};
+pub use crate::msaa::MsaaSamples;
+pub use crate::pipeline::{BlendMode, Pass, PipelineDesc, Topology, VertexLayout};
+pub use crate::presets::{Preset, PresetLibrary, PresetSource, ThumbnailCache, THUMBNAIL_SIZE, builtin_presets, parse_profile};
+pub use crate::renderer::{PresentModePreference, Renderer};
+use crate::hot_reload::ShaderWatcher;
+pub use crate::redraw::RedrawScheduler;
+pub use crate::stats::{CpuTimings, FrameSample, FrameStats, STATS_WINDOW};
+pub use crate::ui_painter::UiFrame;
+use crate::active_window::X11ActiveWindow;
+use crate::ui::Editor;
+pub use crate::validation::{
+    DebugMessage, DebugMessageLog, MessageCounts, MessageSeverity, ValidationConfig, ValidationFailure,
+    ValidationFeature
+};
 
+mod active_window;
+mod allocator;
+mod animation;
+mod config;
+mod crosshair;
+mod deletion;
+mod dynamic;
+mod easing;
+mod fault;
+mod game_profiles;
 mod renderer;
 mod helper;
+mod history;
+mod hot_reload;
+mod hotkey;
+mod input_rules;
+mod mode;
+mod nudge;
+mod msaa;
+mod offscreen;
+mod pipeline;
+mod pipeline_cache;
+mod presets;
+mod property;
+mod redraw;
+mod shader_compiler;
+mod shaders;
+mod stats;
+mod ui;
+mod ui_painter;
+mod validation;
+
+//events sent to the event loop from other threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppEvent {
+    //the global hotkey was pressed
+    ToggleMode,
+    //another window got focus or the focused one moved (--follow-window)
+    FocusChanged(Option<FocusedWindow>),
+    //a button of an input rule was pressed / released (anywhere)
+    Input(InputEvent)
+}
 
 #[derive(Default)]
 pub struct App {
@@ -18,42 +89,446 @@ pub struct App {
     //optional handle to window
     window: Option<Window>,
     //optional handle to renderer 
-    renderer: Option<Renderer>
+    renderer: Option<Renderer>,
+    //runtime options (cli + environment)
+    config: Config,
+    //watches glsl sources in development mode
+    shader_watcher: Option<ShaderWatcher>,
+    //active crosshair
+    crosshair: Crosshair,
+    //egui configurator (created with the renderer, which owns its textures)
+    editor: Option<Editor>,
+    //edit (interactive) or play (click-through)
+    mode: Mode,
+    //undo / redo of crosshair edits
+    history: EditHistory,
+    //file crosshair and history are saved to after every edit step
+    session_path: Option<PathBuf>,
+    //built-in presets and the user's profiles (presets window)
+    preset_library: PresetLibrary,
+    //preset previews rendered offscreen
+    thumbnail_cache: ThumbnailCache,
+    //switches profiles by running / focused game (none without games.txt)
+    profile_watcher: Option<ProfileWatcher>,
+    //hide / swap the crosshair on global input (none without input.txt)
+    input_rules: Option<InputRules>,
+    //profiles the input rules swap in, loaded up front (no file io per frame)
+    swap_presets: HashMap<String, Preset>,
+    //last focused game window the overlay was put on (--follow-window)
+    followed_window: Option<FocusedWindow>,
+    //something other than a game has focus: the crosshair is hidden in play mode
+    non_game_focused: bool,
+    //modifier keys currently held (keyboard shortcuts)
+    modifiers: ModifiersState,
+    //arrow / bracket key fine tuning
+    keyboard_editor: KeyboardEditor,
+    //a nudge key is held: its auto-repeats belong to one undo step
+    nudge_key_held: bool,
+    //time keyframe tracks count from (start / profile switch)
+    animation_start: Option<Instant>,
+    //decides when a new frame has to be rendered
+    redraw_scheduler: RedrawScheduler,
+    //time to rebuild the renderer after it failed (device / surface lost)
+    rebuild_renderer_at: Option<Instant>
 }
 
 impl App {
-    pub fn new(width: u32, height: u32) {
-        let event_loop = 
-            EventLoop::new().expect("failed creating event loop!");
-        event_loop.set_control_flow(ControlFlow::Poll);
+    pub fn new(width: u32, height: u32, config: Config) {
+        let event_loop = EventLoop::<AppEvent>::with_user_event()
+            .build()
+            .expect("failed creating event loop!");
+        event_loop.set_control_flow(ControlFlow::Wait);
+
+        //the hotkey has to work while the game has focus
+        let proxy = event_loop.create_proxy();
+        match hotkey::listen_x11(config.hotkey, move || { let _ = proxy.send_event(AppEvent::ToggleMode); }) {
+            Ok(()) => println!("press {} to switch between edit and play mode", config.hotkey),
+            Err(err) => println!("global hotkey unavailable: {err}")
+        }
+
+        if config.follow_window {
+            let proxy = event_loop.create_proxy();
+            let result = X11ActiveWindow::connect()
+                .and_then(|active_window| active_window.listen(move |focused| { let _ = proxy.send_event(AppEvent::FocusChanged(focused)); }));
+            if let Err(err) = result {
+                println!("can't follow the focused window: {err}");
+            }
+        }
+
+        let input_rules = InputRules::default_path()
+            .as_deref()
+            .and_then(InputRules::load)
+            .filter(|input_rules| !input_rules.is_empty());
+        if let Some(input_rules) = input_rules.as_ref() {
+            let proxy = event_loop.create_proxy();
+            if let Err(err) = input_rules::listen_x11(&input_rules.buttons(), move |event| { let _ = proxy.send_event(AppEvent::Input(event)); }) {
+                println!("input rules unavailable: {err}");
+            }
+        }
+
+        let shader_watcher = config.hot_reload_dir.clone().map(ShaderWatcher::new);
+        let session_path = EditSession::default_path();
+        let profile_watcher = ProfileRegistry::default_path()
+            .as_deref()
+            .and_then(ProfileRegistry::load)
+            .filter(|registry| !registry.rules.is_empty() || registry.default_profile.is_some())
+            .map(|registry| ProfileWatcher::new(registry, ProcFs::system()));
+        let preset_library = PresetLibrary::new(PresetLibrary::default_profile_dir());
+        let mut swap_presets = HashMap::new();
+        for name in input_rules.iter().flat_map(InputRules::swap_profiles) {
+            match preset_library.load(name) {
+                Ok(preset) => {
+                    swap_presets.insert(name.to_string(), preset);
+                },
+                Err(err) => println!("can't swap to profile {name}: {err}")
+            }
+        }
         let mut app = App {
             width,
             height,
+            mode: config.start_mode,
+            config,
+            shader_watcher,
+            session_path,
+            preset_library,
+            thumbnail_cache: ThumbnailCache::new(ThumbnailCache::default_dir()),
+            profile_watcher,
+            input_rules,
+            swap_presets,
+            animation_start: Some(Instant::now()),
             ..Default::default()
         };
+        //continue where the last run stopped
+        if let Some(session) = app.session_path.as_deref().and_then(EditSession::load) {
+            println!("restored the last edit session");
+            app.crosshair = session.crosshair;
+            app.history = session.history;
+        }
+        //the frame time graph is only useful while it keeps updating
+        if app.config.show_stats {
+            app.redraw_scheduler.start_animation("stats", 30.0);
+        }
         event_loop.run_app(&mut app).expect("failed running app!");
     }
 }
-impl ApplicationHandler for App {
+impl App {
+    //delay between attempts to rebuild a failed renderer
+    const REBUILD_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+    //frame rate of animations if the monitor doesn't tell
+    const DEFAULT_REFRESH_RATE: f32 = 60.0;
+
+    //monitor refresh rate in frames per second (for animations)
+    fn refresh_rate(&self) -> f32 {
+        self.window
+            .as_ref()
+            .and_then(Window::current_monitor)
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map_or(Self::DEFAULT_REFRESH_RATE, |millihertz| millihertz as f32 / 1000.0)
+    }
+
+    //seconds since the crosshair's animation started
+    fn animation_time(&self, now: Instant) -> f32 {
+        self.animation_start.map_or(0.0, |start| now.saturating_duration_since(start).as_secs_f32())
+    }
+
+    fn create_renderer(&mut self, event_loop: &ActiveEventLoop) -> Result<Renderer, RenderError> {
+        let window = self.window.as_ref().unwrap();
+        let mut renderer = Renderer::new(event_loop, window, &self.config)?;
+        //a fresh egui context uploads all of its textures again
+        let mut editor = Editor::new(window);
+        editor.set_presets(Self::preset_tiles(&self.preset_library, &self.thumbnail_cache, &mut renderer));
+        self.editor = Some(editor);
+        //only the first renderer fails on purpose
+        if let Some(inject_fault) = self.config.inject_fault.take() {
+            renderer.inject_fault(inject_fault);
+        }
+        Ok(renderer)
+    }
+
+    //drops the broken renderer, a new one is built in about_to_wait
+    //(the crosshair lives in App and survives the rebuild)
+    fn render_failed(&mut self, err: RenderError) {
+        println!("renderer failed: {err}; rebuilding it");
+        self.renderer = None;
+        self.rebuild_renderer_at = Some(Instant::now());
+    }
+
+    fn rebuild_renderer(&mut self, event_loop: &ActiveEventLoop) {
+        //a driver that is still resetting fails creation for a while
+        match self.create_renderer(event_loop) {
+            Ok(renderer) => {
+                println!("renderer rebuilt");
+                self.renderer = Some(renderer);
+                self.rebuild_renderer_at = None;
+                if let Some(game) = self.followed_window {
+                    self.cover(game);
+                }
+                self.apply_mode();
+            },
+            Err(err) => {
+                println!("failed rebuilding the renderer: {err}; retrying in {:?}", Self::REBUILD_RETRY_INTERVAL);
+                self.rebuild_renderer_at = Some(Instant::now() + Self::REBUILD_RETRY_INTERVAL);
+            }
+        }
+    }
+
+    //presets with their thumbnails (rendered on a cache miss)
+    fn preset_tiles(preset_library: &PresetLibrary, thumbnail_cache: &ThumbnailCache, renderer: &mut Renderer) -> Vec<(Preset, Option<Vec<u8>>)> {
+        preset_library.list().into_iter().map(|preset| {
+            let thumbnail = thumbnail_cache.get_or_render(&preset.crosshair, |crosshair, size| {
+                renderer.render_thumbnail(crosshair, size).map_err(|err| err.to_string())
+            });
+            (preset, thumbnail)
+        }).collect()
+    }
+
+    //copies the preset into the profile directory under a new name
+    //and shows it in the presets window
+    fn clone_preset(&mut self, name: &str) {
+        let new_name = self.preset_library.unused_name(name);
+        match self.preset_library.clone_preset(name, &new_name) {
+            Ok(path) => println!("cloned preset {name} to {}", path.display()),
+            Err(err) => {
+                println!("failed cloning preset {name}: {err}");
+                return
+            }
+        }
+        if let (Some(renderer), Some(editor)) = (self.renderer.as_mut(), self.editor.as_mut()) {
+            editor.set_presets(Self::preset_tiles(&self.preset_library, &self.thumbnail_cache, renderer));
+        }
+        self.redraw_scheduler.mark_dirty();
+    }
+
+    //replaces the whole crosshair, offset included (games differ in where
+    //their center is), as an undoable step
+    fn switch_profile(&mut self, name: &str) {
+        let preset = match self.preset_library.load(name) {
+            Ok(preset) => preset,
+            Err(err) => {
+                println!("failed switching to profile {name}: {err}");
+                return
+            }
+        };
+        println!("switching to profile {name}");
+        let before = std::mem::replace(&mut self.crosshair, preset.crosshair);
+        //its animation starts from the beginning
+        self.animation_start = Some(Instant::now());
+        self.history.end_step();
+        self.history.record(EditCommand::diff(&before, &self.crosshair));
+        self.history.end_step();
+        self.save_session();
+        self.redraw_scheduler.mark_dirty();
+    }
+
+    fn save_session(&self) {
+        if let Some(session_path) = self.session_path.as_deref() {
+            EditSession::save(session_path, &self.crosshair, &self.history);
+        }
+    }
+
+    //ctrl+z undo, ctrl+y / ctrl+shift+z redo, then the nudge keys
+    //(true if the key was used, it isn't passed to the editor then)
+    fn handle_key(&mut self, event: &KeyEvent) -> bool {
+        if !event.state.is_pressed() {
+            self.nudge_key_held = false;
+            return false
+        }
+
+        if self.modifiers.control_key() {
+            let Key::Character(character) = &event.logical_key else {
+                return false
+            };
+            let changed = match character.to_lowercase().as_str() {
+                "p" => {
+                    self.cycle_present_mode();
+                    return true
+                },
+                "z" if self.modifiers.shift_key() => self.history.redo(&mut self.crosshair),
+                "z" => self.history.undo(&mut self.crosshair),
+                "y" => self.history.redo(&mut self.crosshair),
+                _ => return false
+            };
+            if changed {
+                self.save_session();
+                self.redraw_scheduler.mark_dirty();
+            }
+            return true
+        }
+
+        let before = self.crosshair.clone();
+        if !self.keyboard_editor.handle_key(&event.logical_key, self.modifiers.shift_key(), &mut self.crosshair) {
+            return false
+        }
+        //a fresh press is a new step, auto-repeats extend it
+        if !event.repeat && self.history.end_step() {
+            self.save_session();
+        }
+        self.history.record(EditCommand::diff(&before, &self.crosshair));
+        self.nudge_key_held = true;
+        //the hud line changes with the selection as well
+        self.redraw_scheduler.mark_dirty();
+        true
+    }
+
+    //switches to the next present mode (the swapchain is recreated with the next frame)
+    fn cycle_present_mode(&mut self) {
+        //kept in the config, so a rebuilt renderer uses it as well
+        let preference = self.config.present_mode.cycled();
+        self.config.present_mode = preference;
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.set_present_mode(preference);
+        }
+        println!("present mode: {}", preference.name());
+        self.redraw_scheduler.mark_dirty();
+    }
+
+    //puts the overlay on the focused game (its center is the game's center),
+    //hides the crosshair while another window has focus
+    fn follow_focus(&mut self, focused: Option<FocusedWindow>) {
+        //the overlay itself has focus in edit mode
+        if focused.is_some_and(|focused| focused.pid == Some(std::process::id())) {
+            return
+        }
+        let game = focused.filter(|focused| self.is_game(focused));
+        if let Some(game) = game {
+            self.followed_window = Some(game);
+            self.cover(game);
+        }
+        self.non_game_focused = game.is_none();
+        self.apply_visibility();
+    }
+
+    //games are the processes of games.txt (any window without rules)
+    fn is_game(&self, focused: &FocusedWindow) -> bool {
+        let Some(registry) = self.profile_watcher.as_ref().map(ProfileWatcher::registry).filter(|registry| !registry.rules.is_empty()) else {
+            return true
+        };
+        focused.pid
+            .and_then(|pid| ProcFs::new(PathBuf::from("/proc")).process(pid))
+            .is_some_and(|process| registry.is_game(&process))
+    }
+
+    //the game stays visible: an opaque window (no renderer yet, or no
+    //compositor) is left where it is instead of hiding the game
+    fn cover(&self, game: FocusedWindow) {
+        let Some(window) = self.window.as_ref() else {
+            return
+        };
+        if !self.renderer.as_ref().is_some_and(Renderer::is_transparent) {
+            println!("not covering the game window, the overlay isn't transparent");
+            return
+        }
+        //undecorated, the outer position is the client area's
+        window.set_outer_position(PhysicalPosition::new(game.x, game.y));
+        //applied asynchronously, the renderer follows with WindowEvent::Resized
+        let _ = window.request_inner_size(PhysicalSize::new(game.width, game.height));
+    }
+
+    //the editor is always visible, the crosshair only over a game
+    fn apply_visibility(&self) {
+        if let Some(window) = self.window.as_ref() {
+            window.set_visible(self.mode.shows_editor() || !self.non_game_focused);
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        if mode == self.mode {
+            return
+        }
+        println!("switching to {mode:?} mode");
+        self.mode = mode;
+        self.apply_mode();
+    }
+
+    //window attributes and renderer state of the current mode
+    //(redrawn right away, the editor appears / disappears)
+    fn apply_mode(&mut self) {
+        let takes_input = self.mode.takes_input();
+        if let Some(window) = self.window.as_ref() {
+            //click-through: pointer events go to the window underneath
+            if let Err(err) = window.set_cursor_hittest(takes_input) {
+                println!("failed switching click-through: {err}");
+            }
+            if takes_input {
+                window.focus_window();
+            }
+        }
+        self.apply_visibility();
+        if let Some(renderer) = self.renderer.as_mut() {
+            //play mode is only usable if the game can be seen through the window
+            if !self.mode.shows_editor() && !renderer.is_transparent() {
+                println!("the overlay window isn't transparent (no compositor?), it hides what is underneath");
+            }
+            renderer.set_show_center_guide(self.mode.shows_editor());
+            if !self.mode.shows_editor() {
+                renderer.clear_ui();
+            }
+        }
+        self.redraw_scheduler.mark_dirty();
+    }
+}
+impl ApplicationHandler<AppEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        //create window
-        let attributes = Window::default_attributes()
-            .with_title("application")
-            .with_inner_size(LogicalSize::new(self.width, self.height));
-
-        self.window = Some(
-            event_loop
-                .create_window(attributes)
-                .expect("failed creating window!")
-        );
-
-        //create vulkan-stuff
-        self.renderer = Some(
-            Renderer::new(
-                &event_loop,
-                self.window.as_ref().unwrap()
-            )
-        );
+        //create window (dropped on suspend, its native handle may be gone)
+        if self.window.is_none() {
+            let attributes = Window::default_attributes()
+                .with_title("application")
+                .with_inner_size(LogicalSize::new(self.width, self.height))
+                //stays above the game in both modes
+                .with_window_level(WindowLevel::AlwaysOnTop)
+                //covers the game's client area exactly
+                .with_decorations(!self.config.follow_window)
+                //an argb visual, the game shows through everything but the crosshair
+                .with_transparent(true);
+
+            self.window = Some(
+                event_loop
+                    .create_window(attributes)
+                    .expect("failed creating window!")
+            );
+        }
+
+        //create vulkan-stuff, or only the surface and swapchain
+        //if instance and device survived a suspend
+        match self.renderer.as_mut() {
+            Some(renderer) => {
+                if let Err(err) = renderer.resume(event_loop, self.window.as_ref().unwrap()) {
+                    self.render_failed(err);
+                }
+            },
+            None => match self.create_renderer(event_loop) {
+                Ok(renderer) => {
+                    self.renderer = Some(renderer);
+                    self.rebuild_renderer_at = None;
+                },
+                Err(err) => {
+                    println!("failed creating the renderer: {err}; retrying in {:?}", Self::REBUILD_RETRY_INTERVAL);
+                    self.rebuild_renderer_at = Some(Instant::now() + Self::REBUILD_RETRY_INTERVAL);
+                }
+            }
+        }
+        //only now it is known whether the window can be seen through
+        if let Some(game) = self.followed_window {
+            self.cover(game);
+        }
+        self.apply_mode();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        //the surface has to be gone before the window
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.suspend();
+        }
+        self.window = None;
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        //the renderer presents to the window: finish its work and
+        //drop it first
+        if let Some(mut renderer) = self.renderer.take() {
+            renderer.wait_idle();
+        }
+        self.window = None;
     }
 
     fn window_event(
@@ -62,18 +537,179 @@ impl ApplicationHandler for App {
         _window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
+        //shortcuts and nudge keys take precedence over the editor
+        let handled = match &event {
+            WindowEvent::KeyboardInput { event, .. } if self.mode.takes_input() => self.handle_key(event),
+            _ => false
+        };
+
+        //play mode is click-through, the editor gets no input
+        if let (Some(editor), Some(window)) = (self.editor.as_mut(), self.window.as_ref())
+            && self.mode.takes_input()
+            && !handled
+            && editor.on_window_event(window, &event).repaint
+        {
+            self.redraw_scheduler.mark_dirty();
+        }
+
         match event {
             WindowEvent::CloseRequested => {
                 println!("the close button was pressed; stopping");
                 event_loop.exit();
             },
+            WindowEvent::Resized(_) => {
+                if let Some(renderer) = self.renderer.as_mut() {
+                    renderer.resized();
+                }
+                self.redraw_scheduler.mark_dirty();
+            },
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
             WindowEvent::RedrawRequested => {
-                self.window
+                let window = self.window.as_ref().unwrap();
+                //the editor runs right before drawing, edits show up in this frame
+                let before = self.crosshair.clone();
+                let hud_line = self.keyboard_editor.hud_line(&self.crosshair);
+                let mut editor_output = self.editor
+                    .as_mut()
+                    .filter(|_| self.mode.shows_editor())
+                    .map(|editor| editor.run(window, &mut self.crosshair, &hud_line));
+                self.history.record(EditCommand::diff(&before, &self.crosshair));
+                //a drag or held nudge key continues the open step, anything else ends it
+                let interacting = self.nudge_key_held || editor_output.as_ref().is_some_and(|editor_output| editor_output.interacting);
+                if !interacting && self.history.end_step() {
+                    self.save_session();
+                }
+                let repaint_delay = editor_output.as_ref().map(|editor_output| editor_output.repaint_delay);
+                let clone_preset = editor_output.as_mut().and_then(|editor_output| editor_output.clone_preset.take());
+                //input rules only apply while playing, the editor shows what is edited
+                let input_state = match self.input_rules.as_ref() {
+                    Some(input_rules) if !self.mode.shows_editor() => input_rules.state(),
+                    _ => InputState::Normal
+                };
+                let time = self.animation_time(Instant::now());
+                let mut drawn = match &input_state {
+                    InputState::Swapped(name) => self.swap_presets.get(name).map(|preset| {
+                        let mut crosshair = self.crosshair.clone();
+                        preset.apply_to(&mut crosshair);
+                        crosshair
+                    }),
+                    _ => None
+                }.unwrap_or_else(|| self.crosshair.clone());
+                //tracks replace the edited values (the editor shows the base ones)
+                if !drawn.animation.is_empty() {
+                    drawn = drawn.at_time(time);
+                }
+                //the dynamic crosshair opens its gap on movement / firing
+                let spread = self.input_rules
                     .as_ref()
-                    .unwrap()
-                    .request_redraw();
+                    .and_then(InputRules::dynamic)
+                    .filter(|_| !self.mode.shows_editor())
+                    .map_or(0.0, |dynamic| dynamic.spread(Instant::now()));
+                drawn.gap += spread;
+                let result = match self.renderer.as_mut() {
+                    Some(renderer) => {
+                        if let Some(editor_output) = editor_output {
+                            renderer.set_ui(editor_output.frame);
+                        }
+                        renderer.set_show_crosshair(input_state != InputState::Hidden);
+                        renderer.set_time(time);
+                        renderer.draw(window, &drawn)
+                    },
+                    None => Ok(())
+                };
+                if let Err(err) = result {
+                    self.render_failed(err);
+                }
+                let now = Instant::now();
+                self.redraw_scheduler.frame_rendered(now);
+
+                //egui animations / tooltips ask for follow-up frames
+                if let Some(repaint_delay) = repaint_delay {
+                    self.redraw_scheduler.repaint_after(now, repaint_delay);
+                }
+                if let Some(name) = clone_preset {
+                    self.clone_preset(&name);
+                }
             },
             _ => ()
         }
     }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: AppEvent) {
+        match event {
+            AppEvent::ToggleMode => self.set_mode(self.mode.toggled()),
+            AppEvent::FocusChanged(focused) => self.follow_focus(focused),
+            AppEvent::Input(event) => {
+                let Some(input_rules) = self.input_rules.as_mut() else {
+                    return
+                };
+                if let Some(dynamic) = input_rules.dynamic_mut() {
+                    dynamic.handle(event, Instant::now());
+                }
+                if input_rules.handle(event) {
+                    self.redraw_scheduler.mark_dirty();
+                }
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() && self.rebuild_renderer_at.is_some_and(|rebuild_at| rebuild_at <= Instant::now()) {
+            self.rebuild_renderer(event_loop);
+        }
+
+        //hot-reload changed shaders (development mode)
+        if let (Some(shader_watcher), Some(renderer)) = (self.shader_watcher.as_mut(), self.renderer.as_mut()) {
+            let changed = shader_watcher.poll();
+            if !changed.is_empty() && renderer.reload_shaders(&changed) {
+                self.redraw_scheduler.mark_dirty();
+            }
+        }
+
+        if let Some(name) = self.profile_watcher.as_mut().and_then(ProfileWatcher::poll) {
+            self.switch_profile(&name);
+        }
+
+        //frames only while a track still changes (a finished "once" track is static again)
+        let now = Instant::now();
+        if self.crosshair.is_animated_at(self.animation_time(now)) {
+            let frames_per_second = self.crosshair.animation_rate.unwrap_or_else(|| self.refresh_rate());
+            self.redraw_scheduler.start_animation("crosshair", frames_per_second);
+        } else {
+            self.redraw_scheduler.stop_animation("crosshair");
+        }
+
+        //frames while the dynamic gap opens / closes
+        let spreading = self.input_rules
+            .as_ref()
+            .and_then(InputRules::dynamic)
+            .is_some_and(|dynamic| !self.mode.shows_editor() && dynamic.is_animating(now));
+        if spreading {
+            self.redraw_scheduler.start_animation("dynamic", self.refresh_rate());
+        } else {
+            self.redraw_scheduler.stop_animation("dynamic");
+        }
+
+        let Some(window) = self.window.as_ref() else {
+            return
+        };
+        if self.redraw_scheduler.should_redraw(Instant::now()) {
+            window.request_redraw();
+        }
+
+        //sleep until the next event, animation frame, shader or process poll
+        let wakeup = [
+            self.redraw_scheduler.next_wakeup(),
+            self.shader_watcher.as_ref().map(ShaderWatcher::next_poll),
+            self.profile_watcher.as_ref().map(ProfileWatcher::next_poll),
+            self.rebuild_renderer_at
+        ]
+            .into_iter()
+            .flatten()
+            .min();
+        event_loop.set_control_flow(match wakeup {
+            Some(wakeup) => ControlFlow::WaitUntil(wakeup),
+            None => ControlFlow::Wait
+        });
+    }
 }
\ No newline at end of file
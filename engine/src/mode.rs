@@ -0,0 +1,35 @@
+//what the overlay window is used for, toggled with the global hotkey
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    //configuring: the window takes input, shows the editor and a center guide
+    #[default]
+    Edit,
+    //gaming: click-through, only the crosshair is drawn
+    Play
+}
+impl Mode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "edit" => Some(Self::Edit),
+            "play" => Some(Self::Play),
+            _ => None
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Edit => Self::Play,
+            Self::Play => Self::Edit
+        }
+    }
+
+    //pointer and keyboard input reach the window (otherwise it is click-through)
+    pub fn takes_input(self) -> bool {
+        self == Self::Edit
+    }
+
+    //editor ui and center guide are drawn
+    pub fn shows_editor(self) -> bool {
+        self == Self::Edit
+    }
+}
@@ -0,0 +1,129 @@
+use ash::{Device, Instance};
+use ash::vk::{
+    self, ComponentMapping, Extent2D, Format, Image, ImageAspectFlags, ImageCreateInfo, ImageLayout, ImageSubresourceRange, ImageTiling, ImageType, ImageUsageFlags, ImageView, ImageViewCreateInfo, ImageViewType, PhysicalDevice, SampleCountFlags, SharingMode
+};
+
+use crate::allocator::{Allocation, Allocator, MemoryUsage};
+use crate::deletion::{DestroyContext, DeviceResource};
+
+//requested multisample anti-aliasing level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MsaaSamples {
+    #[default]
+    Off,
+    X2,
+    X4,
+    X8
+}
+impl MsaaSamples {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().trim_end_matches('x') {
+            "0" | "1" | "off" => Some(Self::Off),
+            "2" => Some(Self::X2),
+            "4" => Some(Self::X4),
+            "8" => Some(Self::X8),
+            _ => None
+        }
+    }
+
+    fn sample_count(self) -> SampleCountFlags {
+        match self {
+            Self::Off => SampleCountFlags::TYPE_1,
+            Self::X2 => SampleCountFlags::TYPE_2,
+            Self::X4 => SampleCountFlags::TYPE_4,
+            Self::X8 => SampleCountFlags::TYPE_8
+        }
+    }
+}
+
+//highest sample count <= requested that the device supports
+//for color attachments (falls back to 1 sample)
+pub(crate) fn select_sample_count(instance: &Instance, physical_device: &PhysicalDevice, requested: MsaaSamples) -> SampleCountFlags {
+    let limits = unsafe { instance.get_physical_device_properties(*physical_device) }.limits;
+    let supported = limits.framebuffer_color_sample_counts;
+
+    let selected = [SampleCountFlags::TYPE_8, SampleCountFlags::TYPE_4, SampleCountFlags::TYPE_2]
+        .into_iter()
+        .filter(|sample_count| sample_count.as_raw() <= requested.sample_count().as_raw())
+        .find(|sample_count| supported.contains(*sample_count))
+        .unwrap_or(SampleCountFlags::TYPE_1);
+
+    if selected != requested.sample_count() {
+        println!("msaa {requested:?} not supported by the device, using {selected:?}");
+    }
+    selected
+}
+
+//multisampled color attachment that is resolved into the swapchain image
+pub(crate) struct ColorTarget {
+    image: Image,
+    allocation: Allocation,
+    pub(crate) image_view: ImageView
+}
+impl ColorTarget {
+    //nothing is left behind on failure
+    pub(crate) fn new(logical_device: &Device, allocator: &mut Allocator, format: Format, extent: Extent2D, samples: SampleCountFlags) -> Result<Self, vk::Result> {
+        let image_create_info = ImageCreateInfo {
+            image_type: ImageType::TYPE_2D,
+            format,
+            extent: vk::Extent3D { width: extent.width, height: extent.height, depth: 1 },
+            mip_levels: 1,
+            array_layers: 1,
+            samples,
+            tiling: ImageTiling::OPTIMAL,
+            //only lives during the render pass (resolved at its end)
+            usage: ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSIENT_ATTACHMENT,
+            sharing_mode: SharingMode::EXCLUSIVE,
+            initial_layout: ImageLayout::UNDEFINED,
+            ..Default::default()
+        };
+
+        unsafe {
+            let image = logical_device.create_image(&image_create_info, None)?;
+
+            //tilers can keep transient attachments in on-chip memory
+            let allocation = match allocator.allocate_image(logical_device, image, MemoryUsage::GpuLazy, "msaa color target") {
+                Ok(allocation) => allocation,
+                Err(err) => {
+                    logical_device.destroy_image(image, None);
+                    return Err(err)
+                }
+            };
+
+            let image_view_create_info = ImageViewCreateInfo {
+                image,
+                view_type: ImageViewType::TYPE_2D,
+                format,
+                components: ComponentMapping::default(),
+                subresource_range: ImageSubresourceRange {
+                    aspect_mask: ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1
+                },
+                ..Default::default()
+            };
+            let image_view = match logical_device.create_image_view(&image_view_create_info, None) {
+                Ok(image_view) => image_view,
+                Err(err) => {
+                    logical_device.destroy_image(image, None);
+                    allocator.free(logical_device, allocation);
+                    return Err(err)
+                }
+            };
+
+            Ok(Self { image, allocation, image_view })
+        }
+    }
+
+}
+impl DeviceResource for ColorTarget {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe {
+            ctx.logical_device.destroy_image_view(self.image_view, None);
+            ctx.logical_device.destroy_image(self.image, None);
+        }
+        ctx.allocator.free(ctx.logical_device, self.allocation);
+    }
+}
@@ -0,0 +1,145 @@
+use winit::keyboard::{Key, NamedKey};
+
+use crate::crosshair::Crosshair;
+use crate::property::{Property, PropertyValue};
+
+//properties the bracket keys change, cycled with tab
+pub const NUDGE_ELEMENTS: [Property; 5] = [
+    Property::Gap,
+    Property::ArmLength,
+    Property::ArmThickness,
+    Property::DotSize,
+    Property::OutlineThickness
+];
+//step with shift held (otherwise one physical pixel)
+const COARSE_STEP: f32 = 10.0;
+
+//keyboard-only fine tuning (edit mode):
+//arrows move the offset, [ / ] change the selected element, tab selects
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardEditor {
+    //index into NUDGE_ELEMENTS
+    selected: usize
+}
+impl KeyboardEditor {
+    pub fn selected(&self) -> Property {
+        NUDGE_ELEMENTS[self.selected]
+    }
+
+    //applies the key to the crosshair (false if it isn't a nudge key)
+    pub fn handle_key(&mut self, key: &Key, shift: bool, crosshair: &mut Crosshair) -> bool {
+        let step = if shift { COARSE_STEP } else { 1.0 };
+        match key {
+            Key::Named(NamedKey::ArrowLeft) => crosshair.offset_x -= step,
+            Key::Named(NamedKey::ArrowRight) => crosshair.offset_x += step,
+            Key::Named(NamedKey::ArrowUp) => crosshair.offset_y -= step,
+            Key::Named(NamedKey::ArrowDown) => crosshair.offset_y += step,
+            Key::Named(NamedKey::Tab) => {
+                let count = NUDGE_ELEMENTS.len();
+                self.selected = if shift { (self.selected + count - 1) % count } else { (self.selected + 1) % count };
+            },
+            //shift turns the brackets into braces on most layouts
+            Key::Character(character) => match character.as_str() {
+                "[" | "{" => self.adjust_selected(crosshair, -step),
+                "]" | "}" => self.adjust_selected(crosshair, step),
+                _ => return false
+            },
+            _ => return false
+        }
+        true
+    }
+
+    fn adjust_selected(&self, crosshair: &mut Crosshair, delta: f32) {
+        let property = self.selected();
+        //arms and the dot are at least one pixel, gap and outline may vanish
+        let minimum = match property {
+            Property::ArmThickness | Property::DotSize => 1.0,
+            _ => 0.0
+        };
+        if let PropertyValue::Number(value) = property.get(crosshair) {
+            property.set(crosshair, PropertyValue::Number((value + delta).max(minimum)));
+        }
+    }
+
+    //current values with the selected element in brackets
+    pub fn hud_line(&self, crosshair: &Crosshair) -> String {
+        let mut parts: Vec<String> = NUDGE_ELEMENTS
+            .iter()
+            .map(|&property| {
+                let part = format!("{} {}", property.name(), property.get(crosshair));
+                if property == self.selected() { format!("[{part}]") } else { part }
+            })
+            .collect();
+        parts.push(format!("offset {},{}", crosshair.offset_x, crosshair.offset_y));
+        parts.join("  ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut KeyboardEditor, crosshair: &mut Crosshair, key: Key, shift: bool) -> bool {
+        editor.handle_key(&key, shift, crosshair)
+    }
+
+    fn character(text: &str) -> Key {
+        Key::Character(text.into())
+    }
+
+    #[test]
+    fn arrows_move_the_offset() {
+        let (mut editor, mut crosshair) = (KeyboardEditor::default(), Crosshair::default());
+        assert!(press(&mut editor, &mut crosshair, Key::Named(NamedKey::ArrowRight), false));
+        assert!(press(&mut editor, &mut crosshair, Key::Named(NamedKey::ArrowUp), true));
+        assert_eq!((crosshair.offset_x, crosshair.offset_y), (1.0, -COARSE_STEP));
+    }
+
+    #[test]
+    fn brackets_change_the_selected_element() {
+        let (mut editor, mut crosshair) = (KeyboardEditor::default(), Crosshair { gap: 4.0, ..Default::default() });
+        assert!(press(&mut editor, &mut crosshair, character("]"), false));
+        assert_eq!(crosshair.gap, 5.0);
+        //shift turns them into braces
+        assert!(press(&mut editor, &mut crosshair, character("{"), true));
+        assert_eq!(crosshair.gap, 0.0);
+    }
+
+    #[test]
+    fn thickness_stays_visible() {
+        let (mut editor, mut crosshair) = (KeyboardEditor::default(), Crosshair { arm_thickness: 3.0, ..Default::default() });
+        press(&mut editor, &mut crosshair, Key::Named(NamedKey::Tab), false);
+        press(&mut editor, &mut crosshair, Key::Named(NamedKey::Tab), false);
+        assert_eq!(editor.selected(), Property::ArmThickness);
+        press(&mut editor, &mut crosshair, character("["), true);
+        assert_eq!(crosshair.arm_thickness, 1.0);
+    }
+
+    #[test]
+    fn tab_cycles_both_ways() {
+        let (mut editor, mut crosshair) = (KeyboardEditor::default(), Crosshair::default());
+        press(&mut editor, &mut crosshair, Key::Named(NamedKey::Tab), true);
+        assert_eq!(editor.selected(), NUDGE_ELEMENTS[NUDGE_ELEMENTS.len() - 1]);
+        for _ in 0..NUDGE_ELEMENTS.len() {
+            press(&mut editor, &mut crosshair, Key::Named(NamedKey::Tab), false);
+        }
+        assert_eq!(editor.selected(), NUDGE_ELEMENTS[NUDGE_ELEMENTS.len() - 1]);
+        assert_eq!(crosshair, Crosshair::default());
+    }
+
+    #[test]
+    fn other_keys_are_passed_on() {
+        let (mut editor, mut crosshair) = (KeyboardEditor::default(), Crosshair::default());
+        assert!(!press(&mut editor, &mut crosshair, character("a"), false));
+        assert!(!press(&mut editor, &mut crosshair, Key::Named(NamedKey::Enter), false));
+        assert_eq!(crosshair, Crosshair::default());
+    }
+
+    #[test]
+    fn hud_brackets_the_selection() {
+        let editor = KeyboardEditor::default();
+        let line = editor.hud_line(&Crosshair::default());
+        assert!(line.starts_with("[gap 4]  arm_length 8"), "{line}");
+        assert!(line.ends_with("offset 0,0"), "{line}");
+    }
+}
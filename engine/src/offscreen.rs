@@ -0,0 +1,211 @@
+use ash::Device;
+use ash::vk::{
+    self, AccessFlags, AttachmentDescription, AttachmentLoadOp, AttachmentReference, AttachmentStoreOp, Buffer, BufferCreateInfo, BufferImageCopy, BufferUsageFlags, CommandBuffer, ComponentMapping, Extent2D, Format, Framebuffer, FramebufferCreateInfo, Image, ImageAspectFlags, ImageCreateInfo, ImageLayout, ImageSubresourceLayers, ImageSubresourceRange, ImageTiling, ImageType, ImageUsageFlags, ImageView, ImageViewCreateInfo, ImageViewType, PipelineBindPoint, PipelineStageFlags, RenderPass, RenderPassCreateInfo, SampleCountFlags, SharingMode, SubpassDependency, SubpassDescription
+};
+
+use crate::allocator::{Allocation, Allocator, MemoryUsage};
+use crate::deletion::{DestroyContext, DeviceResource};
+
+//rgba bytes in memory order, independent of the swapchain format
+pub(crate) const OFFSCREEN_FORMAT: Format = Format::R8G8B8A8_SRGB;
+
+//single sampled pass into an OffscreenTarget, leaves the image ready to be copied
+pub(crate) fn create_offscreen_render_pass(logical_device: &Device) -> Result<RenderPass, vk::Result> {
+    let color_attachment_description =
+        AttachmentDescription {
+            format: OFFSCREEN_FORMAT,
+            samples: SampleCountFlags::TYPE_1,
+            load_op: AttachmentLoadOp::CLEAR,
+            store_op: AttachmentStoreOp::STORE,
+            stencil_load_op: AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: AttachmentStoreOp::DONT_CARE,
+            initial_layout: ImageLayout::UNDEFINED,
+            final_layout: ImageLayout::TRANSFER_SRC_OPTIMAL,
+            ..Default::default()
+        };
+
+    let color_attachment_reference =
+        AttachmentReference {
+            attachment: 0,
+            layout: ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+        };
+
+    let subpass_description =
+        SubpassDescription {
+            pipeline_bind_point: PipelineBindPoint::GRAPHICS,
+            p_color_attachments: &raw const color_attachment_reference,
+            color_attachment_count: 1,
+            ..Default::default()
+        };
+
+    //the copy into the readback buffer waits for the rendering
+    let subpass_dependency =
+        SubpassDependency {
+            src_subpass: 0,
+            dst_subpass: vk::SUBPASS_EXTERNAL,
+            src_stage_mask: PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            dst_stage_mask: PipelineStageFlags::TRANSFER,
+            src_access_mask: AccessFlags::COLOR_ATTACHMENT_WRITE,
+            dst_access_mask: AccessFlags::TRANSFER_READ,
+            ..Default::default()
+        };
+
+    let render_pass_create_info =
+        RenderPassCreateInfo {
+            attachment_count: 1,
+            p_attachments: &raw const color_attachment_description,
+            subpass_count: 1,
+            p_subpasses: &raw const subpass_description,
+            dependency_count: 1,
+            p_dependencies: &raw const subpass_dependency,
+            ..Default::default()
+        };
+
+    unsafe { logical_device.create_render_pass(&render_pass_create_info, None) }
+}
+
+//color image outside of the swapchain (preview thumbnails)
+//and the host visible buffer its pixels are copied to
+pub(crate) struct OffscreenTarget {
+    image: Image,
+    allocation: Allocation,
+    image_view: ImageView,
+    pub(crate) framebuffer: Framebuffer,
+    readback: Buffer,
+    readback_allocation: Allocation,
+    pub(crate) extent: Extent2D
+}
+impl OffscreenTarget {
+    //nothing is left behind on failure
+    pub(crate) fn new(logical_device: &Device, allocator: &mut Allocator, render_pass: RenderPass, extent: Extent2D) -> Result<Self, vk::Result> {
+        let image_create_info = ImageCreateInfo {
+            image_type: ImageType::TYPE_2D,
+            format: OFFSCREEN_FORMAT,
+            extent: vk::Extent3D { width: extent.width, height: extent.height, depth: 1 },
+            mip_levels: 1,
+            array_layers: 1,
+            samples: SampleCountFlags::TYPE_1,
+            tiling: ImageTiling::OPTIMAL,
+            usage: ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSFER_SRC,
+            sharing_mode: SharingMode::EXCLUSIVE,
+            initial_layout: ImageLayout::UNDEFINED,
+            ..Default::default()
+        };
+
+        unsafe {
+            let image = logical_device.create_image(&image_create_info, None)?;
+            let allocation = match allocator.allocate_image(logical_device, image, MemoryUsage::GpuOnly, "offscreen color target") {
+                Ok(allocation) => allocation,
+                Err(err) => {
+                    logical_device.destroy_image(image, None);
+                    return Err(err)
+                }
+            };
+
+            //created one after another, a failure destroys all of them
+            //(null handles are ignored)
+            let mut image_view = ImageView::null();
+            let mut framebuffer = Framebuffer::null();
+            let mut readback = Buffer::null();
+            let readback_allocation = (|| {
+                let image_view_create_info = ImageViewCreateInfo {
+                    image,
+                    view_type: ImageViewType::TYPE_2D,
+                    format: OFFSCREEN_FORMAT,
+                    components: ComponentMapping::default(),
+                    subresource_range: ImageSubresourceRange {
+                        aspect_mask: ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1
+                    },
+                    ..Default::default()
+                };
+                image_view = logical_device.create_image_view(&image_view_create_info, None)?;
+
+                let framebuffer_create_info =
+                    FramebufferCreateInfo {
+                        render_pass,
+                        attachment_count: 1,
+                        p_attachments: &raw const image_view,
+                        width: extent.width,
+                        height: extent.height,
+                        layers: 1,
+                        ..Default::default()
+                    };
+                framebuffer = logical_device.create_framebuffer(&framebuffer_create_info, None)?;
+
+                let buffer_create_info = BufferCreateInfo {
+                    size: Self::byte_size(extent),
+                    usage: BufferUsageFlags::TRANSFER_DST,
+                    sharing_mode: SharingMode::EXCLUSIVE,
+                    ..Default::default()
+                };
+                readback = logical_device.create_buffer(&buffer_create_info, None)?;
+                allocator.allocate_buffer(logical_device, readback, MemoryUsage::Readback, "offscreen readback")
+            })();
+            let readback_allocation = match readback_allocation {
+                Ok(readback_allocation) => readback_allocation,
+                Err(err) => {
+                    logical_device.destroy_buffer(readback, None);
+                    logical_device.destroy_framebuffer(framebuffer, None);
+                    logical_device.destroy_image_view(image_view, None);
+                    logical_device.destroy_image(image, None);
+                    allocator.free(logical_device, allocation);
+                    return Err(err)
+                }
+            };
+
+            Ok(Self { image, allocation, image_view, framebuffer, readback, readback_allocation, extent })
+        }
+    }
+
+    //tightly packed rgba8
+    fn byte_size(extent: Extent2D) -> u64 {
+        extent.width as u64 * extent.height as u64 * 4
+    }
+
+    //records the copy of the rendered image (TRANSFER_SRC_OPTIMAL) into the readback buffer
+    pub(crate) fn cmd_copy_to_readback(&self, logical_device: &Device, command_buffer: CommandBuffer) {
+        let region = BufferImageCopy {
+            buffer_offset: 0,
+            //tightly packed
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: ImageSubresourceLayers {
+                aspect_mask: ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1
+            },
+            image_offset: vk::Offset3D::default(),
+            image_extent: vk::Extent3D { width: self.extent.width, height: self.extent.height, depth: 1 }
+        };
+        unsafe {
+            logical_device.cmd_copy_image_to_buffer(command_buffer, self.image, ImageLayout::TRANSFER_SRC_OPTIMAL, self.readback, &[region]);
+        }
+    }
+
+    //pixels of the last completed copy
+    pub(crate) fn read_pixels(&mut self, logical_device: &Device, allocator: &Allocator) -> Result<Vec<u8>, vk::Result> {
+        allocator.invalidate(logical_device, &self.readback_allocation)?;
+        let size = Self::byte_size(self.extent) as usize;
+        let mapped = self.readback_allocation
+            .mapped_slice_mut()
+            .ok_or(vk::Result::ERROR_MEMORY_MAP_FAILED)?;
+        Ok(mapped[..size].to_vec())
+    }
+}
+impl DeviceResource for OffscreenTarget {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe {
+            ctx.logical_device.destroy_framebuffer(self.framebuffer, None);
+            ctx.logical_device.destroy_image_view(self.image_view, None);
+            ctx.logical_device.destroy_image(self.image, None);
+            ctx.logical_device.destroy_buffer(self.readback, None);
+        }
+        ctx.allocator.free(ctx.logical_device, self.allocation);
+        ctx.allocator.free(ctx.logical_device, self.readback_allocation);
+    }
+}
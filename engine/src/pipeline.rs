@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use ash::Device;
+use ash::vk::{
+    self, BlendFactor, BlendOp, ColorComponentFlags, CullModeFlags, DescriptorSetLayout, DescriptorSetLayoutBinding, DescriptorSetLayoutCreateInfo, DescriptorType, DynamicState, Format, FrontFace, GraphicsPipelineCreateInfo, Pipeline, PipelineCache, PipelineColorBlendAttachmentState, PipelineColorBlendStateCreateInfo, PipelineDynamicStateCreateInfo, PipelineInputAssemblyStateCreateInfo, PipelineLayout, PipelineLayoutCreateInfo, PipelineMultisampleStateCreateInfo, PipelineRasterizationStateCreateInfo, PipelineShaderStageCreateInfo, PipelineVertexInputStateCreateInfo, PipelineViewportStateCreateInfo, PolygonMode, PrimitiveTopology, PushConstantRange, RenderPass, SampleCountFlags, ShaderModule, ShaderModuleCreateInfo, ShaderStageFlags, VertexInputAttributeDescription, VertexInputBindingDescription, VertexInputRate
+};
+
+use crate::deletion::{DestroyContext, DeviceResource};
+use crate::helper;
+use crate::shaders::ShaderLibrary;
+
+//how fragment output is combined with the framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    //overwrite (solid fill)
+    Opaque,
+    //straight alpha blending (outlines, sdf shapes, textured images)
+    Alpha,
+    //alpha blending with premultiplied color
+    Premultiplied,
+    //add to the framebuffer (glow)
+    Additive
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topology {
+    TriangleList,
+    TriangleStrip,
+    LineList
+}
+
+//vertex buffer layout (binding 0, interleaved, f32 components
+//unless noted otherwise)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexLayout {
+    //vertices generated in the shader from gl_VertexIndex
+    None,
+    //location 0: vec2 position
+    Position2D,
+    //location 0: vec2 position, location 1: vec4 color
+    Position2DColor,
+    //location 0: vec2 position, location 1: vec2 uv
+    Position2DUv,
+    //location 0: vec2 position, location 1: vec2 uv,
+    //location 2: vec4 color from 4 normalized bytes (egui vertices)
+    Position2DUvColor8
+}
+impl VertexLayout {
+    //format and size (bytes) per attribute location
+    fn attributes(self) -> &'static [(Format, u32)] {
+        const VEC2: (Format, u32) = (Format::R32G32_SFLOAT, 8);
+        const VEC4: (Format, u32) = (Format::R32G32B32A32_SFLOAT, 16);
+        const RGBA8: (Format, u32) = (Format::R8G8B8A8_UNORM, 4);
+        match self {
+            Self::None => &[],
+            Self::Position2D => &[VEC2],
+            Self::Position2DColor => &[VEC2, VEC4],
+            Self::Position2DUv => &[VEC2, VEC2],
+            Self::Position2DUvColor8 => &[VEC2, VEC2, RGBA8]
+        }
+    }
+}
+
+//render pass a pipeline draws in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pass {
+    //crosshair (multisampled if msaa is enabled)
+    Scene,
+    //user interface on top of the resolved scene (single sampled)
+    Ui,
+    //image outside of the swapchain (single sampled, OFFSCREEN_FORMAT)
+    Offscreen
+}
+
+//declarative description of a graphics pipeline
+//(also serves as the key pipelines are cached by)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineDesc {
+    //shader base names: "crosshair" -> crosshair.vert / crosshair.frag
+    pub vertex_shader: &'static str,
+    pub fragment_shader: &'static str,
+    pub blend: BlendMode,
+    pub topology: Topology,
+    pub vertex_layout: VertexLayout,
+    //size of the push constant block visible to both stages (bytes)
+    pub push_constant_size: u32,
+    //set 0: binding 0 texture2D, binding 1 sampler (fragment stage)
+    pub textured: bool,
+    pub pass: Pass
+}
+impl PipelineDesc {
+    //solid rects generated from push constants (shaders/crosshair.*)
+    pub const CROSSHAIR: Self = Self {
+        vertex_shader: "crosshair",
+        fragment_shader: "crosshair",
+        blend: BlendMode::Alpha,
+        topology: Topology::TriangleList,
+        vertex_layout: VertexLayout::None,
+        push_constant_size: std::mem::size_of::<RectPushConstants>() as u32,
+        textured: false,
+        pass: Pass::Scene
+    };
+
+    //egui meshes (shaders/ui.*)
+    pub const UI: Self = Self {
+        vertex_shader: "ui",
+        fragment_shader: "ui",
+        //egui outputs premultiplied colors
+        blend: BlendMode::Premultiplied,
+        topology: Topology::TriangleList,
+        vertex_layout: VertexLayout::Position2DUvColor8,
+        //screen size in points
+        push_constant_size: 2 * std::mem::size_of::<f32>() as u32,
+        textured: true,
+        pass: Pass::Ui
+    };
+
+    //crosshair rects rendered offscreen (preview thumbnails)
+    pub const THUMBNAIL: Self = Self {
+        pass: Pass::Offscreen,
+        ..Self::CROSSHAIR
+    };
+}
+
+//push constant block of shaders/crosshair.vert
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RectPushConstants {
+    pub(crate) rect: [f32; 4],
+    pub(crate) color: [f32; 4],
+    pub(crate) extent: [f32; 2],
+    pub(crate) pivot: [f32; 2],
+    //radians, clockwise
+    pub(crate) rotation: f32,
+    //seconds since the animation started
+    pub(crate) time: f32
+}
+impl RectPushConstants {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        //plain f32 data without padding
+        unsafe {
+            std::slice::from_raw_parts(
+                (self as *const Self).cast::<u8>(),
+                std::mem::size_of::<Self>()
+            )
+        }
+    }
+}
+
+pub(crate) struct PipelineData {
+    pub(crate) pipeline_layout: PipelineLayout,
+    pub(crate) pipeline: Pipeline
+}
+
+//everything a pipeline depends on besides its description
+pub(crate) struct PipelineContext<'a> {
+    pub(crate) logical_device: &'a Device,
+    pub(crate) render_pass: RenderPass,
+    //must match the render pass attachments
+    pub(crate) samples: SampleCountFlags,
+    pub(crate) ui_render_pass: RenderPass,
+    pub(crate) offscreen_render_pass: RenderPass,
+    //layout of set 0 for textured pipelines
+    pub(crate) texture_set_layout: DescriptorSetLayout,
+    pub(crate) shader_library: &'a ShaderLibrary,
+    pub(crate) pipeline_cache: PipelineCache
+}
+
+//descriptor set layout of textured pipelines (PipelineDesc::textured)
+pub(crate) fn create_texture_set_layout(logical_device: &Device) -> Result<DescriptorSetLayout, vk::Result> {
+    let bindings = [
+        DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: DescriptorType::SAMPLED_IMAGE,
+            descriptor_count: 1,
+            stage_flags: ShaderStageFlags::FRAGMENT,
+            ..Default::default()
+        },
+        DescriptorSetLayoutBinding {
+            binding: 1,
+            descriptor_type: DescriptorType::SAMPLER,
+            descriptor_count: 1,
+            stage_flags: ShaderStageFlags::FRAGMENT,
+            ..Default::default()
+        }
+    ];
+    let descriptor_set_layout_create_info = DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+
+    unsafe { logical_device.create_descriptor_set_layout(&descriptor_set_layout_create_info, None) }
+}
+
+//builds pipelines on demand and caches them by description
+#[derive(Default)]
+pub(crate) struct PipelineRegistry {
+    pipelines: HashMap<PipelineDesc, PipelineData>
+}
+impl PipelineRegistry {
+    pub(crate) fn get_or_create(&mut self, ctx: &PipelineContext, desc: &PipelineDesc) -> Result<&PipelineData, vk::Result> {
+        match self.pipelines.entry(*desc) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(create_graphics_pipeline(ctx, desc)?))
+        }
+    }
+
+    pub(crate) fn get(&self, desc: &PipelineDesc) -> Option<&PipelineData> {
+        self.pipelines.get(desc)
+    }
+
+    //recreates every cached pipeline (e.g. after shaders changed),
+    //returns the old pipelines (may still be in use by submitted frames)
+    //
+    //all or nothing: if one pipeline fails, the ones already rebuilt are
+    //destroyed and the registry keeps the old pipelines
+    pub(crate) fn rebuild_all(&mut self, ctx: &PipelineContext) -> Result<Vec<PipelineData>, vk::Result> {
+        let mut rebuilt = Vec::with_capacity(self.pipelines.len());
+        for desc in self.pipelines.keys() {
+            match create_graphics_pipeline(ctx, desc) {
+                Ok(pipeline_data) => rebuilt.push((*desc, pipeline_data)),
+                Err(err) => {
+                    //nothing uses the new pipelines yet
+                    for (_, pipeline_data) in rebuilt {
+                        unsafe {
+                            ctx.logical_device.destroy_pipeline(pipeline_data.pipeline, None);
+                            ctx.logical_device.destroy_pipeline_layout(pipeline_data.pipeline_layout, None);
+                        }
+                    }
+                    return Err(err)
+                }
+            }
+        }
+        Ok(rebuilt
+            .into_iter()
+            .filter_map(|(desc, pipeline_data)| self.pipelines.insert(desc, pipeline_data))
+            .collect())
+    }
+
+    //empties the registry, handing out all pipelines for destruction
+    pub(crate) fn take_all(&mut self) -> Vec<PipelineData> {
+        self.pipelines.drain().map(|(_, pipeline_data)| pipeline_data).collect()
+    }
+}
+
+impl DeviceResource for PipelineData {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe {
+            ctx.logical_device.destroy_pipeline(self.pipeline, None);
+            ctx.logical_device.destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+    }
+}
+
+fn create_shader_module(logical_device: &Device, spirv: &[u32]) -> Result<ShaderModule, vk::Result> {
+    let shader_module_create_info = ShaderModuleCreateInfo {
+        code_size: std::mem::size_of_val(spirv),
+        p_code: spirv.as_ptr(),
+        ..Default::default()
+    };
+
+    unsafe { logical_device.create_shader_module(&shader_module_create_info, None) }
+}
+
+fn color_blend_attachment_state(blend: BlendMode) -> PipelineColorBlendAttachmentState {
+    let color_write_mask =
+        ColorComponentFlags::R |
+        ColorComponentFlags::G |
+        ColorComponentFlags::B |
+        ColorComponentFlags::A;
+
+    //(src color factor, dst color factor, src alpha factor, dst alpha factor)
+    let factors = match blend {
+        BlendMode::Opaque => {
+            return PipelineColorBlendAttachmentState {
+                color_write_mask,
+                blend_enable: vk::FALSE,
+                ..Default::default()
+            }
+        },
+        BlendMode::Alpha =>
+            (BlendFactor::SRC_ALPHA, BlendFactor::ONE_MINUS_SRC_ALPHA, BlendFactor::ONE, BlendFactor::ONE_MINUS_SRC_ALPHA),
+        BlendMode::Premultiplied =>
+            (BlendFactor::ONE, BlendFactor::ONE_MINUS_SRC_ALPHA, BlendFactor::ONE, BlendFactor::ONE_MINUS_SRC_ALPHA),
+        BlendMode::Additive =>
+            (BlendFactor::SRC_ALPHA, BlendFactor::ONE, BlendFactor::ONE, BlendFactor::ONE)
+    };
+
+    PipelineColorBlendAttachmentState {
+        color_write_mask,
+        blend_enable: vk::TRUE,
+        src_color_blend_factor: factors.0,
+        dst_color_blend_factor: factors.1,
+        color_blend_op: BlendOp::ADD,
+        src_alpha_blend_factor: factors.2,
+        dst_alpha_blend_factor: factors.3,
+        alpha_blend_op: BlendOp::ADD
+    }
+}
+
+//nothing is left behind on failure
+fn create_graphics_pipeline(ctx: &PipelineContext, desc: &PipelineDesc) -> Result<PipelineData, vk::Result> {
+    let logical_device = ctx.logical_device;
+
+    let vert_spirv = ctx.shader_library.spirv(&format!("{}_vert.spv", desc.vertex_shader));
+    let frag_spirv = ctx.shader_library.spirv(&format!("{}_frag.spv", desc.fragment_shader));
+
+    let vert_shader_module = create_shader_module(logical_device, &vert_spirv)?;
+    let frag_shader_module = match create_shader_module(logical_device, &frag_spirv) {
+        Ok(frag_shader_module) => frag_shader_module,
+        Err(err) => {
+            unsafe { logical_device.destroy_shader_module(vert_shader_module, None) };
+            return Err(err)
+        }
+    };
+    //the modules are only needed while the pipeline is created
+    let destroy_shader_modules = || unsafe {
+        logical_device.destroy_shader_module(frag_shader_module, None);
+        logical_device.destroy_shader_module(vert_shader_module, None);
+    };
+
+    let vert_shader_stage_create_info =
+        PipelineShaderStageCreateInfo {
+            stage: ShaderStageFlags::VERTEX,
+            module: vert_shader_module,
+            p_name: c"main".as_ptr(),
+            ..Default::default()
+        };
+
+    let frag_shader_stage_create_info =
+        PipelineShaderStageCreateInfo {
+            stage: ShaderStageFlags::FRAGMENT,
+            module: frag_shader_module,
+            p_name: c"main".as_ptr(),
+            ..Default::default()
+        };
+
+    let shader_stage_create_infos =
+        [vert_shader_stage_create_info, frag_shader_stage_create_info];
+
+    //interleaved attributes of a single vertex buffer binding
+    let mut vertex_attribute_descriptions = Vec::new();
+    let mut vertex_stride = 0;
+    for (location, (format, size)) in desc.vertex_layout.attributes().iter().enumerate() {
+        vertex_attribute_descriptions.push(VertexInputAttributeDescription {
+            location: helper::usize_into_u32(location),
+            binding: 0,
+            format: *format,
+            offset: vertex_stride
+        });
+        vertex_stride += size;
+    }
+    let vertex_binding_descriptions = [VertexInputBindingDescription {
+        binding: 0,
+        stride: vertex_stride,
+        input_rate: VertexInputRate::VERTEX
+    }];
+
+    let vertex_input_create_info = if vertex_attribute_descriptions.is_empty() {
+        PipelineVertexInputStateCreateInfo::default()
+    } else {
+        PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&vertex_binding_descriptions)
+            .vertex_attribute_descriptions(&vertex_attribute_descriptions)
+    };
+
+    let topology = match desc.topology {
+        Topology::TriangleList => PrimitiveTopology::TRIANGLE_LIST,
+        Topology::TriangleStrip => PrimitiveTopology::TRIANGLE_STRIP,
+        Topology::LineList => PrimitiveTopology::LINE_LIST
+    };
+    let input_assembly_create_info =
+        PipelineInputAssemblyStateCreateInfo {
+            topology,
+            primitive_restart_enable: vk::FALSE,
+            ..Default::default()
+        };
+
+    let dynamic_states = [DynamicState::VIEWPORT, DynamicState::SCISSOR];
+    let dynamic_state_create_info =
+        PipelineDynamicStateCreateInfo {
+            dynamic_state_count: helper::usize_into_u32(dynamic_states.len()),
+            p_dynamic_states: dynamic_states.as_ptr(),
+            ..Default::default()
+        };
+
+    let viewport_create_info =
+        PipelineViewportStateCreateInfo {
+            viewport_count: 1,
+            scissor_count: 1,
+            ..Default::default()
+        };
+
+    let rasterization_create_info =
+        PipelineRasterizationStateCreateInfo {
+            //TRUE: fragments beyond the near and far planes are clamped
+            //      to them as opposed to discarding them
+            depth_clamp_enable: vk::FALSE,
+            //TRUE: geometry never passes through rasterizer stage
+            //      -> disables any output to framebuffer
+            rasterizer_discard_enable: vk::FALSE,
+            polygon_mode: PolygonMode::FILL,
+            line_width: 1.0,
+            //2d geometry of any winding (ui meshes)
+            cull_mode: CullModeFlags::NONE,
+            front_face: FrontFace::CLOCKWISE,
+            depth_bias_enable: vk::FALSE,
+            ..Default::default()
+        };
+
+    let (render_pass, samples) = match desc.pass {
+        Pass::Scene => (ctx.render_pass, ctx.samples),
+        Pass::Ui => (ctx.ui_render_pass, SampleCountFlags::TYPE_1),
+        Pass::Offscreen => (ctx.offscreen_render_pass, SampleCountFlags::TYPE_1)
+    };
+    let multisample_create_info =
+        PipelineMultisampleStateCreateInfo {
+            sample_shading_enable: vk::FALSE,
+            rasterization_samples: samples,
+            ..Default::default()
+        };
+
+    //per-attatched-framebuffer configuration
+    let color_blend_attachment_state = color_blend_attachment_state(desc.blend);
+
+    let color_blend_create_info =
+        PipelineColorBlendStateCreateInfo {
+            logic_op_enable: vk::FALSE,
+            attachment_count: 1,
+            p_attachments: &raw const color_blend_attachment_state,
+            ..Default::default()
+        };
+
+    //specification of uniform values in shaders
+    let push_constant_ranges = [PushConstantRange {
+        stage_flags: ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+        offset: 0,
+        size: desc.push_constant_size
+    }];
+    let set_layouts = [ctx.texture_set_layout];
+    let mut pipeline_layout_create_info = PipelineLayoutCreateInfo::default();
+    if desc.push_constant_size > 0 {
+        pipeline_layout_create_info = pipeline_layout_create_info.push_constant_ranges(&push_constant_ranges);
+    }
+    if desc.textured {
+        pipeline_layout_create_info = pipeline_layout_create_info.set_layouts(&set_layouts);
+    }
+
+    let pipeline_layout = match unsafe { logical_device.create_pipeline_layout(&pipeline_layout_create_info, None) } {
+        Ok(pipeline_layout) => pipeline_layout,
+        Err(err) => {
+            destroy_shader_modules();
+            return Err(err)
+        }
+    };
+
+    let graphics_pipeline_create_info =
+        GraphicsPipelineCreateInfo {
+            //vertex and fragment shader-stages
+            stage_count: 2,
+            p_stages: shader_stage_create_infos.as_ptr(),
+            //fixed function stages
+            p_vertex_input_state: &raw const vertex_input_create_info,
+            p_input_assembly_state: &raw const input_assembly_create_info,
+            p_viewport_state: &raw const viewport_create_info,
+            p_rasterization_state: &raw const rasterization_create_info,
+            p_multisample_state: &raw const multisample_create_info,
+            p_color_blend_state: &raw const color_blend_create_info,
+            p_dynamic_state: &raw const dynamic_state_create_info,
+            //pipeline layout
+            layout: pipeline_layout,
+            render_pass,
+            //index of the sub-(render)-pass where
+            //this graphics pipeline will be used
+            subpass: 0,
+            ..Default::default()
+        };
+
+    let pipelines = unsafe {
+        logical_device.create_graphics_pipelines(
+            ctx.pipeline_cache,
+            &[graphics_pipeline_create_info],
+            None
+        )
+    };
+    destroy_shader_modules();
+    let pipeline = match pipelines {
+        Ok(pipelines) => pipelines[0],
+        Err((_, err)) => {
+            unsafe { logical_device.destroy_pipeline_layout(pipeline_layout, None) };
+            return Err(err)
+        }
+    };
+
+    Ok(PipelineData {
+        pipeline_layout,
+        pipeline
+    })
+}
@@ -0,0 +1,202 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use ash::{Device, Instance};
+use ash::vk::{self, PhysicalDevice, PipelineCache, PipelineCacheCreateInfo, PipelineCacheHeaderVersion};
+
+use crate::helper;
+
+const PIPELINE_CACHE_FILE_NAME: &str = "pipeline_cache.bin";
+
+//VkPipelineCacheHeaderVersionOne: size, version, vendor id, device id, uuid
+const HEADER_SIZE: usize = 4 + 4 + 4 + 4 + vk::UUID_SIZE;
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+//checks that cached data was written by the same driver for the same gpu
+//(the driver would reject foreign data anyway, but some drivers crash on it)
+fn validate_header(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> Result<(), String> {
+    if data.len() < HEADER_SIZE {
+        return Err(format!("file too small ({} bytes)", data.len()))
+    }
+
+    let header_size = read_u32(data, 0) as usize;
+    let header_version = PipelineCacheHeaderVersion::from_raw(read_u32(data, 4) as i32);
+    let vendor_id = read_u32(data, 8);
+    let device_id = read_u32(data, 12);
+    let uuid = &data[16..16 + vk::UUID_SIZE];
+
+    if header_size < HEADER_SIZE || header_size > data.len() {
+        return Err(format!("invalid header size {header_size}"))
+    }
+    if header_version != PipelineCacheHeaderVersion::ONE {
+        return Err(format!("unsupported header version {header_version:?}"))
+    }
+    if vendor_id != properties.vendor_id || device_id != properties.device_id {
+        return Err(format!(
+            "written for device {vendor_id:#06x}:{device_id:#06x}, current device is {:#06x}:{:#06x}",
+            properties.vendor_id,
+            properties.device_id
+        ))
+    }
+    if uuid != properties.pipeline_cache_uuid {
+        return Err("pipeline cache uuid mismatch (driver changed)".to_string())
+    }
+
+    Ok(())
+}
+
+//previously saved cache data for this gpu (empty if missing or stale)
+fn load_initial_data(path: &Path, properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            println!("failed reading pipeline cache {}: {err}", path.display());
+            return Vec::new()
+        }
+    };
+
+    match validate_header(&data, properties) {
+        Ok(()) => {
+            println!("loaded pipeline cache ({} bytes)", data.len());
+            data
+        },
+        Err(reason) => {
+            println!("discarding pipeline cache {}: {reason}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+//pipeline cache that is loaded from and saved to the user's cache directory
+//(the default one has no cache object and is never saved)
+#[derive(Default)]
+pub(crate) struct PersistentPipelineCache {
+    pub(crate) pipeline_cache: PipelineCache,
+    //none if no cache directory could be determined
+    path: Option<PathBuf>
+}
+impl PersistentPipelineCache {
+    pub(crate) fn new(instance: &Instance, physical_device: &PhysicalDevice, logical_device: &Device) -> Result<Self, vk::Result> {
+        let properties = unsafe { instance.get_physical_device_properties(*physical_device) };
+        let path = helper::cache_dir().map(|dir| dir.join(PIPELINE_CACHE_FILE_NAME));
+
+        let initial_data = path
+            .as_deref()
+            .map(|path| load_initial_data(path, &properties))
+            .unwrap_or_default();
+
+        let pipeline_cache_create_info = PipelineCacheCreateInfo::default()
+            .initial_data(&initial_data);
+
+        let pipeline_cache = unsafe {
+            logical_device.create_pipeline_cache(&pipeline_cache_create_info, None)?
+        };
+
+        Ok(Self { pipeline_cache, path })
+    }
+
+    //writes the cache to disk (write to temp file + rename,
+    //so an interrupted save never leaves a truncated cache behind)
+    pub(crate) fn save(&self, logical_device: &Device) {
+        let Some(path) = self.path.as_ref() else { return };
+
+        let data = match unsafe { logical_device.get_pipeline_cache_data(self.pipeline_cache) } {
+            Ok(data) => data,
+            Err(err) => {
+                println!("failed getting pipeline cache data: {err}");
+                return
+            }
+        };
+
+        let tmp_path = path.with_extension("tmp");
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp_path, &data))
+            .and_then(|_| fs::rename(&tmp_path, path));
+
+        match result {
+            Ok(()) => println!("saved pipeline cache ({} bytes)", data.len()),
+            Err(err) => println!("failed saving pipeline cache {}: {err}", path.display())
+        }
+    }
+
+    pub(crate) fn destroy(&self, logical_device: &Device) {
+        unsafe { logical_device.destroy_pipeline_cache(self.pipeline_cache, None) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties() -> vk::PhysicalDeviceProperties {
+        vk::PhysicalDeviceProperties {
+            vendor_id: 0x10de,
+            device_id: 0x2484,
+            pipeline_cache_uuid: [7; vk::UUID_SIZE],
+            ..Default::default()
+        }
+    }
+
+    //header written by the driver of `properties`, followed by some cache data
+    fn header(properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend((HEADER_SIZE as u32).to_le_bytes());
+        data.extend((PipelineCacheHeaderVersion::ONE.as_raw() as u32).to_le_bytes());
+        data.extend(properties.vendor_id.to_le_bytes());
+        data.extend(properties.device_id.to_le_bytes());
+        data.extend(properties.pipeline_cache_uuid);
+        data.extend([0xab; 64]);
+        data
+    }
+
+    #[test]
+    fn accepts_matching_header() {
+        assert_eq!(validate_header(&header(&properties()), &properties()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_short_data() {
+        let data = header(&properties());
+        assert!(validate_header(&[], &properties()).is_err());
+        assert!(validate_header(&data[..HEADER_SIZE - 1], &properties()).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_header_size() {
+        let mut data = header(&properties());
+        data[0..4].copy_from_slice(&(HEADER_SIZE as u32 - 1).to_le_bytes());
+        assert!(validate_header(&data, &properties()).is_err());
+        //larger than the file
+        let too_large = data.len() as u32 + 1;
+        data[0..4].copy_from_slice(&too_large.to_le_bytes());
+        assert!(validate_header(&data, &properties()).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_header_version() {
+        let mut data = header(&properties());
+        data[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert!(validate_header(&data, &properties()).is_err());
+    }
+
+    #[test]
+    fn rejects_other_device() {
+        let data = header(&properties());
+        let other_vendor = vk::PhysicalDeviceProperties { vendor_id: 0x1002, ..properties() };
+        let other_device = vk::PhysicalDeviceProperties { device_id: 0x2485, ..properties() };
+        assert!(validate_header(&data, &other_vendor).is_err());
+        assert!(validate_header(&data, &other_device).is_err());
+    }
+
+    #[test]
+    fn rejects_other_driver() {
+        let data = header(&properties());
+        let updated = vk::PhysicalDeviceProperties { pipeline_cache_uuid: [8; vk::UUID_SIZE], ..properties() };
+        assert!(validate_header(&data, &updated).unwrap_err().contains("uuid"));
+    }
+}
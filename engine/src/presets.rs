@@ -0,0 +1,351 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::crosshair::{Color, Crosshair, Shape};
+use crate::helper;
+use crate::property;
+
+const PROFILE_EXTENSION: &str = "profile";
+//edge length of preview thumbnails (physical pixels, 1:1 with the screen)
+pub const THUMBNAIL_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetSource {
+    //shipped with custicle
+    Builtin,
+    //file in the profile directory
+    Profile(PathBuf)
+}
+
+//named crosshair the user can start from
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    pub source: PresetSource,
+    pub crosshair: Crosshair
+}
+impl Preset {
+    //replaces the look of `crosshair`, its offset stays
+    //(it belongs to the screen / game, not to the preset)
+    pub fn apply_to(&self, crosshair: &mut Crosshair) {
+        *crosshair = Crosshair {
+            offset_x: crosshair.offset_x,
+            offset_y: crosshair.offset_y,
+            ..self.crosshair.clone()
+        };
+    }
+}
+
+fn builtin(name: &str, description: &str, crosshair: Crosshair) -> Preset {
+    Preset { name: name.to_string(), description: description.to_string(), source: PresetSource::Builtin, crosshair }
+}
+
+pub fn builtin_presets() -> Vec<Preset> {
+    const CYAN: Color = Color::rgba(0.0, 1.0, 1.0, 1.0);
+    const YELLOW: Color = Color::rgba(1.0, 1.0, 0.0, 1.0);
+    const WHITE: Color = Color::rgba(1.0, 1.0, 1.0, 1.0);
+    vec![
+        builtin("classic", "four arms with a gap and an outline", Crosshair::default()),
+        builtin("dot", "a single outlined dot", Crosshair {
+            arm_length: 0.0,
+            dot: true,
+            dot_size: 4.0,
+            ..Crosshair::default()
+        }),
+        builtin("t-shape", "cross without the top arm", Crosshair {
+            shape: Shape::T,
+            ..Crosshair::default()
+        }),
+        builtin("circle-dot", "thin ring around a dot", Crosshair {
+            arm_length: 0.0,
+            arm_thickness: 1.0,
+            dot: true,
+            dot_size: 2.0,
+            circle: true,
+            circle_radius: 12.0,
+            ..Crosshair::default()
+        }),
+        builtin("chevron", "two diagonal arms below the center", Crosshair {
+            shape: Shape::Chevron,
+            arm_length: 6.0,
+            gap: 0.0,
+            ..Crosshair::default()
+        }),
+        builtin("pro-tight", "small cyan cross with a one pixel gap, popular in tactical shooters", Crosshair {
+            arm_length: 3.0,
+            arm_thickness: 1.0,
+            gap: 1.0,
+            color: CYAN,
+            ..Crosshair::default()
+        }),
+        builtin("pro-static", "short thick yellow arms without an outline", Crosshair {
+            arm_length: 4.0,
+            arm_thickness: 2.0,
+            gap: 1.0,
+            color: YELLOW,
+            outline: false,
+            ..Crosshair::default()
+        }),
+        builtin("pro-dot-cross", "thin white cross around a single pixel dot", Crosshair {
+            arm_length: 5.0,
+            arm_thickness: 1.0,
+            gap: 2.0,
+            color: WHITE,
+            dot: true,
+            dot_size: 1.0,
+            ..Crosshair::default()
+        })
+    ]
+}
+
+//profile names become file names: lowercase letters, digits, '-' and '_'
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid { Ok(()) } else { Err(format!("invalid profile name {name:?} (use a-z, 0-9, '-' and '_')")) }
+}
+
+//"<property> <value>" lines, unset properties keep their defaults
+pub fn parse_profile(text: &str) -> Result<Crosshair, String> {
+    let mut crosshair = Crosshair::default();
+    for (index, line) in text.lines().enumerate().map(|(index, line)| (index + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        property::read_property(&mut crosshair, line).map_err(|err| format!("line {index}: {err}"))?;
+    }
+    Ok(crosshair)
+}
+
+//built-in presets plus the user's profiles
+#[derive(Default)]
+pub struct PresetLibrary {
+    //none if it can't be determined (only built-ins then)
+    profile_dir: Option<PathBuf>
+}
+impl PresetLibrary {
+    pub fn new(profile_dir: Option<PathBuf>) -> Self {
+        Self { profile_dir }
+    }
+
+    //"profiles" in the user's config directory
+    pub fn default_profile_dir() -> Option<PathBuf> {
+        helper::config_dir().map(|dir| dir.join("profiles"))
+    }
+
+    pub fn profile_dir(&self) -> Option<&Path> {
+        self.profile_dir.as_deref()
+    }
+
+    //built-ins first, then profiles by name (unreadable profiles are printed and skipped)
+    pub fn list(&self) -> Vec<Preset> {
+        let mut presets = builtin_presets();
+        presets.extend(self.profiles());
+        presets
+    }
+
+    fn profiles(&self) -> Vec<Preset> {
+        let Some(entries) = self.profile_dir.as_deref().and_then(|dir| fs::read_dir(dir).ok()) else {
+            return Vec::new()
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == PROFILE_EXTENSION))
+            .collect();
+        paths.sort();
+
+        paths.into_iter().filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            match fs::read_to_string(&path).map_err(|err| err.to_string()).and_then(|text| parse_profile(&text)) {
+                Ok(crosshair) => Some(Preset { name, description: "profile".to_string(), source: PresetSource::Profile(path), crosshair }),
+                Err(err) => {
+                    println!("skipping profile {}: {err}", path.display());
+                    None
+                }
+            }
+        }).collect()
+    }
+
+    //profiles shadow built-ins of the same name
+    pub fn load(&self, name: &str) -> Result<Preset, String> {
+        let mut presets = self.list();
+        let index = presets
+            .iter()
+            .rposition(|preset| preset.name == name)
+            .ok_or_else(|| format!("no preset named {name:?}"))?;
+        Ok(presets.swap_remove(index))
+    }
+
+    fn profile_path(&self, name: &str) -> Result<PathBuf, String> {
+        check_name(name)?;
+        let dir = self.profile_dir.as_deref().ok_or("no profile directory (set $XDG_CONFIG_HOME or $HOME)")?;
+        Ok(dir.join(name).with_extension(PROFILE_EXTENSION))
+    }
+
+    //"<base>-copy", "<base>-copy-2", ... (first one without a profile)
+    pub fn unused_name(&self, base: &str) -> String {
+        let taken = |name: &str| self.profile_path(name).is_ok_and(|path| path.exists());
+        let mut name = format!("{base}-copy");
+        let mut number = 2;
+        while taken(&name) {
+            name = format!("{base}-copy-{number}");
+            number += 1;
+        }
+        name
+    }
+
+    //writes preset `name` as profile `new_name` (existing profiles aren't overwritten)
+    pub fn clone_preset(&self, name: &str, new_name: &str) -> Result<PathBuf, String> {
+        let preset = self.load(name)?;
+        let path = self.profile_path(new_name)?;
+        if path.exists() {
+            return Err(format!("profile {new_name:?} already exists"))
+        }
+        let text = format!("# cloned from {name}\n{}", property::write_properties(&preset.crosshair));
+        write_atomically(&path, text.as_bytes()).map_err(|err| format!("failed writing {}: {err}", path.display()))?;
+        Ok(path)
+    }
+}
+
+//temp file + rename, so readers never see a partial file
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    path.parent().map_or(Ok(()), fs::create_dir_all)?;
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)
+}
+
+//rendered previews in the user's cache directory, keyed by the crosshair's properties
+#[derive(Default)]
+pub struct ThumbnailCache {
+    //none: thumbnails are rendered every time
+    dir: Option<PathBuf>
+}
+impl ThumbnailCache {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    //"thumbnails" in the user's cache directory
+    pub fn default_dir() -> Option<PathBuf> {
+        helper::cache_dir().map(|dir| dir.join("thumbnails"))
+    }
+
+    //THUMBNAIL_SIZE² premultiplied rgba8 (srgb) pixels of the centered crosshair,
+    //`render` is only called on a cache miss (failures are printed)
+    pub fn get_or_render(&self, crosshair: &Crosshair, render: impl FnOnce(&Crosshair, u32) -> Result<Vec<u8>, String>) -> Option<Vec<u8>> {
+        let centered = Crosshair { offset_x: 0.0, offset_y: 0.0, ..crosshair.clone() };
+        let path = self.dir.as_deref().map(|dir| {
+            let key = fnv1a(format!("{THUMBNAIL_SIZE}\n{}", property::write_properties(&centered)).as_bytes());
+            dir.join(format!("{key:016x}.pam"))
+        });
+
+        if let Some(pixels) = path.as_deref().and_then(|path| fs::read(path).ok()).and_then(|bytes| read_pam(&bytes, THUMBNAIL_SIZE)) {
+            return Some(pixels)
+        }
+        let pixels = match render(&centered, THUMBNAIL_SIZE) {
+            Ok(pixels) => pixels,
+            Err(err) => {
+                println!("failed rendering thumbnail: {err}");
+                return None
+            }
+        };
+        if let Some(path) = path.as_deref()
+            && let Err(err) = write_atomically(path, &write_pam(&pixels, THUMBNAIL_SIZE)) {
+            println!("failed caching thumbnail {}: {err}", path.display());
+        }
+        Some(pixels)
+    }
+}
+
+//stable across runs and toolchains (unlike DefaultHasher)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+//netpbm PAM header, followed by the raw rgba rows
+fn pam_header(size: u32) -> String {
+    format!("P7\nWIDTH {size}\nHEIGHT {size}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n")
+}
+
+fn write_pam(pixels: &[u8], size: u32) -> Vec<u8> {
+    let mut bytes = pam_header(size).into_bytes();
+    bytes.extend_from_slice(pixels);
+    bytes
+}
+
+//none unless it is exactly what write_pam produces for `size`
+fn read_pam(bytes: &[u8], size: u32) -> Option<Vec<u8>> {
+    let pixels = bytes.strip_prefix(pam_header(size).as_bytes())?;
+    (pixels.len() == (size * size * 4) as usize).then(|| pixels.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //empty directory unique to the test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("custicle-presets-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn profile_names_are_file_names() {
+        assert!(check_name("my-classic_2").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("../classic").is_err());
+        assert!(check_name("a/b").is_err());
+        assert!(check_name("a\\b").is_err());
+        assert!(check_name("Classic").is_err());
+        assert!(check_name("my classic").is_err());
+    }
+
+    #[test]
+    fn profile_keeps_defaults() {
+        let crosshair = parse_profile("# comment\n\ngap 7\ntrack gap once 0=1 1=2\n").unwrap();
+        assert_eq!(crosshair.gap, 7.0);
+        assert_eq!(crosshair.arm_length, Crosshair::default().arm_length);
+        assert_eq!(crosshair.animation.len(), 1);
+    }
+
+    #[test]
+    fn profile_errors_name_the_line() {
+        assert!(parse_profile("gap 7\nwidth 3").unwrap_err().starts_with("line 2:"));
+        assert!(parse_profile("gap wide").is_err());
+    }
+
+    #[test]
+    fn builtins_survive_a_profile_round_trip() {
+        for preset in builtin_presets() {
+            assert_eq!(parse_profile(&property::write_properties(&preset.crosshair)).unwrap(), preset.crosshair, "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn pam_round_trip() {
+        let pixels: Vec<u8> = (0..4 * 4 * 4).map(|byte| byte as u8).collect();
+        let bytes = write_pam(&pixels, 4);
+        assert_eq!(read_pam(&bytes, 4), Some(pixels));
+        //other size or truncated
+        assert_eq!(read_pam(&bytes, 8), None);
+        assert_eq!(read_pam(&bytes[..bytes.len() - 1], 4), None);
+    }
+
+    #[test]
+    fn unused_name_skips_existing_profiles() {
+        let dir = scratch_dir("unused-name");
+        let library = PresetLibrary::new(Some(dir.clone()));
+        assert_eq!(library.unused_name("classic"), "classic-copy");
+
+        library.clone_preset("classic", "classic-copy").unwrap();
+        library.clone_preset("classic", "classic-copy-2").unwrap();
+        assert_eq!(library.unused_name("classic"), "classic-copy-3");
+        //existing profiles aren't overwritten
+        assert!(library.clone_preset("dot", "classic-copy").is_err());
+        assert_eq!(library.load("classic-copy").unwrap().crosshair, Crosshair::default());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
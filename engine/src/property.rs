@@ -0,0 +1,285 @@
+use std::fmt;
+
+use crate::animation::Track;
+use crate::crosshair::{Color, Crosshair, ExtraPixelSide, RenderMode, Shape};
+
+//first word of keyframe track lines in profiles / sessions
+const TRACK_KEYWORD: &str = "track";
+const ANIMATION_RATE_KEYWORD: &str = "animation_rate";
+
+//editable crosshair setting (edit history, keyboard editing, profiles)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Property {
+    Shape,
+    ArmLength,
+    ArmThickness,
+    Gap,
+    Color,
+    Outline,
+    OutlineThickness,
+    OutlineColor,
+    Dot,
+    DotSize,
+    Circle,
+    CircleRadius,
+    RenderMode,
+    OffsetX,
+    OffsetY,
+    Rotation,
+    Opacity,
+    HueShift
+}
+impl Property {
+    pub const ALL: [Self; 18] = [
+        Self::Shape,
+        Self::ArmLength,
+        Self::ArmThickness,
+        Self::Gap,
+        Self::Color,
+        Self::Outline,
+        Self::OutlineThickness,
+        Self::OutlineColor,
+        Self::Dot,
+        Self::DotSize,
+        Self::Circle,
+        Self::CircleRadius,
+        Self::RenderMode,
+        Self::OffsetX,
+        Self::OffsetY,
+        Self::Rotation,
+        Self::Opacity,
+        Self::HueShift
+    ];
+
+    //name used in files
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Shape => "shape",
+            Self::ArmLength => "arm_length",
+            Self::ArmThickness => "arm_thickness",
+            Self::Gap => "gap",
+            Self::Color => "color",
+            Self::Outline => "outline",
+            Self::OutlineThickness => "outline_thickness",
+            Self::OutlineColor => "outline_color",
+            Self::Dot => "dot",
+            Self::DotSize => "dot_size",
+            Self::Circle => "circle",
+            Self::CircleRadius => "circle_radius",
+            Self::RenderMode => "render_mode",
+            Self::OffsetX => "offset_x",
+            Self::OffsetY => "offset_y",
+            Self::Rotation => "rotation",
+            Self::Opacity => "opacity",
+            Self::HueShift => "hue_shift"
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|property| property.name() == name.trim())
+    }
+
+    pub fn get(self, crosshair: &Crosshair) -> PropertyValue {
+        match self {
+            Self::Shape => PropertyValue::Shape(crosshair.shape),
+            Self::ArmLength => PropertyValue::Number(crosshair.arm_length),
+            Self::ArmThickness => PropertyValue::Number(crosshair.arm_thickness),
+            Self::Gap => PropertyValue::Number(crosshair.gap),
+            Self::Color => PropertyValue::Color(crosshair.color),
+            Self::Outline => PropertyValue::Toggle(crosshair.outline),
+            Self::OutlineThickness => PropertyValue::Number(crosshair.outline_thickness),
+            Self::OutlineColor => PropertyValue::Color(crosshair.outline_color),
+            Self::Dot => PropertyValue::Toggle(crosshair.dot),
+            Self::DotSize => PropertyValue::Number(crosshair.dot_size),
+            Self::Circle => PropertyValue::Toggle(crosshair.circle),
+            Self::CircleRadius => PropertyValue::Number(crosshair.circle_radius),
+            Self::RenderMode => PropertyValue::RenderMode(crosshair.render_mode),
+            Self::OffsetX => PropertyValue::Number(crosshair.offset_x),
+            Self::OffsetY => PropertyValue::Number(crosshair.offset_y),
+            Self::Rotation => PropertyValue::Number(crosshair.rotation),
+            Self::Opacity => PropertyValue::Number(crosshair.opacity),
+            Self::HueShift => PropertyValue::Number(crosshair.hue_shift)
+        }
+    }
+
+    //values of the wrong kind are ignored
+    pub fn set(self, crosshair: &mut Crosshair, value: PropertyValue) {
+        match (self, value) {
+            (Self::Shape, PropertyValue::Shape(shape)) => crosshair.shape = shape,
+            (Self::ArmLength, PropertyValue::Number(number)) => crosshair.arm_length = number,
+            (Self::ArmThickness, PropertyValue::Number(number)) => crosshair.arm_thickness = number,
+            (Self::Gap, PropertyValue::Number(number)) => crosshair.gap = number,
+            (Self::Color, PropertyValue::Color(color)) => crosshair.color = color,
+            (Self::Outline, PropertyValue::Toggle(toggle)) => crosshair.outline = toggle,
+            (Self::OutlineThickness, PropertyValue::Number(number)) => crosshair.outline_thickness = number,
+            (Self::OutlineColor, PropertyValue::Color(color)) => crosshair.outline_color = color,
+            (Self::Dot, PropertyValue::Toggle(toggle)) => crosshair.dot = toggle,
+            (Self::DotSize, PropertyValue::Number(number)) => crosshair.dot_size = number,
+            (Self::Circle, PropertyValue::Toggle(toggle)) => crosshair.circle = toggle,
+            (Self::CircleRadius, PropertyValue::Number(number)) => crosshair.circle_radius = number,
+            (Self::RenderMode, PropertyValue::RenderMode(render_mode)) => crosshair.render_mode = render_mode,
+            (Self::OffsetX, PropertyValue::Number(number)) => crosshair.offset_x = number,
+            (Self::OffsetY, PropertyValue::Number(number)) => crosshair.offset_y = number,
+            (Self::Rotation, PropertyValue::Number(number)) => crosshair.rotation = number,
+            (Self::Opacity, PropertyValue::Number(number)) => crosshair.opacity = number,
+            (Self::HueShift, PropertyValue::Number(number)) => crosshair.hue_shift = number,
+            (property, value) => println!("ignoring {value:?} for {}", property.name())
+        }
+    }
+
+    //parses a value of the kind this property holds
+    pub fn parse_value(self, value: &str) -> Option<PropertyValue> {
+        let value = value.trim();
+        match self.get(&Crosshair::default()) {
+            PropertyValue::Number(_) => value.parse().ok().map(PropertyValue::Number),
+            PropertyValue::Toggle(_) => value.parse().ok().map(PropertyValue::Toggle),
+            PropertyValue::Color(_) => {
+                let components: Vec<f32> = value.split(',').map(|component| component.trim().parse().ok()).collect::<Option<_>>()?;
+                let [r, g, b, a] = components.try_into().ok()?;
+                Some(PropertyValue::Color(Color::rgba(r, g, b, a)))
+            },
+            PropertyValue::Shape(_) => match value {
+                "cross" => Some(Shape::Cross),
+                "t" => Some(Shape::T),
+                "chevron" => Some(Shape::Chevron),
+                _ => None
+            }.map(PropertyValue::Shape),
+            PropertyValue::RenderMode(_) => match value {
+                "smooth" => Some(RenderMode::Smooth),
+                "pixel-perfect" | "pixel-perfect-after" => Some(RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::After }),
+                "pixel-perfect-before" => Some(RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::Before }),
+                _ => None
+            }.map(PropertyValue::RenderMode)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyValue {
+    Number(f32),
+    Toggle(bool),
+    Color(Color),
+    Shape(Shape),
+    RenderMode(RenderMode)
+}
+//file representation (never contains whitespace)
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Toggle(toggle) => write!(f, "{toggle}"),
+            Self::Color(color) => write!(f, "{},{},{},{}", color.r, color.g, color.b, color.a),
+            Self::Shape(Shape::Cross) => write!(f, "cross"),
+            Self::Shape(Shape::T) => write!(f, "t"),
+            Self::Shape(Shape::Chevron) => write!(f, "chevron"),
+            Self::RenderMode(RenderMode::Smooth) => write!(f, "smooth"),
+            Self::RenderMode(RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::After }) => write!(f, "pixel-perfect-after"),
+            Self::RenderMode(RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::Before }) => write!(f, "pixel-perfect-before")
+        }
+    }
+}
+
+//keyframe tracks and animation rate of a crosshair, they aren't properties
+//and are replaced as a whole (presets, profile switches)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnimationSettings {
+    pub tracks: Vec<Track>,
+    pub rate: Option<f32>
+}
+impl AnimationSettings {
+    pub fn get(crosshair: &Crosshair) -> Self {
+        Self { tracks: crosshair.animation.clone(), rate: crosshair.animation_rate }
+    }
+
+    pub fn set(&self, crosshair: &mut Crosshair) {
+        crosshair.animation = self.tracks.clone();
+        crosshair.animation_rate = self.rate;
+    }
+
+    //"track <track>" lines followed by an "animation_rate <fps>" line
+    pub fn write(&self) -> String {
+        let tracks = self.tracks.iter().map(|track| format!("{TRACK_KEYWORD} {track}\n"));
+        //only written if set, the monitor's refresh rate is the default
+        let rate = self.rate.map(|rate| format!("{ANIMATION_RATE_KEYWORD} {rate}\n"));
+        tracks.chain(rate).collect()
+    }
+
+    //applies a single "track <track>" or "animation_rate <fps>" line
+    //(a track replaces an earlier one of the same property)
+    pub fn read_line(&mut self, line: &str) -> Result<(), String> {
+        match line.trim().split_once(char::is_whitespace) {
+            Some((TRACK_KEYWORD, value)) => {
+                let track = Track::parse(value)?;
+                self.tracks.retain(|existing| existing.property != track.property);
+                self.tracks.push(track);
+                Ok(())
+            },
+            Some((ANIMATION_RATE_KEYWORD, value)) => {
+                let rate = value.trim().parse::<f32>().ok().filter(|rate| rate.is_finite() && *rate > 0.0);
+                self.rate = Some(rate.ok_or_else(|| format!("invalid animation rate {value:?}"))?);
+                Ok(())
+            },
+            _ => Err(format!("expected \"{TRACK_KEYWORD} <track>\" or \"{ANIMATION_RATE_KEYWORD} <fps>\": {line:?}"))
+        }
+    }
+}
+
+//all properties as "<name> <value>" lines, followed by the animation settings
+pub fn write_properties(crosshair: &Crosshair) -> String {
+    let properties: String = Property::ALL
+        .iter()
+        .map(|property| format!("{} {}\n", property.name(), property.get(crosshair)))
+        .collect();
+    properties + &AnimationSettings::get(crosshair).write()
+}
+
+//applies a single "<name> <value>", "track <track>" or "animation_rate <fps>" line
+//(a track replaces an earlier one of the same property)
+pub fn read_property(crosshair: &mut Crosshair, line: &str) -> Result<(), String> {
+    let (name, value) = line.trim().split_once(char::is_whitespace).ok_or_else(|| format!("expected \"<property> <value>\": {line:?}"))?;
+    if name == TRACK_KEYWORD || name == ANIMATION_RATE_KEYWORD {
+        let mut animation = AnimationSettings::get(crosshair);
+        animation.read_line(line)?;
+        animation.set(crosshair);
+        return Ok(())
+    }
+    let property = Property::parse(name).ok_or_else(|| format!("unknown property {name:?}"))?;
+    let value = property.parse_value(value).ok_or_else(|| format!("invalid value for {name}: {value:?}"))?;
+    property.set(crosshair, value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Looping;
+
+    fn read(text: &str) -> Crosshair {
+        let mut crosshair = Crosshair::default();
+        for line in text.lines() {
+            read_property(&mut crosshair, line).unwrap();
+        }
+        crosshair
+    }
+
+    #[test]
+    fn properties_round_trip() {
+        let mut crosshair = Crosshair { gap: 7.0, rotation: 45.0, animation_rate: Some(30.0), ..Default::default() };
+        crosshair.animation.push(Track::parse("opacity repeat 0=1 1=0.5:smooth").unwrap());
+        assert_eq!(read(&write_properties(&crosshair)), crosshair);
+    }
+
+    #[test]
+    fn animation_rate_is_optional() {
+        assert!(!write_properties(&Crosshair::default()).contains(ANIMATION_RATE_KEYWORD));
+        assert_eq!(read("animation_rate 144").animation_rate, Some(144.0));
+        assert!(read_property(&mut Crosshair::default(), "animation_rate 0").is_err());
+    }
+
+    #[test]
+    fn later_tracks_replace_earlier_ones() {
+        let crosshair = read("track gap once 0=1 1=2\ntrack gap repeat 0=3 1=4");
+        assert_eq!(crosshair.animation.len(), 1);
+        assert_eq!(crosshair.animation[0].looping, Looping::Repeat);
+    }
+}
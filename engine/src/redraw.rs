@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+//decides when the overlay has to render a new frame
+//
+//a static crosshair is only redrawn when something changed (dirty flag),
+//animated content registers itself with a target frame rate and keeps
+//the loop running at that rate until it unregisters
+#[derive(Debug)]
+pub struct RedrawScheduler {
+    //something visible changed since the last frame
+    dirty: bool,
+    //active animations and their target frame rate (frames per second)
+    animations: HashMap<&'static str, f32>,
+    //single frame requested for a later point in time
+    scheduled: Option<Instant>,
+    //time the last frame was rendered
+    last_frame: Option<Instant>
+}
+impl Default for RedrawScheduler {
+    fn default() -> Self {
+        //the first frame always has to be rendered
+        Self { dirty: true, animations: HashMap::new(), scheduled: None, last_frame: None }
+    }
+}
+impl RedrawScheduler {
+    //requests a single new frame (config change, resize, ...)
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    //requests a single new frame at `at` (the earliest request wins)
+    pub fn schedule(&mut self, at: Instant) {
+        self.scheduled = Some(self.scheduled.map_or(at, |scheduled| scheduled.min(at)));
+    }
+
+    //requests a frame `delay` after `now` (egui's repaint delay,
+    //Duration::MAX: no frame at all)
+    pub fn repaint_after(&mut self, now: Instant, delay: Duration) {
+        if delay.is_zero() {
+            self.mark_dirty();
+        } else if let Some(at) = now.checked_add(delay) {
+            self.schedule(at);
+        }
+    }
+
+    //keeps rendering at `frames_per_second` until `stop_animation(id)`
+    //(with several animations the highest rate wins)
+    pub fn start_animation(&mut self, id: &'static str, frames_per_second: f32) {
+        self.animations.insert(id, frames_per_second.max(1.0));
+    }
+
+    pub fn stop_animation(&mut self, id: &'static str) {
+        self.animations.remove(id);
+    }
+
+    pub fn is_animating(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    fn frame_interval(&self) -> Option<Duration> {
+        self.animations
+            .values()
+            .copied()
+            .reduce(f32::max)
+            .map(|frames_per_second| Duration::from_secs_f32(1.0 / frames_per_second))
+    }
+
+    //time the next animation frame is due (none if nothing is animated)
+    fn next_animation_frame(&self) -> Option<Instant> {
+        let interval = self.frame_interval()?;
+        Some(match self.last_frame {
+            Some(last_frame) => last_frame + interval,
+            None => Instant::now()
+        })
+    }
+
+    //true if a frame should be requested now
+    pub fn should_redraw(&self, now: Instant) -> bool {
+        self.dirty
+            || self.scheduled.is_some_and(|scheduled| scheduled <= now)
+            || self.next_animation_frame().is_some_and(|next_frame| next_frame <= now)
+    }
+
+    //time the event loop has to wake up for the next frame
+    //(none: sleep until the next event)
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        if self.dirty {
+            return None
+        }
+        match (self.scheduled, self.next_animation_frame()) {
+            (Some(scheduled), Some(next_frame)) => Some(scheduled.min(next_frame)),
+            (scheduled, next_frame) => scheduled.or(next_frame)
+        }
+    }
+
+    //to be called after a frame was rendered
+    pub fn frame_rendered(&mut self, now: Instant) {
+        self.dirty = false;
+        if self.scheduled.is_some_and(|scheduled| scheduled <= now) {
+            self.scheduled = None;
+        }
+        self.last_frame = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dirty_renders_once_then_idles() {
+        let mut scheduler = RedrawScheduler::default();
+        let now = Instant::now();
+        assert!(scheduler.should_redraw(now));
+        scheduler.frame_rendered(now);
+        assert!(!scheduler.should_redraw(now));
+        assert_eq!(scheduler.next_wakeup(), None);
+
+        scheduler.mark_dirty();
+        assert!(scheduler.should_redraw(now));
+        scheduler.frame_rendered(now);
+        assert!(!scheduler.should_redraw(now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn animation_keeps_deadlines() {
+        let mut scheduler = RedrawScheduler::default();
+        scheduler.start_animation("spin", 50.0);
+        scheduler.start_animation("pulse", 100.0);
+        let interval = Duration::from_secs_f32(1.0 / 100.0);
+
+        let mut now = Instant::now();
+        for _ in 0..3 {
+            scheduler.frame_rendered(now);
+            assert_eq!(scheduler.next_wakeup(), Some(now + interval));
+            assert!(!scheduler.should_redraw(now));
+            now += interval;
+            assert!(scheduler.should_redraw(now));
+        }
+
+        scheduler.stop_animation("spin");
+        scheduler.stop_animation("pulse");
+        assert!(!scheduler.is_animating());
+        scheduler.frame_rendered(now);
+        assert_eq!(scheduler.next_wakeup(), None);
+    }
+
+    #[test]
+    fn earliest_schedule_wins() {
+        let mut scheduler = RedrawScheduler::default();
+        let now = Instant::now();
+        scheduler.frame_rendered(now);
+        scheduler.schedule(now + Duration::from_secs(2));
+        scheduler.schedule(now + Duration::from_secs(1));
+        assert_eq!(scheduler.next_wakeup(), Some(now + Duration::from_secs(1)));
+
+        scheduler.frame_rendered(now + Duration::from_secs(1));
+        assert_eq!(scheduler.next_wakeup(), None);
+    }
+
+    #[test]
+    fn repaint_delays() {
+        let mut scheduler = RedrawScheduler::default();
+        let now = Instant::now();
+        scheduler.frame_rendered(now);
+
+        //egui is idle
+        scheduler.repaint_after(now, Duration::MAX);
+        assert_eq!(scheduler.next_wakeup(), None);
+        assert!(!scheduler.should_redraw(now));
+
+        scheduler.repaint_after(now, Duration::from_millis(500));
+        assert_eq!(scheduler.next_wakeup(), Some(now + Duration::from_millis(500)));
+
+        scheduler.repaint_after(now, Duration::ZERO);
+        assert!(scheduler.should_redraw(now));
+    }
+}
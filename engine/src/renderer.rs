@@ -1,13 +1,12 @@
-use std::borrow::Cow;
 use std::collections::BTreeSet;
-use std::ffi::{self, CStr, c_char};
-use std::fs;
-use std::io::Cursor;
+use std::ffi::{CStr, c_char};
 use std::os::raw::c_void;
-use ash::util::read_spv;
-use winit::dpi::LogicalSize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 use winit::event_loop::ActiveEventLoop;
 use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use ash::prelude::VkResult;
 use ash::{
     Entry,
     Instance,
@@ -15,48 +14,24 @@ use ash::{
 };
 use ash::ext::debug_utils;
 use ash::vk::{
-    self, AttachmentDescription, AttachmentLoadOp, AttachmentReference, AttachmentStoreOp, ColorComponentFlags, ColorSpaceKHR, ComponentMapping, ComponentSwizzle, CompositeAlphaFlagsKHR, CullModeFlags, DebugUtilsMessageSeverityFlagsEXT, DebugUtilsMessageTypeFlagsEXT, DebugUtilsMessengerEXT, DeviceCreateInfo, DeviceQueueCreateInfo, DynamicState, Extent2D, Format, FrontFace, GraphicsPipelineCreateInfo, ImageAspectFlags, ImageLayout, ImageSubresourceRange, ImageUsageFlags, ImageView, ImageViewCreateInfo, ImageViewType, PhysicalDevice, PhysicalDeviceFeatures, Pipeline, PipelineBindPoint, PipelineCache, PipelineColorBlendAttachmentState, PipelineColorBlendStateCreateInfo, PipelineDynamicStateCreateInfo, PipelineInputAssemblyStateCreateInfo, PipelineLayout, PipelineLayoutCreateInfo, PipelineMultisampleStateCreateInfo, PipelineRasterizationStateCreateInfo, PipelineShaderStageCreateInfo, PipelineVertexInputStateCreateInfo, PipelineViewportStateCreateInfo, PolygonMode, PresentModeKHR, PrimitiveTopology, Queue, QueueFlags, RenderPass, RenderPassCreateInfo, SampleCountFlags, ShaderModule, ShaderModuleCreateInfo, ShaderStageFlags, SharingMode, SubpassDescription, SurfaceCapabilitiesKHR, SurfaceFormatKHR, SurfaceKHR, SwapchainCreateInfoKHR, SwapchainKHR
+    self, AccessFlags, DescriptorSetLayout, AttachmentDescription, AttachmentLoadOp, AttachmentReference, AttachmentStoreOp, ClearColorValue, ClearValue, ColorSpaceKHR, CommandBuffer, CommandBufferAllocateInfo, CommandBufferBeginInfo, CommandBufferLevel, CommandBufferResetFlags, CommandBufferUsageFlags, CommandPool, CommandPoolCreateFlags, CommandPoolCreateInfo, ComponentMapping, ComponentSwizzle, CompositeAlphaFlagsKHR, DeviceCreateInfo, DeviceQueueCreateInfo, Extent2D, Fence, FenceCreateFlags, FenceCreateInfo, Format, Framebuffer, FramebufferCreateInfo, ImageAspectFlags, ImageLayout, ImageSubresourceRange, ImageUsageFlags, ImageView, ImageViewCreateInfo, ImageViewType, Offset2D, PhysicalDevice, PhysicalDeviceFeatures, PipelineBindPoint, PipelineStageFlags, PresentInfoKHR, PresentModeKHR, Queue, QueueFlags, Rect2D, RenderPass, RenderPassBeginInfo, RenderPassCreateInfo, SampleCountFlags, Semaphore, SemaphoreCreateInfo, ShaderStageFlags, SharingMode, SubmitInfo, SubpassContents, SubpassDependency, SubpassDescription, SurfaceCapabilitiesKHR, SurfaceFormatKHR, SurfaceKHR, SwapchainCreateInfoKHR, SwapchainKHR, ValidationFeaturesEXT, Viewport
 };
 use winit::window::Window;
 
+use crate::allocator::Allocator;
+use crate::config::Config;
+use crate::fault::{RenderError, ScheduledFault};
+use crate::deletion::{DeletionQueue, DestroyContext, DeviceResource};
 use crate::helper;
-
-const DEBUG_MODE_ENABLED: bool = cfg!(debug_assertions); 
-
-//debug callback method
-unsafe extern "system" fn vulkan_debug_callback(
-    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
-    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
-    _user_data: *mut std::os::raw::c_void,
-) -> vk::Bool32 {
-    unsafe {
-        let callback_data = *p_callback_data;
-        let message_id_number = callback_data.message_id_number;
-
-        let message_id_name = if callback_data.p_message_id_name.is_null() {
-            Cow::from("")
-        } else {
-            ffi::CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
-        };
-
-        let message = if callback_data.p_message.is_null() {
-            Cow::from("")
-        } else {
-            ffi::CStr::from_ptr(callback_data.p_message).to_string_lossy()
-        };
-
-        println!(
-            "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
-        );
-
-        vk::FALSE
-    }
-}
-
-//wrapper around debug information
-//(used to destroy the messenger)
-struct DebugCtx {debug_utils_loader: debug_utils::Instance, debug_call_back: DebugUtilsMessengerEXT }
+use crate::msaa::{self, ColorTarget, MsaaSamples};
+use crate::offscreen::{self, OffscreenTarget};
+use crate::crosshair::{self, Crosshair};
+use crate::pipeline::{self, PipelineContext, PipelineData, PipelineDesc, PipelineRegistry, RectPushConstants};
+use crate::pipeline_cache::PersistentPipelineCache;
+use crate::shaders::ShaderLibrary;
+use crate::stats::{CpuTimings, FrameSample, FrameStats, GpuTimer, StatsCsv};
+use crate::ui_painter::{UiFrame, UiGarbage, UiPainter};
+use crate::validation::{self, DebugCtx, DebugMessageLog, ValidationFailure};
 
 //wrapper around queue-family-indices
 #[derive(Debug)]
@@ -82,7 +57,55 @@ struct Queues {
     //queue - graphics commands can be sent to
     graphics_queue: Queue,
     //queue - present commands can be sent to
-    present_queue: Queue    
+    present_queue: Queue,
+    //family of present_queue (a new surface has to support it)
+    present_family: u32
+}
+
+//user choice of the swapchain present mode
+//(falls back to fifo if unsupported by the surface)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PresentModePreference {
+    //vsync without adding latency: fifo_relaxed if available, fifo otherwise
+    //(mailbox renders frames that are never shown, the overlay only
+    //draws on changes anyway)
+    #[default]
+    Auto,
+    Fifo,
+    FifoRelaxed,
+    Mailbox,
+    Immediate
+}
+impl PresentModePreference {
+    pub const ALL: [Self; 5] = [Self::Auto, Self::Fifo, Self::FifoRelaxed, Self::Mailbox, Self::Immediate];
+
+    //name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Fifo => "fifo",
+            Self::FifoRelaxed => "fifo_relaxed",
+            Self::Mailbox => "mailbox",
+            Self::Immediate => "immediate"
+        }
+    }
+
+    //the next preference (wraps around)
+    pub fn cycled(self) -> Self {
+        let index = Self::ALL.iter().position(|preference| *preference == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "auto" => Some(Self::Auto),
+            "fifo" | "vsync" => Some(Self::Fifo),
+            "fifo_relaxed" => Some(Self::FifoRelaxed),
+            "mailbox" => Some(Self::Mailbox),
+            "immediate" => Some(Self::Immediate),
+            _ => None
+        }
+    }
 }
 
 //wrapper - swapchain details (for creation and use) 
@@ -92,11 +115,41 @@ struct SwapchainSupportDetails {
     surface_present_modes: Vec<PresentModeKHR>
 }
 
+//amount of frames that can be recorded while the gpu
+//is still working on previous ones
+const MAX_FRAMES_IN_FLIGHT: usize = 2;
+
+//per frame-in-flight command recording and synchronization
+struct FrameData {
+    command_buffer: CommandBuffer,
+    //signaled when the acquired swapchain image can be rendered to
+    image_available: Semaphore,
+    //signaled when the gpu finished executing this frame
+    in_flight: Fence,
+    //frame number last submitted with these resources
+    //(completed once in_flight is signaled)
+    submitted_frame: Option<u64>
+}
+impl DeviceResource for FrameData {
+    //the command buffer is freed with its pool
+    fn destroy(self, ctx: &mut DestroyContext) {
+        self.image_available.destroy(ctx);
+        self.in_flight.destroy(ctx);
+    }
+}
+
+//(the default one holds no swapchain)
+#[derive(Default)]
 struct SwapchainData {
     //swapchain images format
     swapchain_image_format: Format,
     //swapchain resolution
     swapchain_extent: Extent2D,
+    //presentation mode in use (may differ from the preference)
+    present_mode: PresentModeKHR,
+    //how the compositor blends the images with what is underneath
+    //(OPAQUE: the window isn't see-through)
+    composite_alpha: CompositeAlphaFlagsKHR,
     //queue of images that are waiting to be presented
     //to screen (infrastructure for handling that)
     swapchain: SwapchainKHR,
@@ -106,15 +159,15 @@ struct SwapchainData {
     swapchain_image_views: Vec<vk::ImageView>
 }
 
-struct PipelineData {
-    pipeline_layout: PipelineLayout,
-    pipeline: Pipeline
-}
-
 pub struct Renderer {
+    //loaded vulkan library (creates surfaces on resume)
+    api_entry: Entry,
     //connection between application and vulkan lib
     instance: Instance,
     debug_ctx: Option<DebugCtx>,
+    //messages collected by the debug messenger
+    //(referenced by the debug callback, must outlive debug_ctx)
+    debug_log: Arc<DebugMessageLog>,
     //provides surface info and destroys it
     surface_loader: ash::khr::surface::Instance,
     //(WSI): connect vulkan and window system
@@ -132,40 +185,185 @@ pub struct Renderer {
     //defines attachments referenced by
     //pipeline stages and their usage
     render_pass: RenderPass,
-    //pipeline wrapper
-    graphics_pipeline_data: PipelineData
+    //resolves shader byte code (embedded, override dir, hot-reloaded)
+    shader_library: ShaderLibrary,
+    //pipeline cache persisted in the user's cache directory
+    pipeline_cache: PersistentPipelineCache,
+    //pipelines built from declarative descriptions
+    pipeline_registry: PipelineRegistry,
+    //sub-allocates device memory for images and buffers
+    allocator: Allocator,
+    //samples per pixel used by render pass and pipelines
+    msaa_samples: SampleCountFlags,
+    //multisampled color attachment (none without msaa)
+    color_target: Option<ColorTarget>,
+    //one framebuffer per swapchain image
+    framebuffers: Vec<Framebuffer>,
+    //second pass drawing the ui on top of the (resolved) scene
+    ui_render_pass: RenderPass,
+    //one per swapchain image
+    ui_framebuffers: Vec<Framebuffer>,
+    //renders preview thumbnails outside of the swapchain
+    offscreen_render_pass: RenderPass,
+    //set 0 of textured pipelines
+    texture_set_layout: DescriptorSetLayout,
+    ui_painter: UiPainter,
+    //allocates the per-frame command buffers
+    command_pool: CommandPool,
+    //per frame-in-flight resources
+    frames: Vec<FrameData>,
+    //signaled when rendering to a swapchain image finished (per image)
+    render_finished_semaphores: Vec<Semaphore>,
+    //index into frames
+    current_frame: usize,
+    //swapchain doesn't match the surface anymore (resize, suboptimal)
+    swapchain_outdated: bool,
+    //requested present mode (applied on swapchain (re-)creation)
+    present_mode_preference: PresentModePreference,
+    //gpu timestamps per frame (none if unsupported)
+    gpu_timer: Option<GpuTimer>,
+    //rolling cpu/gpu frame timings
+    stats: FrameStats,
+    //completed frame timings are appended here (--stats-csv)
+    stats_csv: Option<StatsCsv>,
+    //draws the frame time graph on top of the crosshair (--stats)
+    show_stats: bool,
+    //lines through the screen center underneath the crosshair (edit mode)
+    show_center_guide: bool,
+    //the crosshair is drawn (input rules hide it while aiming)
+    show_crosshair: bool,
+    //seconds since the crosshair animation started (shader input)
+    time: f32,
+    //running number of the next frame (= amount of submitted frames)
+    frame_number: u64,
+    //all frames < completed_frames finished on the gpu
+    completed_frames: u64,
+    //replaced resources waiting for the frames that still use them
+    deletion_queue: DeletionQueue,
+    //error the next due draw call fails with (fault injection)
+    injected_fault: Option<ScheduledFault>,
+    //surface and swapchain are released (application suspended)
+    suspended: bool
 }
 impl Renderer {
     const DEVICE_EXTENSIONS: [&CStr; 1] = [vk::KHR_SWAPCHAIN_NAME];
 
-    pub fn new(event_loop: &ActiveEventLoop, window: &Window) -> Self {
+    //what was created before a failure is destroyed again
+    pub fn new(event_loop: &ActiveEventLoop, window: &Window, config: &Config) -> Result<Self, RenderError> {
         let api_entry = Entry::linked();
-        let (instance, debug_ctx)  = Self::create_instance(&api_entry, &event_loop);
+        let debug_log = Arc::new(DebugMessageLog::default());
+        let (instance, debug_ctx)  = Self::create_instance(&api_entry, &event_loop, config, &debug_log)?;
         let surface_loader = ash::khr::surface::Instance::new(&api_entry, &instance);
-        let surface = Self::create_surface(&api_entry, &instance, &event_loop, &window);
-        let physical_device = Self::select_physical_device(&instance, &surface_loader, &surface);
-        let (logical_device, queues) = Self::create_logical_device(&instance, &physical_device, &surface_loader, &surface);
+
+        //until the logical device exists, cleanup is done by hand
+        let destroy_instance = || unsafe {
+            if let Some(debug_ctx) = debug_ctx.as_ref() {
+                debug_ctx.debug_utils_loader.destroy_debug_utils_messenger(debug_ctx.debug_call_back, None);
+            }
+            instance.destroy_instance(None);
+        };
+        let surface = match Self::create_surface(&api_entry, &instance, &event_loop, &window) {
+            Ok(surface) => surface,
+            Err(err) => {
+                destroy_instance();
+                return Err(err.into())
+            }
+        };
+        let device = Self::select_physical_device(&instance, &surface_loader, &surface)
+            .and_then(|physical_device| Ok((physical_device, Self::create_logical_device(&instance, &physical_device, &surface_loader, &surface)?)));
+        let (physical_device, (logical_device, queues)) = match device {
+            Ok(device) => device,
+            Err(err) => {
+                unsafe { surface_loader.destroy_surface(surface, None) };
+                destroy_instance();
+                return Err(err.into())
+            }
+        };
+
         let swapchain_loader = ash::khr::swapchain::Device::new(&instance, &logical_device);
-        let swapchain_data = Self::create_swapchain(&window, &instance, &physical_device, &logical_device, &surface_loader, &surface, &swapchain_loader);
-        let render_pass = Self::create_render_pass(&logical_device, &swapchain_data);
-        let graphics_pipeline_data = Self::create_graphics_pipeline(&logical_device, &swapchain_data, &render_pass);
+        let allocator = Allocator::new(&instance, &physical_device);
+        let msaa_samples = msaa::select_sample_count(&instance, &physical_device, config.msaa);
+        let shader_library = ShaderLibrary::new(config.shader_dir.clone());
+        let stats_csv = config.stats_csv.as_ref().and_then(|path| match StatsCsv::create(path) {
+            Ok(stats_csv) => {
+                println!("writing frame stats to {}", path.display());
+                Some(stats_csv)
+            },
+            Err(err) => {
+                println!("failed creating {}: {err}; frame stats are not written", path.display());
+                None
+            }
+        });
 
-        Self {
+        //from here on the renderer owns everything: the device objects start
+        //out null and are created one by one, on failure dropping the renderer
+        //destroys what exists so far
+        let mut renderer = Self {
+            api_entry,
             instance,
             debug_ctx,
+            debug_log,
             surface_loader,
             surface,
             physical_device,
             logical_device,
             queues,
             swapchain_loader,
-            swapchain_data,
-            render_pass,
-            graphics_pipeline_data
-        }
+            swapchain_data: SwapchainData::default(),
+            render_pass: RenderPass::null(),
+            shader_library,
+            pipeline_cache: PersistentPipelineCache::default(),
+            pipeline_registry: PipelineRegistry::default(),
+            allocator,
+            msaa_samples,
+            color_target: None,
+            framebuffers: Vec::new(),
+            ui_render_pass: RenderPass::null(),
+            ui_framebuffers: Vec::new(),
+            offscreen_render_pass: RenderPass::null(),
+            texture_set_layout: DescriptorSetLayout::null(),
+            ui_painter: UiPainter::default(),
+            command_pool: CommandPool::null(),
+            frames: Vec::new(),
+            render_finished_semaphores: Vec::new(),
+            current_frame: 0,
+            swapchain_outdated: false,
+            present_mode_preference: config.present_mode,
+            gpu_timer: None,
+            stats: FrameStats::default(),
+            stats_csv,
+            show_stats: config.show_stats,
+            show_center_guide: false,
+            show_crosshair: true,
+            time: 0.0,
+            frame_number: 0,
+            completed_frames: 0,
+            deletion_queue: DeletionQueue::default(),
+            injected_fault: None,
+            suspended: false
+        };
+        renderer.swapchain_data = Self::create_swapchain(&window, &renderer.instance, &renderer.physical_device, &renderer.logical_device, &renderer.surface_loader, &renderer.surface, &renderer.swapchain_loader, config.present_mode, SwapchainKHR::null())?;
+        renderer.render_pass = Self::create_render_pass(&renderer.logical_device, &renderer.swapchain_data, msaa_samples)?;
+        renderer.ui_render_pass = Self::create_ui_render_pass(&renderer.logical_device, &renderer.swapchain_data)?;
+        renderer.offscreen_render_pass = offscreen::create_offscreen_render_pass(&renderer.logical_device)?;
+        renderer.texture_set_layout = pipeline::create_texture_set_layout(&renderer.logical_device)?;
+        renderer.ui_painter = UiPainter::new(&renderer.logical_device, MAX_FRAMES_IN_FLIGHT)?;
+        renderer.pipeline_cache = PersistentPipelineCache::new(&renderer.instance, &renderer.physical_device, &renderer.logical_device)?;
+        let graphics_family = Self::find_queue_families(&renderer.instance, &renderer.physical_device, &renderer.surface_loader, &renderer.surface)?
+            .graphics_family
+            .unwrap();
+        renderer.command_pool = Self::create_command_pool(&renderer.logical_device, graphics_family)?;
+        renderer.frames = Self::create_frames(&renderer.logical_device, &renderer.command_pool)?;
+        renderer.render_finished_semaphores = Self::create_render_finished_semaphores(&renderer.logical_device, &renderer.swapchain_data)?;
+        renderer.gpu_timer = GpuTimer::new(&renderer.instance, &renderer.physical_device, &renderer.logical_device, graphics_family, MAX_FRAMES_IN_FLIGHT);
+        renderer.create_render_targets()?;
+        renderer.register_pipeline(&PipelineDesc::CROSSHAIR)?;
+        renderer.register_pipeline(&PipelineDesc::UI)?;
+
+        Ok(renderer)
     }
 
-    fn create_instance(api_entry: &Entry, event_loop: &ActiveEventLoop) -> (Instance, Option<DebugCtx>) {
+    fn create_instance(api_entry: &Entry, event_loop: &ActiveEventLoop, config: &Config, debug_log: &Arc<DebugMessageLog>) -> VkResult<(Instance, Option<DebugCtx>)> {
         unsafe {
             let app_info = vk::ApplicationInfo {
                 p_application_name: c"custicle".as_ptr(),
@@ -176,20 +374,52 @@ impl Renderer {
             let mut extension_names = 
                 ash_window::enumerate_required_extensions(
                     event_loop.display_handle().expect("failed to gather display handle!").as_raw()
-                ).unwrap().to_vec();
+                )?.to_vec();
 
             let mut create_info = vk::InstanceCreateInfo {
                 p_application_info: &app_info,
                 enabled_layer_count: 0,
                 ..Default::default()
             };        
-        
+
+            //validation is only enabled if requested and the
+            //layer (+ debug extension) is actually installed
+            let mut validation_enabled = false;
+            let mut validation_features_supported = false;
+            if config.validation.enabled {
+                let support = validation::query_validation_support(api_entry);
+                if !support.layer {
+                    println!("validation requested but {:?} is not installed; continuing without validation", validation::VALIDATION_LAYER_NAME);
+                } else if !support.debug_utils {
+                    println!("validation requested but {:?} is not available; continuing without validation", debug_utils::NAME);
+                } else {
+                    validation_enabled = true;
+                    validation_features_supported = support.validation_features;
+                    if !config.validation.features.is_empty() && !support.validation_features {
+                        println!("validation features requested but {:?} is not available; ignoring them", ash::ext::validation_features::NAME);
+                    }
+                }
+            }
+
             //enabling validation layer and debug extension
-            //when running in debug mode
-            let layer_names : Vec<*const c_char> = vec![c"VK_LAYER_KHRONOS_validation".as_ptr()];
-            let debug_create_info;
-            if DEBUG_MODE_ENABLED {
-                debug_create_info = Self::get_debug_create_info();
+            let layer_names : Vec<*const c_char> = vec![validation::VALIDATION_LAYER_NAME.as_ptr()];
+            let mut debug_create_info;
+            let validation_feature_enables = config.validation.feature_enables();
+            let validation_features;
+            if validation_enabled {
+                debug_create_info = validation::get_debug_create_info(debug_log);
+
+                //optional validation features (gpu-assisted, best-practices, sync)
+                if validation_features_supported && !validation_feature_enables.is_empty() {
+                    validation_features = ValidationFeaturesEXT {
+                        enabled_validation_feature_count: helper::usize_into_u32(validation_feature_enables.len()),
+                        p_enabled_validation_features: validation_feature_enables.as_ptr(),
+                        ..Default::default()
+                    };
+                    debug_create_info.p_next = &raw const validation_features as *const c_void;
+                    extension_names.push(ash::ext::validation_features::NAME.as_ptr());
+                }
+
                 //debug messenger for instance creation/deletion
                 create_info.p_next = &raw const debug_create_info as *const c_void;
 
@@ -209,14 +439,18 @@ impl Renderer {
             create_info.pp_enabled_extension_names = &raw const extension_names[0];
 
             //create vulkan instance
-            let instance = api_entry
-                .create_instance(&create_info, None)
-                .expect("failed creating vulkan instance!");
+            let instance = api_entry.create_instance(&create_info, None)?;
 
             //create debug_ctx (= debug messenger for validation)
-            let debug_ctx = Self::create_debug_messenger(&api_entry, &instance);
+            let debug_ctx = match validation_enabled.then(|| validation::create_debug_messenger(api_entry, &instance, debug_log)).transpose() {
+                Ok(debug_ctx) => debug_ctx,
+                Err(err) => {
+                    instance.destroy_instance(None);
+                    return Err(err)
+                }
+            };
 
-            (instance, debug_ctx)
+            Ok((instance, debug_ctx))
         }
     } 
 
@@ -240,40 +474,7 @@ impl Renderer {
         }); 
     }
 
-    fn get_debug_create_info() -> vk::DebugUtilsMessengerCreateInfoEXT<'static> {
-        vk::DebugUtilsMessengerCreateInfoEXT {
-            message_severity: 
-                DebugUtilsMessageSeverityFlagsEXT::ERROR | 
-                DebugUtilsMessageSeverityFlagsEXT::WARNING,
-            message_type: 
-                DebugUtilsMessageTypeFlagsEXT::GENERAL |
-                DebugUtilsMessageTypeFlagsEXT::VALIDATION |
-                DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
-            pfn_user_callback: Some(vulkan_debug_callback),
-            ..Default::default()
-        }
-    }
-
-    fn create_debug_messenger(api_entry: &Entry, instance: &Instance) -> Option<DebugCtx> {
-        //return if debug mode is disabled
-        if !DEBUG_MODE_ENABLED { return None }
-
-        let debug_messenger_create_info = Self::get_debug_create_info();
-
-        //create loader and call_back
-        let (debug_utils_loader, debug_call_back) = unsafe {
-            let debug_utils_loader = debug_utils::Instance::new(&api_entry, &instance);
-            let debug_call_back = 
-                debug_utils_loader
-                    .create_debug_utils_messenger(&debug_messenger_create_info, None)
-                    .expect("failed creating debug utils messenger!");
-            (debug_utils_loader, debug_call_back)
-        };
-
-        Some(DebugCtx{ debug_utils_loader, debug_call_back })
-    }
-
-    fn create_surface(api_entry: &Entry, instance: &Instance, event_loop: &ActiveEventLoop, window: &Window) -> SurfaceKHR {
+    fn create_surface(api_entry: &Entry, instance: &Instance, event_loop: &ActiveEventLoop, window: &Window) -> VkResult<SurfaceKHR> {
         unsafe {
             ash_window::create_surface(
                 api_entry,
@@ -281,11 +482,11 @@ impl Renderer {
                 event_loop.display_handle().unwrap().as_raw(),
                 window.window_handle().unwrap().as_raw(),
                 None
-            ).expect("failed creating window surface")
+            )
         }
     }
 
-    fn find_queue_families(instance: &Instance, physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> QueueFamilyIndices {
+    fn find_queue_families(instance: &Instance, physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> VkResult<QueueFamilyIndices> {
         let mut queue_family_indices = QueueFamilyIndices::new();
 
         unsafe {
@@ -304,7 +505,7 @@ impl Renderer {
                         *physical_device,
                         index,
                         *surface
-                    ).expect("failed fetching surface present support!");
+                    )?;
                 
                 if present_support {
                     queue_family_indices.present_family = 
@@ -317,13 +518,12 @@ impl Renderer {
             }
         }
 
-        queue_family_indices
+        Ok(queue_family_indices)
     } 
 
-    fn check_physical_device_extension_support(instance: &Instance, physical_device: &PhysicalDevice) -> bool {
+    fn check_physical_device_extension_support(instance: &Instance, physical_device: &PhysicalDevice) -> VkResult<bool> {
         let available_device_extensions = unsafe { instance
-            .enumerate_device_extension_properties(*physical_device)
-            .expect("failed enumerating device extension properties!")
+            .enumerate_device_extension_properties(*physical_device)?
         };
 
         let mut required_extensions : BTreeSet<_> = 
@@ -333,10 +533,10 @@ impl Renderer {
             required_extensions.remove(extension.extension_name_as_c_str().unwrap());
         }
 
-        required_extensions.is_empty()
+        Ok(required_extensions.is_empty())
     }
 
-    fn is_physical_device_suitable(instance: &Instance, physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> bool {
+    fn is_physical_device_suitable(instance: &Instance, physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> VkResult<bool> {
         unsafe {
             let physical_device_properties= 
                 instance.get_physical_device_properties(*physical_device);
@@ -345,15 +545,15 @@ impl Renderer {
                 instance.get_physical_device_features(*physical_device);
 
             let queue_families = 
-                Self::find_queue_families(&instance, &physical_device, &surface_loader, &surface);
+                Self::find_queue_families(&instance, &physical_device, &surface_loader, &surface)?;
 
             let extension_support = 
-                Self::check_physical_device_extension_support(&instance, &physical_device);
+                Self::check_physical_device_extension_support(&instance, &physical_device)?;
 
             let mut swapchain_adequate = false;
             if extension_support {
                 let swapchain_details = 
-                    Self::query_swapchain_support_details(&physical_device, &surface_loader, &surface);
+                    Self::query_swapchain_support_details(&physical_device, &surface_loader, &surface)?;
                 //swapchain is sufficient when there is at least one format and one present mode
                 swapchain_adequate = !swapchain_details.surface_formats.is_empty() &&
                                             !swapchain_details.surface_present_modes.is_empty();
@@ -372,30 +572,29 @@ impl Renderer {
                 dbg!(queue_families);
             }
 
-            suitable
+            Ok(suitable)
         }
     }
 
-    fn select_physical_device(instance: &Instance, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> PhysicalDevice {
+    fn select_physical_device(instance: &Instance, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> VkResult<PhysicalDevice> {
         unsafe {
-            let physical_devices = instance.enumerate_physical_devices()
-                .expect("couldn't find any physical device!");
-            assert!(physical_devices.len() > 0, "couldn't find any physical device!");
+            let physical_devices = instance.enumerate_physical_devices()?;
             
             for physical_device in physical_devices.iter() {
-                if Self::is_physical_device_suitable(&instance, &physical_device, &surface_loader, &surface) {
-                    return *physical_device                    
+                if Self::is_physical_device_suitable(&instance, &physical_device, &surface_loader, &surface)? {
+                    return Ok(*physical_device)
                 }
             }
             
-            panic!("no suitable physical device found!");
+            println!("no suitable physical device found (out of {})", physical_devices.len());
+            Err(vk::Result::ERROR_FEATURE_NOT_PRESENT)
         }
     }
 
-    fn create_logical_device(instance: &Instance, physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> (Device, Queues)  {
+    fn create_logical_device(instance: &Instance, physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> VkResult<(Device, Queues)>  {
         //creating queues:
         let queue_families = 
-            Self::find_queue_families(&instance, &physical_device, &surface_loader, &surface); 
+            Self::find_queue_families(&instance, &physical_device, &surface_loader, &surface)?; 
         
         //set of unique items
         let unique_queue_families: BTreeSet<u32> = [
@@ -437,9 +636,7 @@ impl Renderer {
         };
 
         let logical_device = unsafe {
-            instance
-                .create_device(*physical_device, &logical_device_create_info, None)
-                .expect("failed creating logical device")
+            instance.create_device(*physical_device, &logical_device_create_info, None)?
         };
 
         let graphics_queue = unsafe {
@@ -458,32 +655,30 @@ impl Renderer {
 
         let queues = Queues {
             graphics_queue,
-            present_queue
+            present_queue,
+            present_family: queue_families.present_family.unwrap()
         };
         
-        (logical_device, queues)
+        Ok((logical_device, queues))
     }
 
-    fn query_swapchain_support_details(physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> SwapchainSupportDetails {
+    fn query_swapchain_support_details(physical_device: &PhysicalDevice, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR) -> VkResult<SwapchainSupportDetails> {
+        //fails with ERROR_SURFACE_LOST_KHR if the surface went away
         unsafe {
             let surface_capabilities = surface_loader
-                .get_physical_device_surface_capabilities(*physical_device, *surface)
-                .expect("failed getting physical-device surface capabilities");
+                .get_physical_device_surface_capabilities(*physical_device, *surface)?;
 
             let surface_formats = surface_loader
-                .get_physical_device_surface_formats(*physical_device, *surface)
-                .expect("failed getting physical-device surface formats");
+                .get_physical_device_surface_formats(*physical_device, *surface)?;
 
             let surface_present_modes = surface_loader
-                .get_physical_device_surface_present_modes(*physical_device, *surface)
-                .expect("failed getting physical-device surface present modes");
+                .get_physical_device_surface_present_modes(*physical_device, *surface)?;
 
-
-            SwapchainSupportDetails {
+            Ok(SwapchainSupportDetails {
                 surface_capabilities,
                 surface_formats,
                 surface_present_modes
-            }
+            })
         }
     }
 
@@ -496,18 +691,37 @@ impl Renderer {
         *available_surface_formats.first().unwrap()
     }
 
-    fn chose_swapchain_present_mode(available_surface_present_modes: &Vec<PresentModeKHR>) -> PresentModeKHR {
-        for present_mode in available_surface_present_modes.iter() {
+    fn chose_swapchain_present_mode(available_surface_present_modes: &[PresentModeKHR], preference: PresentModePreference) -> PresentModeKHR {
+        let requested = match preference {
+            PresentModePreference::Auto => {
+                //fifo_relaxed shows a late frame right away instead of waiting
+                //for the next vblank, fifo is always supported
+                if available_surface_present_modes.contains(&PresentModeKHR::FIFO_RELAXED) {
+                    return PresentModeKHR::FIFO_RELAXED
+                }
+                return PresentModeKHR::FIFO
+            },
+            PresentModePreference::Fifo => PresentModeKHR::FIFO,
+            //like fifo, but a late frame is presented immediately (may tear)
+            PresentModePreference::FifoRelaxed => PresentModeKHR::FIFO_RELAXED,
             /*
                 Instead of blocking the application when the queue is full, the images that are already queued
                 are simply replaced with the newer ones. This mode can be used to render frames as fast as 
                 possible while still avoiding tearing, resulting in fewer latency issues than standard 
                 vertical sync. This is commonly known as "triple buffering" 
             */
-            if *present_mode == PresentModeKHR::MAILBOX {
-                return *present_mode
-            }
+            PresentModePreference::Mailbox => PresentModeKHR::MAILBOX,
+            //no vsync at all, lowest latency (may tear)
+            PresentModePreference::Immediate => PresentModeKHR::IMMEDIATE
+        };
+
+        if available_surface_present_modes.contains(&requested) {
+            return requested
         }
+        println!(
+            "present mode {requested:?} not supported (available: {available_surface_present_modes:?}), falling back to {:?}",
+            PresentModeKHR::FIFO
+        );
 
         /*
             swap chain is a queue where the display takes an image from the front of the queue when the display 
@@ -524,15 +738,16 @@ impl Renderer {
         if surface_capabilities.current_extent.width != u32::MAX {
             return surface_capabilities.current_extent
         } else {
-            let logical_inner_size: LogicalSize<u32> = window.inner_size().to_logical(window.scale_factor());
+            //physical pixels: crosshair geometry is snapped to this grid
+            let physical_inner_size = window.inner_size();
             Extent2D { 
                 width: u32::clamp(
-                    logical_inner_size.width,
+                    physical_inner_size.width,
                     surface_capabilities.min_image_extent.width,
-                    surface_capabilities.min_image_extent.height
+                    surface_capabilities.max_image_extent.width
                 ),
                 height: u32::clamp(
-                    logical_inner_size.height,
+                    physical_inner_size.height,
                     surface_capabilities.min_image_extent.height,
                     surface_capabilities.max_image_extent.height
                 )
@@ -540,19 +755,37 @@ impl Renderer {
         }
     }
 
-    fn create_swapchain(window: &Window, instance: &Instance, physical_device: &PhysicalDevice, logical_device: &Device, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR, swapchain_loader: &ash::khr::swapchain::Device) -> SwapchainData {
+    //the framebuffer is cleared to (0, 0, 0, 0) and blended with straight
+    //alpha, which leaves premultiplied colors in it: PRE_MULTIPLIED is exact,
+    //POST_MULTIPLIED (slightly darker edges) and INHERIT (the window system
+    //decides, x11 compositors assume premultiplied) are still see-through
+    fn chose_composite_alpha(supported: CompositeAlphaFlagsKHR) -> CompositeAlphaFlagsKHR {
+        let transparent = [CompositeAlphaFlagsKHR::PRE_MULTIPLIED, CompositeAlphaFlagsKHR::POST_MULTIPLIED, CompositeAlphaFlagsKHR::INHERIT];
+        match transparent.into_iter().find(|composite_alpha| supported.contains(*composite_alpha)) {
+            Some(composite_alpha) => composite_alpha,
+            None => {
+                println!("the surface doesn't support transparency (supported: {supported:?}), the overlay will be opaque");
+                CompositeAlphaFlagsKHR::OPAQUE
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_swapchain(window: &Window, instance: &Instance, physical_device: &PhysicalDevice, logical_device: &Device, surface_loader: &ash::khr::surface::Instance, surface: &SurfaceKHR, swapchain_loader: &ash::khr::swapchain::Device, present_mode_preference: PresentModePreference, old_swapchain: SwapchainKHR) -> VkResult<SwapchainData> {
         let surface_details = 
-            Self::query_swapchain_support_details(&physical_device, &surface_loader, &surface);
+            Self::query_swapchain_support_details(&physical_device, &surface_loader, &surface)?;
         //https://vulkan-tutorial.com/Drawing_a_triangle/Presentation/Swap_chain#page_Surface-format
         let surface_format = 
             Self::chose_swapchain_surface_format(&surface_details.surface_formats);
         //https://vulkan-tutorial.com/Drawing_a_triangle/Presentation/Swap_chain#page_Presentation-mode
         let surface_present_mode = 
-            Self::chose_swapchain_present_mode(&surface_details.surface_present_modes);
+            Self::chose_swapchain_present_mode(&surface_details.surface_present_modes, present_mode_preference);
         //resolution of the swapchain-images
         //https://vulkan-tutorial.com/Drawing_a_triangle/Presentation/Swap_chain#page_Swap-extent
         let swapchain_extent = 
             Self::chose_swapchain_extent(&surface_details.surface_capabilities, &window);
+        let composite_alpha =
+            Self::chose_composite_alpha(surface_details.surface_capabilities.supported_composite_alpha);
 
         let mut swapchain_min_image_count =
             surface_details.surface_capabilities.min_image_count + 1;
@@ -572,14 +805,16 @@ impl Renderer {
             image_array_layers: 1, 
             image_usage: ImageUsageFlags::COLOR_ATTACHMENT,
             pre_transform: surface_details.surface_capabilities.current_transform,
-            composite_alpha: CompositeAlphaFlagsKHR::OPAQUE,
+            composite_alpha,
             present_mode: surface_present_mode,
             clipped: vk::TRUE,
+            //lets the old swapchain finish its presentation
+            old_swapchain,
             ..Default::default()
         };
 
         let queue_family_indices = 
-            Self::find_queue_families(instance, &physical_device, &surface_loader, &surface);
+            Self::find_queue_families(instance, &physical_device, &surface_loader, &surface)?;
         
         let queue_family_indices_vec = vec![
             queue_family_indices.graphics_family.unwrap(),
@@ -596,21 +831,28 @@ impl Renderer {
 
         let swapchain = unsafe {
             swapchain_loader
-                .create_swapchain(&swapchain_create_info, None)
-                .expect("failed creating swapchain!")
+                .create_swapchain(&swapchain_create_info, None)?
         };
 
 
-        let swapchain_images = unsafe {
-            swapchain_loader
-                .get_swapchain_images(swapchain)
-                .expect("failed getting swapchain images")
+        //destroys what was created so far (the swapchain is unused)
+        let destroy_partial = |swapchain_image_views: &[ImageView]| unsafe {
+            swapchain_image_views.iter().for_each(|image_view| logical_device.destroy_image_view(*image_view, None));
+            swapchain_loader.destroy_swapchain(swapchain, None);
+        };
+
+        let swapchain_images = match unsafe { swapchain_loader.get_swapchain_images(swapchain) } {
+            Ok(swapchain_images) => swapchain_images,
+            Err(err) => {
+                destroy_partial(&[]);
+                return Err(err)
+            }
         };
 
         dbg!(&swapchain_images);
 
         let mut swapchain_image_views: Vec<ImageView> = Vec::with_capacity(swapchain_images.len());
-        swapchain_images.iter().for_each(|swapchain_image| {
+        for swapchain_image in swapchain_images.iter() {
             let swapchain_image_view_create_info =
                 ImageViewCreateInfo {
                     image: *swapchain_image,
@@ -632,34 +874,40 @@ impl Renderer {
                     ..Default::default()
                 };
 
-            swapchain_image_views.push(
-                unsafe {
-                    logical_device.create_image_view(
-                        &swapchain_image_view_create_info,
-                        None
-                    ).expect("failed creating image view")
+            match unsafe { logical_device.create_image_view(&swapchain_image_view_create_info, None) } {
+                Ok(image_view) => swapchain_image_views.push(image_view),
+                Err(err) => {
+                    destroy_partial(&swapchain_image_views);
+                    return Err(err)
                 }
-            );                       
-        });
+            }
+        }
+
+        println!("swapchain: {}x{} {surface_present_mode:?} {composite_alpha:?}", swapchain_extent.width, swapchain_extent.height);
 
-        SwapchainData {
+        Ok(SwapchainData {
             swapchain_image_format: surface_format.format,
+            present_mode: surface_present_mode,
+            composite_alpha,
             swapchain_extent,
             swapchain,
             swapchain_images,
             swapchain_image_views
-        }
+        })
     }
 
-    fn create_render_pass(logical_device: &Device, swapchain_data: &SwapchainData) -> RenderPass {
+    fn create_render_pass(logical_device: &Device, swapchain_data: &SwapchainData, msaa_samples: SampleCountFlags) -> VkResult<RenderPass> {
+        let multisampled = msaa_samples != SampleCountFlags::TYPE_1;
+
         let color_attachment_description = 
             AttachmentDescription {
                 format: swapchain_data.swapchain_image_format,
-                samples: SampleCountFlags::TYPE_1,
+                samples: msaa_samples,
                 //load-op for color/depth buffers
                 load_op: AttachmentLoadOp::CLEAR,
                 //store-op for color/depth buffers
-                store_op: AttachmentStoreOp::STORE,
+                //(multisampled content is only needed until it is resolved)
+                store_op: if multisampled { AttachmentStoreOp::DONT_CARE } else { AttachmentStoreOp::STORE },
                 //load-op for stencil buffers
                 stencil_load_op: AttachmentLoadOp::DONT_CARE,
                 //store-op for stencil buffers
@@ -668,10 +916,24 @@ impl Renderer {
                 //are suitable for operation that they're be involved in
                 initial_layout: ImageLayout::UNDEFINED,
                 //-''-
-                final_layout: ImageLayout::PRESENT_SRC_KHR,
+                final_layout: if multisampled { ImageLayout::COLOR_ATTACHMENT_OPTIMAL } else { ImageLayout::PRESENT_SRC_KHR },
                 
                 ..Default::default()
             };
+
+        //single sampled swapchain image the msaa attachment is resolved into
+        let resolve_attachment_description =
+            AttachmentDescription {
+                format: swapchain_data.swapchain_image_format,
+                samples: SampleCountFlags::TYPE_1,
+                load_op: AttachmentLoadOp::DONT_CARE,
+                store_op: AttachmentStoreOp::STORE,
+                stencil_load_op: AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: AttachmentStoreOp::DONT_CARE,
+                initial_layout: ImageLayout::UNDEFINED,
+                final_layout: ImageLayout::PRESENT_SRC_KHR,
+                ..Default::default()
+            };
         
         let color_attachment_reference = 
             AttachmentReference {
@@ -679,6 +941,12 @@ impl Renderer {
                 layout: ImageLayout::COLOR_ATTACHMENT_OPTIMAL
             };
 
+        let resolve_attachment_reference =
+            AttachmentReference {
+                attachment: 1,
+                layout: ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+            };
+
         let subpass_description = 
             SubpassDescription {
                 pipeline_bind_point: PipelineBindPoint::GRAPHICS,
@@ -687,234 +955,1070 @@ impl Renderer {
                 //``layout (location = 0) out vec4 out_color``
                 p_color_attachments: &raw const color_attachment_reference,
                 color_attachment_count: 1,
+                p_resolve_attachments: if multisampled { &raw const resolve_attachment_reference } else { std::ptr::null() },
+                ..Default::default()
+            };
+
+        //wait for the presentation engine to release the image
+        //before writing to it (image_available semaphore wait stage);
+        //the msaa color target is shared by all frames in flight, so
+        //the previous frame's writes to it have to be finished as well
+        let subpass_dependency =
+            SubpassDependency {
+                src_subpass: vk::SUBPASS_EXTERNAL,
+                dst_subpass: 0,
+                src_stage_mask: PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                src_access_mask: AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access_mask: AccessFlags::COLOR_ATTACHMENT_WRITE,
                 ..Default::default()
             };
 
+        let attachment_descriptions = [color_attachment_description, resolve_attachment_description];
         let render_pass_create_info = 
             RenderPassCreateInfo {
-                attachment_count: 1,
-                p_attachments: &raw const color_attachment_description,
+                attachment_count: if multisampled { 2 } else { 1 },
+                p_attachments: &raw const attachment_descriptions[0],
                 subpass_count: 1,
                 p_subpasses: &raw const subpass_description,
+                dependency_count: 1,
+                p_dependencies: &raw const subpass_dependency,
                 ..Default::default()
             };
         
-        unsafe {
-            logical_device
-                .create_render_pass(&render_pass_create_info, None)
-                .expect("failed creating render pass")
-        }
+        unsafe { logical_device.create_render_pass(&render_pass_create_info, None) }
     }
 
-    fn create_shader_module(logical_device: &Device, byte_code: &Vec<u8>) -> ShaderModule {
-        let spirv = read_spv(&mut Cursor::new(byte_code))
-            .expect("failed to convert u8-vec to u32-vec");
-
-        let shader_module_create_info = ShaderModuleCreateInfo {
-            code_size: spirv.len() * std::mem::size_of::<u32>(),
-            p_code: &raw const spirv[0],
-            ..Default::default()
-        };
-
-
-        unsafe {
-            logical_device
-                .create_shader_module(&shader_module_create_info, None)
-                .expect("failed creating shader module!")
-        }
-    }
-
-    fn create_graphics_pipeline(logical_device: &Device, swapchain_data: &SwapchainData, render_pass: &RenderPass) -> PipelineData {
-        let vert_byte_src = 
-            fs::read("./shaders/default_vert.spv")
-                .expect("failed reading vertex shader");
-        let frag_byte_src = 
-            fs::read("./shaders/default_frag.spv")
-                .expect("failed reading fragment shader");
-
-        let vert_shader_module = Self::create_shader_module(&logical_device, &vert_byte_src);
-        let frag_shader_module = Self::create_shader_module(&logical_device, &frag_byte_src);
-
-        let vert_shader_stage_create_info = 
-            PipelineShaderStageCreateInfo {
-                stage: ShaderStageFlags::VERTEX,
-                module: vert_shader_module,
-                p_name: c"main".as_ptr(),
-                ..Default::default()
-            };
-        
-        let frag_shader_stage_create_info = 
-            PipelineShaderStageCreateInfo {
-                stage: ShaderStageFlags::FRAGMENT,
-                module: frag_shader_module,
-                p_name: c"main".as_ptr(),
+    //draws on top of the presentable swapchain image the scene pass left behind
+    fn create_ui_render_pass(logical_device: &Device, swapchain_data: &SwapchainData) -> VkResult<RenderPass> {
+        let color_attachment_description =
+            AttachmentDescription {
+                format: swapchain_data.swapchain_image_format,
+                samples: SampleCountFlags::TYPE_1,
+                //keep the crosshair
+                load_op: AttachmentLoadOp::LOAD,
+                store_op: AttachmentStoreOp::STORE,
+                stencil_load_op: AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: AttachmentStoreOp::DONT_CARE,
+                initial_layout: ImageLayout::PRESENT_SRC_KHR,
+                final_layout: ImageLayout::PRESENT_SRC_KHR,
                 ..Default::default()
             };
 
-        let shader_stage_create_infos = 
-            [vert_shader_stage_create_info, frag_shader_stage_create_info];
-
-        let vertex_input_create_info = 
-            PipelineVertexInputStateCreateInfo {
-            ..Default::default()
+        let color_attachment_reference =
+            AttachmentReference {
+                attachment: 0,
+                layout: ImageLayout::COLOR_ATTACHMENT_OPTIMAL
             };
 
-        let input_assembly_create_info = 
-            PipelineInputAssemblyStateCreateInfo {
-                topology: PrimitiveTopology::TRIANGLE_LIST,
-                primitive_restart_enable: vk::FALSE,
+        let subpass_description =
+            SubpassDescription {
+                pipeline_bind_point: PipelineBindPoint::GRAPHICS,
+                p_color_attachments: &raw const color_attachment_reference,
+                color_attachment_count: 1,
                 ..Default::default()
             };
 
-        let dynamic_states = [DynamicState::VIEWPORT, DynamicState::SCISSOR];
-        let dynamic_state_create_info = 
-            PipelineDynamicStateCreateInfo {
-                dynamic_state_count: helper::usize_into_u32(dynamic_states.len()),
-                p_dynamic_states: &raw const dynamic_states[0],
+        //blending reads what the scene pass wrote
+        let subpass_dependency =
+            SubpassDependency {
+                src_subpass: vk::SUBPASS_EXTERNAL,
+                dst_subpass: 0,
+                src_stage_mask: PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                src_access_mask: AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access_mask: AccessFlags::COLOR_ATTACHMENT_READ | AccessFlags::COLOR_ATTACHMENT_WRITE,
                 ..Default::default()
             };
-        
-        let viewport_create_info = 
-            PipelineViewportStateCreateInfo {
-                viewport_count: 1,
-                scissor_count: 1,
+
+        let render_pass_create_info =
+            RenderPassCreateInfo {
+                attachment_count: 1,
+                p_attachments: &raw const color_attachment_description,
+                subpass_count: 1,
+                p_subpasses: &raw const subpass_description,
+                dependency_count: 1,
+                p_dependencies: &raw const subpass_dependency,
                 ..Default::default()
             };
 
-        let rasterization_create_info = 
-            PipelineRasterizationStateCreateInfo {
-                //TRUE: fragments beyond the near and far planes are clamped
-                //      to them as opposed to discarding them
-                depth_clamp_enable: vk::FALSE,
-                //TRUE: geometry never passes through rasterizer stage
-                //      -> disables any output to framebuffer
-                rasterizer_discard_enable: vk::FALSE,
-                polygon_mode: PolygonMode::FILL,
-                line_width: 1.0,
-                cull_mode: CullModeFlags::BACK,
-                front_face: FrontFace::CLOCKWISE,
-                depth_bias_enable: vk::FALSE,
-                ..Default::default()
+        unsafe { logical_device.create_render_pass(&render_pass_create_info, None) }
+    }
+
+    //nothing is left behind on failure
+    fn create_framebuffers(logical_device: &Device, swapchain_data: &SwapchainData, render_pass: &RenderPass, color_target: Option<&ColorTarget>) -> VkResult<Vec<Framebuffer>> {
+        let mut framebuffers = Vec::with_capacity(swapchain_data.swapchain_image_views.len());
+        for swapchain_image_view in swapchain_data.swapchain_image_views.iter() {
+            //with msaa: [multisampled color, resolve target]
+            let attachments = match color_target {
+                Some(color_target) => vec![color_target.image_view, *swapchain_image_view],
+                None => vec![*swapchain_image_view]
             };
 
-        let multisample_create_info = 
-            PipelineMultisampleStateCreateInfo {
-                sample_shading_enable: vk::FALSE,
-                rasterization_samples: SampleCountFlags::TYPE_1,
+            let framebuffer_create_info =
+                FramebufferCreateInfo {
+                    render_pass: *render_pass,
+                    attachment_count: helper::usize_into_u32(attachments.len()),
+                    p_attachments: attachments.as_ptr(),
+                    width: swapchain_data.swapchain_extent.width,
+                    height: swapchain_data.swapchain_extent.height,
+                    layers: 1,
+                    ..Default::default()
+                };
+
+            match unsafe { logical_device.create_framebuffer(&framebuffer_create_info, None) } {
+                Ok(framebuffer) => framebuffers.push(framebuffer),
+                Err(err) => {
+                    framebuffers.iter().for_each(|framebuffer| unsafe { logical_device.destroy_framebuffer(*framebuffer, None) });
+                    return Err(err)
+                }
+            }
+        }
+        Ok(framebuffers)
+    }
+
+    fn create_command_pool(logical_device: &Device, graphics_family: u32) -> VkResult<CommandPool> {
+        let command_pool_create_info =
+            CommandPoolCreateInfo {
+                //command buffers are re-recorded every frame
+                flags: CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+                queue_family_index: graphics_family,
                 ..Default::default()
             };
 
-        //per-attatched-framebuffer configuration
-        let color_blend_attachment_state = 
-            PipelineColorBlendAttachmentState {
-                color_write_mask: ColorComponentFlags::R |
-                                ColorComponentFlags::G |
-                                ColorComponentFlags::B |
-                                ColorComponentFlags::A,
-                blend_enable: vk::FALSE,
+        unsafe { logical_device.create_command_pool(&command_pool_create_info, None) }
+    }
+
+    //nothing is left behind on failure (the command buffers are freed with the pool)
+    fn create_frames(logical_device: &Device, command_pool: &CommandPool) -> VkResult<Vec<FrameData>> {
+        let command_buffer_allocate_info =
+            CommandBufferAllocateInfo {
+                command_pool: *command_pool,
+                level: CommandBufferLevel::PRIMARY,
+                command_buffer_count: helper::usize_into_u32(MAX_FRAMES_IN_FLIGHT),
                 ..Default::default()
             };
-        
-        let color_blend_create_info = 
-            PipelineColorBlendStateCreateInfo {
-                logic_op_enable: vk::FALSE,
-                attachment_count: 1,
-                p_attachments: &raw const color_blend_attachment_state,
-                ..Default::default()
+
+        let command_buffers = unsafe {
+            logical_device.allocate_command_buffers(&command_buffer_allocate_info)?
+        };
+
+        let mut frames: Vec<FrameData> = Vec::with_capacity(command_buffers.len());
+        let destroy_frames = |frames: &[FrameData]| frames.iter().for_each(|frame| unsafe {
+            logical_device.destroy_semaphore(frame.image_available, None);
+            logical_device.destroy_fence(frame.in_flight, None);
+        });
+        for command_buffer in command_buffers {
+            let image_available = match unsafe { logical_device.create_semaphore(&SemaphoreCreateInfo::default(), None) } {
+                Ok(image_available) => image_available,
+                Err(err) => {
+                    destroy_frames(&frames);
+                    return Err(err)
+                }
+            };
+            //created signaled, so the first wait doesn't block forever
+            let fence_create_info = FenceCreateInfo { flags: FenceCreateFlags::SIGNALED, ..Default::default() };
+            let in_flight = match unsafe { logical_device.create_fence(&fence_create_info, None) } {
+                Ok(in_flight) => in_flight,
+                Err(err) => {
+                    unsafe { logical_device.destroy_semaphore(image_available, None) };
+                    destroy_frames(&frames);
+                    return Err(err)
+                }
             };
 
-        //specification of uniform values in shaders
-        let pipeline_layout_create_info = PipelineLayoutCreateInfo::default();
+            frames.push(FrameData { command_buffer, image_available, in_flight, submitted_frame: None });
+        }
+        Ok(frames)
+    }
 
-        let pipeline_layout = unsafe {
-            logical_device
-                .create_pipeline_layout(
-                    &pipeline_layout_create_info,
-                    None
-                )
-                .expect("failed creating pipeline layout")
+    //one per swapchain image: the semaphore may only be reused
+    //after the presentation of that image has finished
+    //(nothing is left behind on failure)
+    fn create_render_finished_semaphores(logical_device: &Device, swapchain_data: &SwapchainData) -> VkResult<Vec<Semaphore>> {
+        let mut semaphores = Vec::with_capacity(swapchain_data.swapchain_images.len());
+        for _ in swapchain_data.swapchain_images.iter() {
+            match unsafe { logical_device.create_semaphore(&SemaphoreCreateInfo::default(), None) } {
+                Ok(semaphore) => semaphores.push(semaphore),
+                Err(err) => {
+                    semaphores.iter().for_each(|semaphore| unsafe { logical_device.destroy_semaphore(*semaphore, None) });
+                    return Err(err)
+                }
+            }
+        }
+        Ok(semaphores)
+    }
+
+    fn pipeline_context(&self) -> PipelineContext<'_> {
+        PipelineContext {
+            logical_device: &self.logical_device,
+            render_pass: self.render_pass,
+            samples: self.msaa_samples,
+            ui_render_pass: self.ui_render_pass,
+            offscreen_render_pass: self.offscreen_render_pass,
+            texture_set_layout: self.texture_set_layout,
+            shader_library: &self.shader_library,
+            pipeline_cache: self.pipeline_cache.pipeline_cache
+        }
+    }
+
+    fn record_command_buffer(&self, command_buffer: CommandBuffer, image_index: usize, crosshair: &Crosshair) -> VkResult<()> {
+        let extent = self.swapchain_data.swapchain_extent;
+
+        let guide = if self.show_center_guide { crosshair::center_guide(extent.width, extent.height) } else { Vec::new() };
+        let crosshair_rects = if self.show_crosshair { crosshair.geometry(extent.width, extent.height) } else { Vec::new() };
+        let stats = if self.show_stats { self.stats.readout(extent.width, extent.height) } else { Vec::new() };
+
+        unsafe {
+            self.logical_device
+                .begin_command_buffer(command_buffer, &CommandBufferBeginInfo::default())?;
+            if let Some(gpu_timer) = self.gpu_timer.as_ref() {
+                gpu_timer.cmd_begin(&self.logical_device, command_buffer, self.current_frame);
+            }
+            //texture uploads can't happen inside a render pass
+            self.ui_painter.cmd_upload(&self.logical_device, command_buffer);
+
+            //transparent background (only the crosshair is visible,
+            //if the swapchain's composite alpha isn't OPAQUE)
+            let clear_values = [ClearValue {
+                color: ClearColorValue { float32: [0.0, 0.0, 0.0, 0.0] }
+            }];
+            let render_pass_begin_info =
+                RenderPassBeginInfo {
+                    render_pass: self.render_pass,
+                    framebuffer: self.framebuffers[image_index],
+                    render_area: Rect2D { offset: Offset2D::default(), extent },
+                    clear_value_count: helper::usize_into_u32(clear_values.len()),
+                    p_clear_values: clear_values.as_ptr(),
+                    ..Default::default()
+                };
+            self.logical_device.cmd_begin_render_pass(command_buffer, &render_pass_begin_info, SubpassContents::INLINE);
+
+            self.cmd_set_viewport(command_buffer, extent);
+            if let Some(pipeline_data) = self.pipeline_registry.get(&PipelineDesc::CROSSHAIR) {
+                //only the crosshair turns
+                self.cmd_draw_rects(command_buffer, pipeline_data, &guide, extent, None);
+                self.cmd_draw_rects(command_buffer, pipeline_data, &crosshair_rects, extent, Some(crosshair));
+                self.cmd_draw_rects(command_buffer, pipeline_data, &stats, extent, None);
+            }
+
+            self.logical_device.cmd_end_render_pass(command_buffer);
+
+            if let Some(pipeline_data) = self.pipeline_registry.get(&PipelineDesc::UI).filter(|_| self.ui_painter.has_primitives()) {
+                let render_pass_begin_info =
+                    RenderPassBeginInfo {
+                        render_pass: self.ui_render_pass,
+                        framebuffer: self.ui_framebuffers[image_index],
+                        render_area: Rect2D { offset: Offset2D::default(), extent },
+                        ..Default::default()
+                    };
+                self.logical_device.cmd_begin_render_pass(command_buffer, &render_pass_begin_info, SubpassContents::INLINE);
+                self.cmd_set_viewport(command_buffer, extent);
+                self.ui_painter.cmd_draw(&self.logical_device, command_buffer, pipeline_data, extent, self.current_frame);
+                self.logical_device.cmd_end_render_pass(command_buffer);
+            }
+
+            if let Some(gpu_timer) = self.gpu_timer.as_ref() {
+                gpu_timer.cmd_end(&self.logical_device, command_buffer, self.current_frame);
+            }
+            self.logical_device
+                .end_command_buffer(command_buffer)
+        }
+    }
+
+    //viewport and scissor are dynamic pipeline state
+    fn cmd_set_viewport(&self, command_buffer: CommandBuffer, extent: Extent2D) {
+        let viewport = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: extent.width as f32,
+            height: extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0
+        };
+        unsafe {
+            self.logical_device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+            self.logical_device.cmd_set_scissor(command_buffer, 0, &[Rect2D { offset: Offset2D::default(), extent }]);
+        }
+    }
+
+    //one push constant update + draw per rect
+    //(a crosshair consists of only a handful of rects),
+    //rects of `rotated_by` turn around its center
+    fn cmd_draw_rects(&self, command_buffer: CommandBuffer, pipeline_data: &PipelineData, rects: &[crosshair::Rect], extent: Extent2D, rotated_by: Option<&Crosshair>) {
+        let (pivot, rotation) = rotated_by.map_or(([0.0, 0.0], 0.0), |crosshair| {
+            let (center_x, center_y) = crosshair.center(extent.width, extent.height);
+            ([center_x, center_y], crosshair.rotation.to_radians())
+        });
+        unsafe {
+            self.logical_device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipeline_data.pipeline);
+            for rect in rects.iter() {
+                let push_constants = RectPushConstants {
+                    rect: [rect.x, rect.y, rect.width, rect.height],
+                    color: rect.color.to_array(),
+                    extent: [extent.width as f32, extent.height as f32],
+                    pivot,
+                    rotation,
+                    time: self.time
+                };
+                self.logical_device.cmd_push_constants(
+                    command_buffer,
+                    pipeline_data.pipeline_layout,
+                    ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+                    0,
+                    push_constants.as_bytes()
+                );
+                self.logical_device.cmd_draw(command_buffer, 6, 1, 0, 0);
+            }
+        }
+    }
+
+    //renders the crosshair into a size x size image and reads it back
+    //(premultiplied rgba8 in srgb, waits for the gpu)
+    pub fn render_thumbnail(&mut self, crosshair: &Crosshair, size: u32) -> Result<Vec<u8>, RenderError> {
+        self.register_pipeline(&PipelineDesc::THUMBNAIL)?;
+        let extent = Extent2D { width: size, height: size };
+        let mut target = OffscreenTarget::new(&self.logical_device, &mut self.allocator, self.offscreen_render_pass, extent)?;
+
+        let result = self
+            .submit_thumbnail(&target, crosshair)
+            .and_then(|_| target.read_pixels(&self.logical_device, &self.allocator));
+
+        //the gpu is done with it (or lost)
+        let mut ctx = DestroyContext {
+            logical_device: &self.logical_device,
+            allocator: &mut self.allocator,
+            swapchain_loader: &self.swapchain_loader
         };
+        target.destroy(&mut ctx);
+        Ok(result?)
+    }
+
+    fn submit_thumbnail(&self, target: &OffscreenTarget, crosshair: &Crosshair) -> VkResult<()> {
+        let extent = target.extent;
+        let rects = crosshair.geometry(extent.width, extent.height);
 
-        let graphics_pipeline_create_info = 
-            GraphicsPipelineCreateInfo {
-                //vertex and fragment shader-stages
-                stage_count: 2,
-                p_stages: &raw const shader_stage_create_infos[0],
-                //fixed function stages
-                p_vertex_input_state: &raw const vertex_input_create_info,
-                p_input_assembly_state: &raw const input_assembly_create_info,
-                p_viewport_state: &raw const viewport_create_info,
-                p_rasterization_state: &raw const rasterization_create_info,
-                p_multisample_state: &raw const multisample_create_info,
-                p_color_blend_state: &raw const color_blend_create_info,
-                p_dynamic_state: &raw const dynamic_state_create_info,
-                //pipeline layout
-                layout: pipeline_layout,
-                render_pass: *render_pass,
-                //index of the sub-(render)-pass where 
-                //this graphics pipeline will be used
-                subpass: 0,
+        let command_buffer_allocate_info =
+            CommandBufferAllocateInfo {
+                command_pool: self.command_pool,
+                level: CommandBufferLevel::PRIMARY,
+                command_buffer_count: 1,
                 ..Default::default()
             };
 
-        let pipelines = unsafe {
-            logical_device.create_graphics_pipelines(
-                PipelineCache::null(),
-                &[graphics_pipeline_create_info],
-                None
-            )
-            .expect("failed creating graphics pipelines")
-        };
-        let pipeline = pipelines[0];
-        
         unsafe {
-            logical_device.destroy_shader_module(frag_shader_module, None);
-            logical_device.destroy_shader_module(vert_shader_module, None);
+            let command_buffer = self.logical_device.allocate_command_buffers(&command_buffer_allocate_info)?[0];
+            let fence = self.logical_device.create_fence(&FenceCreateInfo::default(), None)?;
+
+            let result = (|| {
+                let begin_info = CommandBufferBeginInfo { flags: CommandBufferUsageFlags::ONE_TIME_SUBMIT, ..Default::default() };
+                self.logical_device.begin_command_buffer(command_buffer, &begin_info)?;
+
+                let clear_values = [ClearValue {
+                    color: ClearColorValue { float32: [0.0, 0.0, 0.0, 0.0] }
+                }];
+                let render_pass_begin_info =
+                    RenderPassBeginInfo {
+                        render_pass: self.offscreen_render_pass,
+                        framebuffer: target.framebuffer,
+                        render_area: Rect2D { offset: Offset2D::default(), extent },
+                        clear_value_count: helper::usize_into_u32(clear_values.len()),
+                        p_clear_values: clear_values.as_ptr(),
+                        ..Default::default()
+                    };
+                self.logical_device.cmd_begin_render_pass(command_buffer, &render_pass_begin_info, SubpassContents::INLINE);
+                self.cmd_set_viewport(command_buffer, extent);
+                if let Some(pipeline_data) = self.pipeline_registry.get(&PipelineDesc::THUMBNAIL) {
+                    self.cmd_draw_rects(command_buffer, pipeline_data, &rects, extent, Some(crosshair));
+                }
+                self.logical_device.cmd_end_render_pass(command_buffer);
+                target.cmd_copy_to_readback(&self.logical_device, command_buffer);
+                self.logical_device.end_command_buffer(command_buffer)?;
+
+                let submit_info = SubmitInfo {
+                    command_buffer_count: 1,
+                    p_command_buffers: &raw const command_buffer,
+                    ..Default::default()
+                };
+                self.logical_device.queue_submit(self.queues.graphics_queue, &[submit_info], fence)?;
+                self.logical_device.wait_for_fences(&[fence], true, u64::MAX)
+            })();
+
+            self.logical_device.destroy_fence(fence, None);
+            self.logical_device.free_command_buffers(self.command_pool, &[command_buffer]);
+            result
+        }
+    }
+
+    //renders and presents a single frame
+    //(on errors the renderer has to be rebuilt, see RenderError)
+    pub fn draw(&mut self, window: &Window, crosshair: &Crosshair) -> Result<(), RenderError> {
+        if let Some(injected_fault) = self.injected_fault.take_if(|fault| fault.is_due(self.frame_number)) {
+            println!("injecting fault {:?}", injected_fault.fault);
+            return Err(injected_fault.fault.into())
         }
 
+        //no surface to render into
+        if self.suspended {
+            return Ok(())
+        }
+        if self.swapchain_outdated {
+            self.recreate_swapchain(window)?;
+        }
+        //minimized: nothing to render into
+        let extent = self.swapchain_data.swapchain_extent;
+        if extent.width == 0 || extent.height == 0 {
+            return Ok(())
+        }
 
-        PipelineData {
-            pipeline_layout,
-            pipeline
+        unsafe {
+            //wait until the gpu finished the frame that used these resources
+            self.logical_device
+                .wait_for_fences(&[self.frames[self.current_frame].in_flight], true, u64::MAX)?;
         }
+        self.complete_frame(self.current_frame);
+        self.collect_garbage();
+        let mut ui_garbage = UiGarbage::default();
+        let prepared = self.ui_painter.prepare(&self.logical_device, &mut self.allocator, self.texture_set_layout, self.current_frame, &mut ui_garbage);
+        self.retire(ui_garbage);
+        prepared?;
+
+        let mut timings = CpuTimings::default();
+        let frame = &self.frames[self.current_frame];
+        unsafe {
+            let started = Instant::now();
+            let image_index = match self.swapchain_loader.acquire_next_image(
+                self.swapchain_data.swapchain,
+                u64::MAX,
+                frame.image_available,
+                Fence::null()
+            ) {
+                Ok((image_index, _suboptimal)) => image_index,
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    self.swapchain_outdated = true;
+                    window.request_redraw();
+                    return Ok(())
+                },
+                Err(err) => return Err(err.into())
+            };
+            timings.acquire = started.elapsed();
+
+            //only reset once work is guaranteed to be submitted
+            let started = Instant::now();
+            self.logical_device
+                .reset_fences(&[frame.in_flight])?;
+            self.logical_device
+                .reset_command_buffer(frame.command_buffer, CommandBufferResetFlags::empty())?;
+            self.record_command_buffer(frame.command_buffer, image_index as usize, crosshair)?;
+            timings.record = started.elapsed();
+
+            let started = Instant::now();
+            let render_finished = self.render_finished_semaphores[image_index as usize];
+            let wait_stages = [PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+            let submit_info = SubmitInfo::default()
+                .wait_semaphores(std::slice::from_ref(&frame.image_available))
+                .wait_dst_stage_mask(&wait_stages)
+                .command_buffers(std::slice::from_ref(&frame.command_buffer))
+                .signal_semaphores(std::slice::from_ref(&render_finished));
+            self.logical_device
+                .queue_submit(self.queues.graphics_queue, &[submit_info], frame.in_flight)?;
+            timings.submit = started.elapsed();
+
+            let started = Instant::now();
+            let present_info = PresentInfoKHR::default()
+                .wait_semaphores(std::slice::from_ref(&render_finished))
+                .swapchains(std::slice::from_ref(&self.swapchain_data.swapchain))
+                .image_indices(std::slice::from_ref(&image_index));
+            let present_result = self.swapchain_loader.queue_present(self.queues.present_queue, &present_info);
+            timings.present = started.elapsed();
+            match present_result {
+                Ok(false) => (),
+                //suboptimal: still presented, recreate before the next frame
+                Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    self.swapchain_outdated = true;
+                    window.request_redraw();
+                },
+                Err(err) => return Err(err.into())
+            }
+        }
+
+        self.record_frame_timings(timings);
+        //used by the frame just submitted
+        let ui_garbage = self.ui_painter.finish_frame();
+        self.retire(ui_garbage);
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        Ok(())
+    }
+
+    //makes a later draw call fail as if the device / surface was lost
+    //(after_frames is counted from now; exercises the recovery path)
+    pub fn inject_fault(&mut self, fault: ScheduledFault) {
+        self.injected_fault = Some(ScheduledFault {
+            after_frames: self.frame_number + fault.after_frames,
+            ..fault
+        });
+    }
+
+    fn record_frame_timings(&mut self, cpu: CpuTimings) {
+        let frame = self.frame_number;
+        self.frame_number += 1;
+        self.stats.push(FrameSample { frame, cpu, gpu: None });
+
+        //completed once its fence is signaled
+        self.frames[self.current_frame].submitted_frame = Some(frame);
+    }
+
+    //marks the frame last submitted with the given frame resources as
+    //finished and reads its gpu timestamps (their fence has to be signaled)
+    fn complete_frame(&mut self, frame_index: usize) {
+        let Some(frame) = self.frames[frame_index].submitted_frame.take() else {
+            return
+        };
+        //the queue executes frames in order
+        self.completed_frames = self.completed_frames.max(frame + 1);
+
+        let gpu = self.gpu_timer
+            .as_ref()
+            .and_then(|gpu_timer| gpu_timer.read(&self.logical_device, frame_index));
+        if let (Some(sample), Some(stats_csv)) = (self.stats.resolve_gpu_time(frame, gpu), self.stats_csv.as_mut())
+            && let Err(err) = stats_csv.write(&sample) {
+            //reported once, the file isn't written anymore
+            println!("failed writing frame stats, stopping the csv: {err}");
+            self.stats_csv = None;
+        }
+    }
+
+    //replaces the ui drawn on top of the crosshair (kept until the next call)
+    pub fn set_ui(&mut self, frame: UiFrame) {
+        self.ui_painter.set_frame(frame);
+    }
+
+    //stops drawing the ui (its textures are kept)
+    pub fn clear_ui(&mut self) {
+        self.ui_painter.clear();
+    }
+
+    //rolling cpu/gpu timings of the last frames
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    //toggles the on-screen frame time graph
+    pub fn set_show_stats(&mut self, show_stats: bool) {
+        self.show_stats = show_stats;
+    }
+
+    //toggles the center guide drawn underneath the crosshair
+    pub fn set_show_center_guide(&mut self, show_center_guide: bool) {
+        self.show_center_guide = show_center_guide;
+    }
+
+    //hides the crosshair (input rules), guide and stats are still drawn
+    pub fn set_show_crosshair(&mut self, show_crosshair: bool) {
+        self.show_crosshair = show_crosshair;
     }
 
-    pub fn _draw(&self) {}
+    //animation time of the next frame (seconds)
+    pub fn set_time(&mut self, seconds: f32) {
+        self.time = seconds;
+    }
+
+    //switches the present mode, the swapchain is recreated before the next frame
+    pub fn set_present_mode(&mut self, preference: PresentModePreference) {
+        if preference != self.present_mode_preference {
+            self.present_mode_preference = preference;
+            self.swapchain_outdated = true;
+        }
+    }
+
+    //the swapchain is blended with what is underneath the window
+    //(needs a transparent window and compositor support)
+    pub fn is_transparent(&self) -> bool {
+        self.swapchain_data.composite_alpha != CompositeAlphaFlagsKHR::OPAQUE
+    }
+
+    //present mode the current swapchain was created with
+    pub fn present_mode(&self) -> PresentModePreference {
+        match self.swapchain_data.present_mode {
+            PresentModeKHR::FIFO_RELAXED => PresentModePreference::FifoRelaxed,
+            PresentModeKHR::MAILBOX => PresentModePreference::Mailbox,
+            PresentModeKHR::IMMEDIATE => PresentModePreference::Immediate,
+            _ => PresentModePreference::Fifo
+        }
+    }
+
+    //marks the swapchain for recreation (e.g. after the window was resized)
+    pub fn resized(&mut self) {
+        self.swapchain_outdated = true;
+    }
+
+    //hands a resource to the deletion queue, it is destroyed once
+    //all frames submitted so far finished
+    fn retire(&mut self, resource: impl DeviceResource + 'static) {
+        self.deletion_queue.retire(self.frame_number, resource);
+    }
+
+    //destroys retired resources no submitted frame uses anymore
+    fn collect_garbage(&mut self) {
+        //frames that finished in the meantime (without blocking)
+        for frame_index in 0..self.frames.len() {
+            let signaled = unsafe { self.logical_device.get_fence_status(self.frames[frame_index].in_flight) };
+            if signaled == Ok(true) {
+                self.complete_frame(frame_index);
+            }
+        }
+
+        let mut ctx = DestroyContext {
+            logical_device: &self.logical_device,
+            allocator: &mut self.allocator,
+            swapchain_loader: &self.swapchain_loader
+        };
+        self.deletion_queue.collect(self.completed_frames, &mut ctx);
+    }
+
+    //framebuffers and multisampled target of the current swapchain
+    fn retire_render_targets(&mut self) {
+        let framebuffers = std::mem::take(&mut self.framebuffers);
+        self.retire(framebuffers);
+        let ui_framebuffers = std::mem::take(&mut self.ui_framebuffers);
+        self.retire(ui_framebuffers);
+        let color_target = self.color_target.take();
+        self.retire(color_target);
+    }
+
+    //creates the multisampled target and framebuffers for the current swapchain
+    //(what was created before a failure is owned by the renderer)
+    fn create_render_targets(&mut self) -> VkResult<()> {
+        self.color_target = (self.msaa_samples != SampleCountFlags::TYPE_1).then(|| ColorTarget::new(
+            &self.logical_device,
+            &mut self.allocator,
+            self.swapchain_data.swapchain_image_format,
+            self.swapchain_data.swapchain_extent,
+            self.msaa_samples
+        )).transpose()?;
+        self.framebuffers = Self::create_framebuffers(&self.logical_device, &self.swapchain_data, &self.render_pass, self.color_target.as_ref())?;
+        self.ui_framebuffers = Self::create_framebuffers(&self.logical_device, &self.swapchain_data, &self.ui_render_pass, None)?;
+        Ok(())
+    }
+
+    fn recreate_swapchain(&mut self, window: &Window) -> VkResult<()> {
+        self.swapchain_outdated = false;
+
+        let swapchain_data = Self::create_swapchain(window, &self.instance, &self.physical_device, &self.logical_device, &self.surface_loader, &self.surface, &self.swapchain_loader, self.present_mode_preference, self.swapchain_data.swapchain)?;
+        let old_swapchain_data = std::mem::replace(&mut self.swapchain_data, swapchain_data);
+
+        //the old swapchain may still be in use by frames in flight
+        self.retire_render_targets();
+        let old_render_finished_semaphores = std::mem::take(&mut self.render_finished_semaphores);
+        self.retire(old_render_finished_semaphores);
+        self.retire(old_swapchain_data.swapchain_image_views);
+        self.retire(old_swapchain_data.swapchain);
+
+        self.render_finished_semaphores = Self::create_render_finished_semaphores(&self.logical_device, &self.swapchain_data)?;
+        self.create_render_targets()
+    }
+
+    //releases everything tied to the window (surface, swapchain and its
+    //render targets), instance, device and pipelines are kept
+    pub fn suspend(&mut self) {
+        if self.suspended {
+            return
+        }
+        self.suspended = true;
+
+        self.retire_render_targets();
+        let render_finished_semaphores = std::mem::take(&mut self.render_finished_semaphores);
+        self.retire(render_finished_semaphores);
+        let swapchain_image_views = std::mem::take(&mut self.swapchain_data.swapchain_image_views);
+        self.retire(swapchain_image_views);
+        self.retire(self.swapchain_data.swapchain);
+        self.swapchain_data.swapchain = SwapchainKHR::null();
+        self.swapchain_data.swapchain_images.clear();
+
+        //the surface (and everything presenting to it) has to go right away
+        self.wait_idle();
+        self.destroy_retired();
+        unsafe { self.surface_loader.destroy_surface(self.surface, None) };
+        self.surface = SurfaceKHR::null();
+    }
+
+    //recreates surface and swapchain for the (new) window after suspend
+    //(an error means the renderer has to be rebuilt from scratch)
+    pub fn resume(&mut self, event_loop: &ActiveEventLoop, window: &Window) -> Result<(), RenderError> {
+        if !self.suspended {
+            return Ok(())
+        }
+
+        self.surface = Self::create_surface(&self.api_entry, &self.instance, event_loop, window)?;
+        //the device's present queue has to be able to present to the new surface
+        let supported = unsafe {
+            self.surface_loader.get_physical_device_surface_support(self.physical_device, self.queues.present_family, self.surface)?
+        };
+        if !supported {
+            return Err(RenderError::SurfaceLost)
+        }
+
+        let previous_format = self.swapchain_data.swapchain_image_format;
+        self.swapchain_data = Self::create_swapchain(window, &self.instance, &self.physical_device, &self.logical_device, &self.surface_loader, &self.surface, &self.swapchain_loader, self.present_mode_preference, SwapchainKHR::null())?;
+        self.render_finished_semaphores = Self::create_render_finished_semaphores(&self.logical_device, &self.swapchain_data)?;
+        //render pass and pipelines depend on the image format
+        if self.swapchain_data.swapchain_image_format != previous_format {
+            let render_pass = std::mem::take(&mut self.render_pass);
+            self.retire(render_pass);
+            let ui_render_pass = std::mem::take(&mut self.ui_render_pass);
+            self.retire(ui_render_pass);
+            self.render_pass = Self::create_render_pass(&self.logical_device, &self.swapchain_data, self.msaa_samples)?;
+            self.ui_render_pass = Self::create_ui_render_pass(&self.logical_device, &self.swapchain_data)?;
+            self.rebuild_pipelines()?;
+        }
+        self.create_render_targets()?;
+
+        self.suspended = false;
+        self.swapchain_outdated = false;
+        Ok(())
+    }
+
+    //blocks until the gpu finished all submitted work
+    //(failures are logged, the device may already be lost)
+    pub fn wait_idle(&mut self) {
+        if let Err(err) = unsafe { self.logical_device.device_wait_idle() } {
+            println!("failed waiting for device idle: {err}");
+        }
+        //collect the timings of the frames that were still in flight
+        (0..self.frames.len()).for_each(|frame_index| self.complete_frame(frame_index));
+    }
+
+    //destroys all retired resources (the device has to be idle)
+    fn destroy_retired(&mut self) {
+        let mut ctx = DestroyContext {
+            logical_device: &self.logical_device,
+            allocator: &mut self.allocator,
+            swapchain_loader: &self.swapchain_loader
+        };
+        self.deletion_queue.flush(&mut ctx);
+    }
+
+    //changes the anti-aliasing level at runtime
+    //(recreates render pass, render targets and pipelines,
+    //an error means the renderer has to be rebuilt from scratch)
+    pub fn set_msaa(&mut self, msaa: MsaaSamples) -> Result<(), RenderError> {
+        let msaa_samples = msaa::select_sample_count(&self.instance, &self.physical_device, msaa);
+        if msaa_samples == self.msaa_samples {
+            return Ok(())
+        }
+
+        //old targets, render pass and pipelines may still be in use by frames in flight
+        self.retire_render_targets();
+        let render_pass = std::mem::take(&mut self.render_pass);
+        self.retire(render_pass);
+
+        self.msaa_samples = msaa_samples;
+        self.render_pass = Self::create_render_pass(&self.logical_device, &self.swapchain_data, self.msaa_samples)?;
+        self.create_render_targets()?;
+        self.rebuild_pipelines()?;
+        Ok(())
+    }
+
+    //rebuilds all registered pipelines, retiring the old ones
+    //(the old ones are kept if one fails)
+    fn rebuild_pipelines(&mut self) -> VkResult<()> {
+        let mut pipeline_registry = std::mem::take(&mut self.pipeline_registry);
+        let old_pipelines = pipeline_registry.rebuild_all(&self.pipeline_context());
+        self.pipeline_registry = pipeline_registry;
+        self.retire(old_pipelines?);
+        Ok(())
+    }
+
+
+    //builds (and caches) the pipeline for the given description
+    pub fn register_pipeline(&mut self, desc: &PipelineDesc) -> Result<(), RenderError> {
+        let mut pipeline_registry = std::mem::take(&mut self.pipeline_registry);
+        let result = pipeline_registry.get_or_create(&self.pipeline_context(), desc).map(|_| ());
+        self.pipeline_registry = pipeline_registry;
+        Ok(result?)
+    }
+
+    //recompiles the given glsl sources and rebuilds all pipelines
+    //(on compile errors the diagnostics are printed and the old pipelines are kept)
+    pub fn reload_shaders(&mut self, sources: &[PathBuf]) -> bool {
+        let compiled = match ShaderLibrary::compile_sources(sources) {
+            Ok(compiled) => compiled,
+            Err(diagnostics) => {
+                println!("shader compilation failed, keeping the current pipeline:\n{diagnostics}");
+                return false
+            }
+        };
+        if compiled.is_empty() {
+            return false
+        }
+
+        self.shader_library.insert_compiled(compiled);
+
+        if let Err(err) = self.rebuild_pipelines() {
+            println!("failed rebuilding pipelines: {err}; keeping the current pipelines");
+            return false
+        }
+
+        println!("reloaded shaders: {sources:?}");
+        true
+    }
+
+    //messages reported by the validation layers so far
+    //(empty if validation is disabled or unavailable)
+    pub fn debug_log(&self) -> &Arc<DebugMessageLog> {
+        &self.debug_log
+    }
+
+    //runs a rendering scenario and fails if it caused validation errors
+    //(only meaningful when validation is enabled)
+    pub fn check_no_validation_errors<R>(&mut self, scenario: impl FnOnce(&mut Self) -> R) -> Result<R, ValidationFailure> {
+        let debug_log = Arc::clone(&self.debug_log);
+        let checkpoint = debug_log.counts();
+        let result = scenario(self);
+        debug_log.check_no_errors_since(&checkpoint)?;
+        Ok(result)
+    }
+
+    pub fn validation_enabled(&self) -> bool {
+        self.debug_ctx.is_some()
+    }
 }
 impl Drop for Renderer {
-    //cleanup of vulkan objects (LIFO)
+    //everything still owned is retired (on top of the pending resources)
+    //and destroyed at once, then the device level objects go (LIFO)
     fn drop(&mut self) {
         println!("cleaning up the renderer!");
+        //wait for submitted frames to finish
+        self.wait_idle();
+        if let Some(stats_csv) = self.stats_csv.as_mut()
+            && let Err(err) = stats_csv.flush() {
+            println!("failed flushing frame stats: {err}");
+        }
+
+        let gpu_timer = self.gpu_timer.take();
+        self.retire(gpu_timer);
+        let frames = std::mem::take(&mut self.frames);
+        self.retire(frames);
+        self.retire(self.command_pool);
+        self.retire_render_targets();
+        let render_finished_semaphores = std::mem::take(&mut self.render_finished_semaphores);
+        self.retire(render_finished_semaphores);
+        let ui_resources = self.ui_painter.take_all();
+        self.retire(ui_resources);
+        let pipelines = self.pipeline_registry.take_all();
+        self.retire(pipelines);
+        self.retire(self.texture_set_layout);
+        self.retire(self.render_pass);
+        self.retire(self.ui_render_pass);
+        self.retire(self.offscreen_render_pass);
+        let swapchain_image_views = std::mem::take(&mut self.swapchain_data.swapchain_image_views);
+        self.retire(swapchain_image_views);
+        self.retire(self.swapchain_data.swapchain);
+
+        self.destroy_retired();
+
         unsafe {
-            //destroy pipeline
-            self.logical_device.destroy_pipeline(self.graphics_pipeline_data.pipeline, None);
-            //destroy pipeline layout
-            self.logical_device
-                .destroy_pipeline_layout(self.graphics_pipeline_data.pipeline_layout, None);
-            //destroy render pass
-            self.logical_device
-                .destroy_render_pass(self.render_pass, None);
-            //destroy swapchain image views
-            self.swapchain_data.swapchain_image_views.iter().for_each(|swapchain_image_view| {
-                self.logical_device.destroy_image_view(*swapchain_image_view, None);
-            });
-            //destroy swapchain
-            self.swapchain_loader.destroy_swapchain(self.swapchain_data.swapchain, None);
+            //persist and destroy pipeline cache
+            self.pipeline_cache.save(&self.logical_device);
+            self.pipeline_cache.destroy(&self.logical_device);
+            //free device memory (reports leaked allocations)
+            self.allocator.destroy(&self.logical_device);
             //destroy logical device
             self.logical_device.destroy_device(None);
-            //destroy surface
-            self.surface_loader.destroy_surface(self.surface, None); 
+            //destroy surface (null while suspended)
+            self.surface_loader.destroy_surface(self.surface, None);
             //destroy debug_call_back (if it exists)
-            if self.debug_ctx.is_some() {
-                let debug_ctx= self.debug_ctx.as_ref();
-                debug_ctx.unwrap().debug_utils_loader
-                    .destroy_debug_utils_messenger(
-                        debug_ctx.unwrap().debug_call_back, 
-                        None
-                    );
-            } 
+            if let Some(debug_ctx) = self.debug_ctx.as_ref() {
+                debug_ctx.debug_utils_loader
+                    .destroy_debug_utils_messenger(debug_ctx.debug_call_back, None);
+            }
             //destroy vulkan instance
             self.instance.destroy_instance(None);
         }
+
+        if !self.stats.is_empty() {
+            println!("frame stats (last {})", self.stats.summary());
+        }
+
+        let counts = self.debug_log.counts();
+        if counts.errors > 0 || counts.warnings > 0 {
+            println!(
+                "validation summary: {} error(s), {} warning(s)",
+                counts.errors,
+                counts.warnings
+            );
+        }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::application::ApplicationHandler;
+    use winit::event::WindowEvent;
+    use winit::event_loop::EventLoop;
+    use winit::platform::x11::EventLoopBuilderExtX11;
+    use winit::window::WindowId;
+    use crate::fault::Fault;
+    use crate::validation::VALIDATION_LAYER_NAME;
+
+    #[test]
+    fn auto_prefers_fifo_relaxed() {
+        let all = [PresentModeKHR::IMMEDIATE, PresentModeKHR::MAILBOX, PresentModeKHR::FIFO_RELAXED, PresentModeKHR::FIFO];
+        assert_eq!(Renderer::chose_swapchain_present_mode(&all, PresentModePreference::Auto), PresentModeKHR::FIFO_RELAXED);
+        let without_relaxed = [PresentModeKHR::MAILBOX, PresentModeKHR::FIFO];
+        assert_eq!(Renderer::chose_swapchain_present_mode(&without_relaxed, PresentModePreference::Auto), PresentModeKHR::FIFO);
+    }
+
+    #[test]
+    fn unsupported_modes_fall_back_to_fifo() {
+        let available = [PresentModeKHR::FIFO];
+        for preference in PresentModePreference::ALL {
+            assert_eq!(Renderer::chose_swapchain_present_mode(&available, preference), PresentModeKHR::FIFO);
+        }
+        let available = [PresentModeKHR::FIFO, PresentModeKHR::MAILBOX];
+        assert_eq!(Renderer::chose_swapchain_present_mode(&available, PresentModePreference::Mailbox), PresentModeKHR::MAILBOX);
+    }
+
+    #[test]
+    fn composite_alpha_prefers_premultiplied() {
+        let all = CompositeAlphaFlagsKHR::OPAQUE | CompositeAlphaFlagsKHR::PRE_MULTIPLIED | CompositeAlphaFlagsKHR::POST_MULTIPLIED | CompositeAlphaFlagsKHR::INHERIT;
+        assert_eq!(Renderer::chose_composite_alpha(all), CompositeAlphaFlagsKHR::PRE_MULTIPLIED);
+        let without_pre = CompositeAlphaFlagsKHR::OPAQUE | CompositeAlphaFlagsKHR::POST_MULTIPLIED | CompositeAlphaFlagsKHR::INHERIT;
+        assert_eq!(Renderer::chose_composite_alpha(without_pre), CompositeAlphaFlagsKHR::POST_MULTIPLIED);
+        let inherit = CompositeAlphaFlagsKHR::OPAQUE | CompositeAlphaFlagsKHR::INHERIT;
+        assert_eq!(Renderer::chose_composite_alpha(inherit), CompositeAlphaFlagsKHR::INHERIT);
+        assert_eq!(Renderer::chose_composite_alpha(CompositeAlphaFlagsKHR::OPAQUE), CompositeAlphaFlagsKHR::OPAQUE);
+    }
+
+    //scenario on a real device, reports failures instead of panicking
+    //(the event loop is still running)
+    type Scenario = fn(&ActiveEventLoop, &Window) -> Result<(), String>;
+
+    //runs the scenarios once the event loop resumed (windows can only be created then)
+    struct ScenarioRunner {
+        scenarios: Vec<(&'static str, Scenario)>,
+        results: Vec<(&'static str, Result<(), String>)>
+    }
+    impl ApplicationHandler for ScenarioRunner {
+        fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+            let window = event_loop
+                .create_window(Window::default_attributes().with_title("custicle scenarios"))
+                .expect("failed creating window!");
+            for (name, scenario) in std::mem::take(&mut self.scenarios) {
+                self.results.push((name, scenario(event_loop, &window)));
+            }
+            event_loop.exit();
+        }
+
+        fn window_event(&mut self, _event_loop: &ActiveEventLoop, _window_id: WindowId, _event: WindowEvent) {}
+    }
+
+    fn scenario_config() -> Config {
+        let mut config = Config::default();
+        config.validation.enabled = true;
+        config
+    }
+
+    //the app's recovery: the draw call fails, the broken renderer is dropped
+    //and a new one is built for the same window
+    fn faults_are_recovered(event_loop: &ActiveEventLoop, window: &Window) -> Result<(), String> {
+        let config = scenario_config();
+        let crosshair = Crosshair::default();
+        for fault in [Fault::DeviceLost, Fault::SurfaceLost] {
+            let mut renderer = Renderer::new(event_loop, window, &config)
+                .map_err(|err| format!("{fault:?}: creating the renderer: {err}"))?;
+            renderer.inject_fault(ScheduledFault { fault, after_frames: 2 });
+            for _ in 0..2 {
+                renderer.draw(window, &crosshair)
+                    .map_err(|err| format!("{fault:?}: drawing before the fault: {err}"))?;
+            }
+            let result = renderer.draw(window, &crosshair);
+            if result != Err(fault.into()) {
+                return Err(format!("{fault:?}: expected the injected fault, got {result:?}"))
+            }
+
+            drop(renderer);
+            let mut renderer = Renderer::new(event_loop, window, &config)
+                .map_err(|err| format!("{fault:?}: rebuilding the renderer: {err}"))?;
+            for _ in 0..3 {
+                renderer.draw(window, &crosshair)
+                    .map_err(|err| format!("{fault:?}: drawing after the rebuild: {err}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    //creation, drawing (with a swapchain recreation and an msaa change), a
+    //thumbnail and the teardown must not cause validation errors
+    fn renders_without_validation_errors(event_loop: &ActiveEventLoop, window: &Window) -> Result<(), String> {
+        let mut renderer = Renderer::new(event_loop, window, &scenario_config())
+            .map_err(|err| format!("creating the renderer: {err}"))?;
+        if !renderer.validation_enabled() {
+            return Err(format!("{VALIDATION_LAYER_NAME:?} is not installed"))
+        }
+        let debug_log = Arc::clone(renderer.debug_log());
+        debug_log.check_no_errors().map_err(|failure| format!("creating the renderer: {failure}"))?;
+
+        let crosshair = Crosshair::default();
+        renderer
+            .check_no_validation_errors(|renderer| -> Result<(), RenderError> {
+                renderer.set_show_stats(true);
+                renderer.set_show_center_guide(true);
+                for _ in 0..3 {
+                    renderer.draw(window, &crosshair)?;
+                }
+                renderer.resized();
+                renderer.draw(window, &crosshair)?;
+                renderer.set_msaa(MsaaSamples::X4)?;
+                renderer.draw(window, &crosshair)?;
+                renderer.render_thumbnail(&crosshair, 64)?;
+                Ok(())
+            })
+            .map_err(|failure| format!("drawing: {failure}"))?
+            .map_err(|err| format!("drawing failed: {err}"))?;
+
+        //the log outlives the renderer, leaked objects are reported on teardown
+        let checkpoint = debug_log.counts();
+        drop(renderer);
+        debug_log.check_no_errors_since(&checkpoint).map_err(|failure| format!("dropping the renderer: {failure}"))
+    }
+
+    //VK_DRIVER_FILES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json xvfb-run cargo test -p engine -- --ignored
+    //(validation scenarios need the khronos validation layer installed)
+    //(winit allows one event loop per process, so all scenarios share this test)
+    #[test]
+    #[ignore = "needs an x11 display and lavapipe"]
+    fn lavapipe_scenarios() {
+        let event_loop = EventLoop::builder()
+            .with_any_thread(true)
+            .build()
+            .expect("failed creating event loop!");
+        let mut runner = ScenarioRunner {
+            scenarios: vec![
+                ("renders without validation errors", renders_without_validation_errors),
+                ("faults are recovered", faults_are_recovered)
+            ],
+            results: Vec::new()
+        };
+        event_loop.run_app(&mut runner).expect("event loop failed!");
+
+        assert!(!runner.results.is_empty(), "the event loop never resumed");
+        let failures: Vec<String> = runner.results
+            .iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|err| format!("{name}: {err}")))
+            .collect();
+        assert!(failures.is_empty(), "failed scenarios: {failures:#?}");
+    }
+
+    #[test]
+    fn present_mode_names_round_trip() {
+        for preference in PresentModePreference::ALL {
+            assert_eq!(PresentModePreference::parse(preference.name()), Some(preference));
+        }
+        let mut preference = PresentModePreference::Auto;
+        for _ in PresentModePreference::ALL {
+            preference = preference.cycled();
+        }
+        assert_eq!(preference, PresentModePreference::Auto);
+    }
+}
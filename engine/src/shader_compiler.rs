@@ -0,0 +1,59 @@
+//glsl -> spir-v compilation (naga)
+//NOTE: also included by build.rs through #[path], so this
+//      file must not depend on anything else in the crate
+use std::path::Path;
+use naga::back::spv;
+use naga::front::glsl;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment
+}
+impl ShaderStage {
+    //stage derived from the file extension (.vert / .frag)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "vert" => Some(Self::Vertex),
+            "frag" => Some(Self::Fragment),
+            _ => None
+        }
+    }
+
+    fn naga_stage(self) -> naga::ShaderStage {
+        match self {
+            Self::Vertex => naga::ShaderStage::Vertex,
+            Self::Fragment => naga::ShaderStage::Fragment
+        }
+    }
+}
+
+//name of the compiled shader (crosshair.vert -> crosshair_vert.spv)
+pub fn spirv_file_name(source_path: &Path) -> Option<String> {
+    let stem = source_path.file_stem()?.to_str()?;
+    let extension = source_path.extension()?.to_str()?;
+    Some(format!("{stem}_{extension}.spv"))
+}
+
+//compiles glsl source into spir-v words
+//(errors contain the human readable compiler diagnostics)
+pub fn compile_glsl(file_name: &str, source: &str, stage: ShaderStage) -> Result<Vec<u32>, String> {
+    let module = glsl::Frontend::default()
+        .parse(&glsl::Options::from(stage.naga_stage()), source)
+        .map_err(|err| format!("{file_name}:\n{}", err.emit_to_string(source)))?;
+
+    let module_info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|err| err.emit_to_string_with_path(source, file_name))?;
+
+    let options = spv::Options {
+        //naga flips y by default (wgpu convention),
+        //vulkan glsl has to be emitted untouched
+        flags: spv::WriterFlags::empty(),
+        ..Default::default()
+    };
+
+    spv::write_vec(&module, &module_info, &options, None)
+        .map_err(|err| format!("{file_name}: {err}"))
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use ash::util::read_spv;
+
+use crate::shader_compiler::{self, ShaderStage};
+
+//spir-v compiled from ../shaders by build.rs
+include!(concat!(env!("OUT_DIR"), "/embedded_shaders.rs"));
+
+//environment variable pointing to a directory with .spv files
+//that take precedence over the embedded shaders (development)
+pub const SHADER_DIR_ENV_VAR: &str = "CUSTICLE_SHADER_DIR";
+
+//glsl sources the binary was built from (default hot-reload directory)
+pub const SOURCE_SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../shaders");
+
+fn embedded_spirv(name: &str) -> Option<&'static [u8]> {
+    EMBEDDED_SHADERS
+        .iter()
+        .find(|(embedded_name, _)| *embedded_name == name)
+        .map(|(_, bytes)| *bytes)
+}
+
+//loads a shader by its spir-v file name (e.g. "crosshair_vert.spv")
+//from the override directory (if set and present) or the binary
+fn load_spirv(name: &str, override_dir: Option<&Path>) -> Vec<u32> {
+    if let Some(override_dir) = override_dir {
+        let path = override_dir.join(name);
+        match fs::read(&path) {
+            Ok(bytes) => match read_spv(&mut Cursor::new(&bytes)) {
+                Ok(spirv) => return spirv,
+                Err(err) => println!("ignoring invalid shader override {}: {err}", path.display())
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+            Err(err) => println!("failed reading shader override {}: {err}", path.display())
+        }
+    }
+
+    let bytes = embedded_spirv(name)
+        .unwrap_or_else(|| panic!("no embedded shader named {name}!"));
+    read_spv(&mut Cursor::new(bytes))
+        .expect("failed to convert u8-slice to u32-vec")
+}
+
+//resolves shaders for pipeline creation:
+//hot-reloaded spir-v > override directory > embedded spir-v
+#[derive(Default)]
+pub(crate) struct ShaderLibrary {
+    override_dir: Option<PathBuf>,
+    //spir-v compiled at runtime, keyed by spir-v file name
+    compiled: HashMap<String, Vec<u32>>
+}
+impl ShaderLibrary {
+    pub(crate) fn new(override_dir: Option<PathBuf>) -> Self {
+        Self { override_dir, compiled: HashMap::new() }
+    }
+
+    pub(crate) fn spirv(&self, name: &str) -> Vec<u32> {
+        match self.compiled.get(name) {
+            Some(spirv) => spirv.clone(),
+            None => load_spirv(name, self.override_dir.as_deref())
+        }
+    }
+
+    //compiles the given glsl sources without touching the library
+    //(all or nothing: the first failure returns its diagnostics)
+    pub(crate) fn compile_sources(sources: &[PathBuf]) -> Result<Vec<(String, Vec<u32>)>, String> {
+        let mut compiled = Vec::with_capacity(sources.len());
+        for source_path in sources.iter() {
+            let (Some(stage), Some(spv_name)) = (
+                ShaderStage::from_path(source_path),
+                shader_compiler::spirv_file_name(source_path)
+            ) else {
+                continue
+            };
+
+            let source = fs::read_to_string(source_path)
+                .map_err(|err| format!("failed reading {}: {err}", source_path.display()))?;
+            let file_name = source_path.display().to_string();
+            let spirv = shader_compiler::compile_glsl(&file_name, &source, stage)?;
+            compiled.push((spv_name, spirv));
+        }
+        Ok(compiled)
+    }
+
+    pub(crate) fn insert_compiled(&mut self, compiled: Vec<(String, Vec<u32>)>) {
+        self.compiled.extend(compiled);
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use ash::{Device, Instance};
+use ash::vk::{CommandBuffer, PhysicalDevice, PipelineStageFlags, QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType};
+
+use crate::crosshair::{Color, Rect};
+use crate::deletion::{DestroyContext, DeviceResource};
+use crate::helper;
+
+//amount of frames kept by the rolling statistics
+pub const STATS_WINDOW: usize = 240;
+
+//cpu time spent in the individual steps of Renderer::draw
+//(waiting for the frame fence is throttling, not cost, and excluded)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTimings {
+    pub acquire: Duration,
+    pub record: Duration,
+    pub submit: Duration,
+    pub present: Duration
+}
+impl CpuTimings {
+    pub fn total(&self) -> Duration {
+        self.acquire + self.record + self.submit + self.present
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSample {
+    //running frame number
+    pub frame: u64,
+    pub cpu: CpuTimings,
+    //time the gpu spent executing the frame's command buffer
+    //(none until the frame finished or without timestamp support)
+    pub gpu: Option<Duration>
+}
+
+//rolling statistics over the last STATS_WINDOW frames
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    samples: VecDeque<FrameSample>
+}
+impl FrameStats {
+    pub(crate) fn push(&mut self, sample: FrameSample) {
+        if self.samples.len() == STATS_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    //gpu timestamps arrive once the frame's fence is signaled,
+    //returns the completed sample (none if it left the window)
+    pub(crate) fn resolve_gpu_time(&mut self, frame: u64, gpu: Option<Duration>) -> Option<FrameSample> {
+        let sample = self.samples.iter_mut().rev().find(|sample| sample.frame == frame)?;
+        sample.gpu = gpu;
+        Some(*sample)
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &FrameSample> {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<&FrameSample> {
+        self.samples.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    //mean cpu timings over the window
+    pub fn average_cpu(&self) -> Option<CpuTimings> {
+        if self.samples.is_empty() {
+            return None
+        }
+        let count = helper::usize_into_u32(self.samples.len());
+        let sum = self.samples.iter().fold(CpuTimings::default(), |sum, sample| CpuTimings {
+            acquire: sum.acquire + sample.cpu.acquire,
+            record: sum.record + sample.cpu.record,
+            submit: sum.submit + sample.cpu.submit,
+            present: sum.present + sample.cpu.present
+        });
+        Some(CpuTimings {
+            acquire: sum.acquire / count,
+            record: sum.record / count,
+            submit: sum.submit / count,
+            present: sum.present / count
+        })
+    }
+
+    //mean gpu time over the frames that have a timestamp
+    pub fn average_gpu(&self) -> Option<Duration> {
+        let gpu_times: Vec<Duration> = self.samples.iter().filter_map(|sample| sample.gpu).collect();
+        if gpu_times.is_empty() {
+            return None
+        }
+        Some(gpu_times.iter().sum::<Duration>() / helper::usize_into_u32(gpu_times.len()))
+    }
+
+    pub fn max_cpu(&self) -> Option<Duration> {
+        self.samples.iter().map(|sample| sample.cpu.total()).max()
+    }
+
+    pub fn max_gpu(&self) -> Option<Duration> {
+        self.samples.iter().filter_map(|sample| sample.gpu).max()
+    }
+
+    pub fn summary(&self) -> String {
+        let millis = |duration: Option<Duration>| match duration {
+            Some(duration) => format!("{:.3} ms", duration.as_secs_f64() * 1000.0),
+            None => "n/a".to_string()
+        };
+        format!(
+            "{} frame(s): cpu avg {} (max {}), gpu avg {} (max {})",
+            self.samples.len(),
+            millis(self.average_cpu().map(|cpu| cpu.total())),
+            millis(self.max_cpu()),
+            millis(self.average_gpu()),
+            millis(self.max_gpu())
+        )
+    }
+
+    //bar graph in the bottom-left corner: one bar per frame, cpu on
+    //top of gpu time, with a marker line at one millisecond
+    pub fn readout(&self, width: u32, height: u32) -> Vec<Rect> {
+        const BAR_WIDTH: f32 = 1.0;
+        const PIXELS_PER_MILLISECOND: f32 = 20.0;
+        const MARGIN: f32 = 8.0;
+        const CPU_COLOR: Color = Color::rgba(0.2, 0.6, 1.0, 0.8);
+        const GPU_COLOR: Color = Color::rgba(1.0, 0.6, 0.2, 0.8);
+        const MARKER_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+
+        let base_y = height as f32 - MARGIN;
+        let graph_width = (STATS_WINDOW as f32 * BAR_WIDTH).min(width as f32 - 2.0 * MARGIN);
+        if graph_width <= 0.0 {
+            return Vec::new()
+        }
+        let bar_height = |duration: Duration| (duration.as_secs_f32() * 1000.0 * PIXELS_PER_MILLISECOND).min(base_y);
+
+        let mut rects = Vec::with_capacity(2 * self.samples.len() + 1);
+        rects.push(Rect { x: MARGIN, y: base_y - PIXELS_PER_MILLISECOND, width: graph_width, height: 1.0, color: MARKER_COLOR });
+
+        //newest frame on the right
+        let visible = (graph_width / BAR_WIDTH) as usize;
+        for (index, sample) in self.samples.iter().rev().take(visible).enumerate() {
+            let x = MARGIN + graph_width - (index + 1) as f32 * BAR_WIDTH;
+            let gpu = bar_height(sample.gpu.unwrap_or_default());
+            let cpu = bar_height(sample.cpu.total());
+            rects.push(Rect { x, y: base_y - gpu, width: BAR_WIDTH, height: gpu, color: GPU_COLOR });
+            rects.push(Rect { x, y: base_y - gpu - cpu, width: BAR_WIDTH, height: cpu, color: CPU_COLOR });
+        }
+        rects
+    }
+}
+
+//appends every completed frame sample to a csv file
+pub(crate) struct StatsCsv {
+    writer: BufWriter<File>
+}
+impl StatsCsv {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "frame,acquire_us,record_us,submit_us,present_us,cpu_total_us,gpu_us")?;
+        Ok(Self { writer })
+    }
+
+    pub(crate) fn write(&mut self, sample: &FrameSample) -> io::Result<()> {
+        let micros = |duration: Duration| duration.as_secs_f64() * 1_000_000.0;
+        let gpu = sample.gpu.map(|gpu| format!("{:.3}", micros(gpu))).unwrap_or_default();
+        writeln!(
+            self.writer,
+            "{},{:.3},{:.3},{:.3},{:.3},{:.3},{gpu}",
+            sample.frame,
+            micros(sample.cpu.acquire),
+            micros(sample.cpu.record),
+            micros(sample.cpu.submit),
+            micros(sample.cpu.present),
+            micros(sample.cpu.total())
+        )
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+//gpu timestamps at the start and end of each frame's command buffer
+//(two queries per frame in flight)
+pub(crate) struct GpuTimer {
+    query_pool: QueryPool,
+    //nanoseconds per timestamp tick
+    timestamp_period: f32,
+    //timestamps only have timestamp_valid_bits significant bits
+    valid_mask: u64
+}
+impl GpuTimer {
+    //none if the graphics queue doesn't support timestamps
+    //(or the query pool can't be created)
+    pub(crate) fn new(instance: &Instance, physical_device: &PhysicalDevice, logical_device: &Device, graphics_family: u32, frames_in_flight: usize) -> Option<Self> {
+        let (valid_bits, timestamp_period) = unsafe {
+            let queue_families = instance.get_physical_device_queue_family_properties(*physical_device);
+            let limits = instance.get_physical_device_properties(*physical_device).limits;
+            (queue_families[graphics_family as usize].timestamp_valid_bits, limits.timestamp_period)
+        };
+        if valid_bits == 0 {
+            println!("gpu timestamps not supported by the graphics queue; only cpu timings are collected");
+            return None
+        }
+
+        let query_pool_create_info = QueryPoolCreateInfo {
+            query_type: QueryType::TIMESTAMP,
+            query_count: helper::usize_into_u32(2 * frames_in_flight),
+            ..Default::default()
+        };
+        let query_pool = match unsafe { logical_device.create_query_pool(&query_pool_create_info, None) } {
+            Ok(query_pool) => query_pool,
+            Err(err) => {
+                println!("failed creating timestamp query pool: {err}; only cpu timings are collected");
+                return None
+            }
+        };
+        let valid_mask = if valid_bits >= 64 { u64::MAX } else { (1 << valid_bits) - 1 };
+
+        Some(Self { query_pool, timestamp_period, valid_mask })
+    }
+
+    fn first_query(frame_index: usize) -> u32 {
+        helper::usize_into_u32(2 * frame_index)
+    }
+
+    //start of the command buffer (outside of the render pass)
+    pub(crate) fn cmd_begin(&self, logical_device: &Device, command_buffer: CommandBuffer, frame_index: usize) {
+        let first_query = Self::first_query(frame_index);
+        unsafe {
+            logical_device.cmd_reset_query_pool(command_buffer, self.query_pool, first_query, 2);
+            logical_device.cmd_write_timestamp(command_buffer, PipelineStageFlags::TOP_OF_PIPE, self.query_pool, first_query);
+        }
+    }
+
+    //end of the command buffer (outside of the render pass)
+    pub(crate) fn cmd_end(&self, logical_device: &Device, command_buffer: CommandBuffer, frame_index: usize) {
+        unsafe {
+            logical_device.cmd_write_timestamp(command_buffer, PipelineStageFlags::BOTTOM_OF_PIPE, self.query_pool, Self::first_query(frame_index) + 1);
+        }
+    }
+
+    //only called once the frame's fence is signaled: the fence covers the
+    //whole submission, so both timestamps are available by then and WAIT
+    //isn't needed (a NOT_READY anyway leaves the sample without gpu time
+    //instead of stalling the frame)
+    pub(crate) fn read(&self, logical_device: &Device, frame_index: usize) -> Option<Duration> {
+        let mut timestamps = [0u64; 2];
+        unsafe {
+            logical_device
+                .get_query_pool_results(self.query_pool, Self::first_query(frame_index), &mut timestamps, QueryResultFlags::TYPE_64)
+                .ok()?;
+        }
+        let ticks = timestamps[1].wrapping_sub(timestamps[0]) & self.valid_mask;
+        Some(Duration::from_nanos((ticks as f64 * self.timestamp_period as f64) as u64))
+    }
+}
+impl DeviceResource for GpuTimer {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_query_pool(self.query_pool, None) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(frame: u64, cpu_micros: u64, gpu_micros: Option<u64>) -> FrameSample {
+        let quarter = Duration::from_micros(cpu_micros) / 4;
+        FrameSample {
+            frame,
+            cpu: CpuTimings { acquire: quarter, record: quarter, submit: quarter, present: quarter },
+            gpu: gpu_micros.map(Duration::from_micros)
+        }
+    }
+
+    fn stats(samples: &[FrameSample]) -> FrameStats {
+        let mut stats = FrameStats::default();
+        samples.iter().for_each(|sample| stats.push(*sample));
+        stats
+    }
+
+    #[test]
+    fn averages_and_maxima() {
+        let stats = stats(&[sample(0, 1000, Some(400)), sample(1, 3000, None), sample(2, 2000, Some(800))]);
+        assert_eq!(stats.average_cpu().unwrap().total(), Duration::from_micros(2000));
+        assert_eq!(stats.max_cpu(), Some(Duration::from_micros(3000)));
+        //frames without a timestamp don't count
+        assert_eq!(stats.average_gpu(), Some(Duration::from_micros(600)));
+        assert_eq!(stats.max_gpu(), Some(Duration::from_micros(800)));
+        assert_eq!(stats.summary(), "3 frame(s): cpu avg 2.000 ms (max 3.000 ms), gpu avg 0.600 ms (max 0.800 ms)");
+    }
+
+    #[test]
+    fn empty_stats() {
+        let stats = FrameStats::default();
+        assert_eq!(stats.average_cpu(), None);
+        assert_eq!(stats.summary(), "0 frame(s): cpu avg n/a (max n/a), gpu avg n/a (max n/a)");
+    }
+
+    #[test]
+    fn window_keeps_newest_frames() {
+        let mut stats = stats(&(0..STATS_WINDOW as u64 + 10).map(|frame| sample(frame, 1000, None)).collect::<Vec<_>>());
+        assert_eq!(stats.len(), STATS_WINDOW);
+        assert_eq!(stats.samples().next().unwrap().frame, 10);
+
+        let resolved = stats.resolve_gpu_time(STATS_WINDOW as u64, Some(Duration::from_micros(5))).unwrap();
+        assert_eq!(resolved.gpu, Some(Duration::from_micros(5)));
+        //left the window
+        assert_eq!(stats.resolve_gpu_time(5, Some(Duration::from_micros(5))), None);
+    }
+
+    #[test]
+    fn readout_stacks_cpu_on_gpu() {
+        let stats = stats(&[sample(0, 1000, Some(500)), sample(1, 500, None)]);
+        let rects = stats.readout(800, 600);
+        //marker plus two bars per frame
+        assert_eq!(rects.len(), 5);
+        let marker = rects[0];
+        assert_eq!((marker.height, marker.y), (1.0, 600.0 - 8.0 - 20.0));
+
+        //newest frame first, at the right edge
+        let (gpu, cpu) = (rects[1], rects[2]);
+        assert_eq!(gpu.height, 0.0);
+        assert_eq!(cpu.height, 10.0);
+        assert_eq!(gpu.x, 8.0 + STATS_WINDOW as f32 - 1.0);
+        let (gpu, cpu) = (rects[3], rects[4]);
+        assert_eq!(gpu.height, 10.0);
+        assert_eq!(cpu.y + cpu.height, gpu.y);
+        assert!(rects.iter().all(|rect| rect.y + rect.height <= 600.0 - 8.0));
+    }
+
+    #[test]
+    fn readout_fits_tiny_windows() {
+        let stats = stats(&[sample(0, 1000, None)]);
+        assert!(stats.readout(10, 10).is_empty());
+        assert_eq!(stats.readout(20, 600).len(), 3);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let path = std::env::temp_dir().join(format!("custicle-stats-{}.csv", std::process::id()));
+        let mut csv = StatsCsv::create(&path).unwrap();
+        csv.write(&sample(7, 2000, Some(250))).unwrap();
+        csv.write(&sample(8, 1000, None)).unwrap();
+        csv.flush().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), [
+            "frame,acquire_us,record_us,submit_us,present_us,cpu_total_us,gpu_us",
+            "7,500.000,500.000,500.000,500.000,2000.000,250.000",
+            "8,250.000,250.000,250.000,250.000,1000.000,"
+        ]);
+    }
+}
@@ -0,0 +1,263 @@
+use std::time::Duration;
+use egui::load::SizedTexture;
+use egui::{Align2, Button, ColorImage, ComboBox, Context, DragValue, Image, Pos2, Rect, RichText, ScrollArea, Slider, TextureHandle, TextureOptions, Ui, Vec2, ViewportId};
+use egui_winit::{EventResponse, State};
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+use crate::crosshair::{Color, Crosshair, ExtraPixelSide, RenderMode, Shape};
+use crate::presets::{Preset, PresetSource, THUMBNAIL_SIZE};
+use crate::ui_painter::UiFrame;
+
+//result of running the editor for one frame
+pub(crate) struct EditorOutput {
+    pub(crate) frame: UiFrame,
+    //egui wants another frame after this delay (Duration::MAX: not at all)
+    pub(crate) repaint_delay: Duration,
+    //a widget is being dragged (its changes belong to one undo step)
+    pub(crate) interacting: bool,
+    //"clone" was clicked for this preset
+    pub(crate) clone_preset: Option<String>
+}
+
+//thumbnails share one texture (the ui painter has a fixed number of
+//texture slots), ATLAS_COLUMNS² of them at most
+const ATLAS_COLUMNS: usize = 16;
+
+//entry of the presets window
+struct PresetTile {
+    preset: Preset,
+    //uv rect in the thumbnail atlas, none if rendering it failed
+    //or the atlas is full
+    thumbnail: Option<Rect>
+}
+
+//egui configurator for the crosshair, edits are applied directly
+//to the crosshair and visible with the same frame
+pub(crate) struct Editor {
+    ctx: Context,
+    state: State,
+    presets: Vec<PresetTile>,
+    thumbnail_atlas: Option<TextureHandle>
+}
+impl Editor {
+    pub(crate) fn new(window: &Window) -> Self {
+        let ctx = Context::default();
+        let state = State::new(ctx.clone(), ViewportId::ROOT, window, Some(window.scale_factor() as f32), None, None);
+        Self { ctx, state, presets: Vec::new(), thumbnail_atlas: None }
+    }
+
+    //replaces the presets window contents, thumbnails are THUMBNAIL_SIZE²
+    //premultiplied rgba8 pixels (see ThumbnailCache)
+    pub(crate) fn set_presets(&mut self, presets: Vec<(Preset, Option<Vec<u8>>)>) {
+        let thumbnail_size = THUMBNAIL_SIZE as usize;
+        let thumbnail_count = presets.iter().filter(|(_, pixels)| pixels.is_some()).count().min(ATLAS_COLUMNS * ATLAS_COLUMNS);
+        let rows = thumbnail_count.div_ceil(ATLAS_COLUMNS).max(1);
+        let atlas_size = [ATLAS_COLUMNS * thumbnail_size, rows * thumbnail_size];
+        let mut atlas = vec![0; atlas_size[0] * atlas_size[1] * 4];
+        //uv size of one thumbnail
+        let cell = Vec2::new(1.0 / ATLAS_COLUMNS as f32, 1.0 / rows as f32);
+
+        let mut slot = 0;
+        self.presets = presets.into_iter().map(|(preset, pixels)| {
+            let thumbnail = pixels.filter(|_| slot < thumbnail_count).map(|pixels| {
+                let (column, row) = (slot % ATLAS_COLUMNS, slot / ATLAS_COLUMNS);
+                slot += 1;
+                let row_bytes = thumbnail_size * 4;
+                for (y, line) in pixels.chunks_exact(row_bytes).enumerate() {
+                    let start = ((row * thumbnail_size + y) * atlas_size[0] + column * thumbnail_size) * 4;
+                    atlas[start..start + row_bytes].copy_from_slice(line);
+                }
+                Rect::from_min_size(Pos2::new(column as f32 * cell.x, row as f32 * cell.y), cell)
+            });
+            PresetTile { preset, thumbnail }
+        }).collect();
+
+        //pixel exact, like the crosshair itself; reusing the handle
+        //replaces the texture instead of adding one
+        let atlas = ColorImage::from_rgba_premultiplied(atlas_size, &atlas);
+        match self.thumbnail_atlas.as_mut() {
+            Some(handle) => handle.set(atlas, TextureOptions::NEAREST),
+            None => self.thumbnail_atlas = Some(self.ctx.load_texture("preset thumbnails", atlas, TextureOptions::NEAREST))
+        }
+    }
+
+    //feeds a window event to egui (repaint: the ui has to be redrawn)
+    pub(crate) fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> EventResponse {
+        self.state.on_window_event(window, event)
+    }
+
+    //`hud_line` is shown at the bottom (keyboard editing values)
+    pub(crate) fn run(&mut self, window: &Window, crosshair: &mut Crosshair, hud_line: &str) -> EditorOutput {
+        let raw_input = self.state.take_egui_input(window);
+        let mut clone_preset = None;
+        let full_output = self.ctx.run(raw_input, |ctx| {
+            egui::Window::new("crosshair")
+                .resizable(false)
+                .show(ctx, |ui| crosshair_editor(ui, crosshair));
+            egui::Window::new("presets")
+                .default_open(false)
+                .show(ctx, |ui| clone_preset = presets_browser(ui, &self.presets, self.thumbnail_atlas.as_ref(), crosshair));
+            egui::Area::new("hud".into())
+                .anchor(Align2::LEFT_BOTTOM, [8.0, -8.0])
+                .interactable(false)
+                .show(ctx, |ui| ui.label(RichText::new(hud_line).monospace()));
+        });
+        self.state.handle_platform_output(window, full_output.platform_output);
+
+        let repaint_delay = full_output.viewport_output
+            .get(&ViewportId::ROOT)
+            .map_or(Duration::MAX, |viewport_output| viewport_output.repaint_delay);
+        EditorOutput {
+            frame: UiFrame {
+                primitives: self.ctx.tessellate(full_output.shapes, full_output.pixels_per_point),
+                textures_delta: full_output.textures_delta,
+                pixels_per_point: full_output.pixels_per_point
+            },
+            repaint_delay,
+            interacting: self.ctx.is_using_pointer(),
+            clone_preset
+        }
+    }
+}
+
+fn crosshair_editor(ui: &mut Ui, crosshair: &mut Crosshair) {
+    egui::Grid::new("crosshair").num_columns(2).show(ui, |ui| {
+        ui.label("shape");
+        ComboBox::from_id_salt("shape")
+            .selected_text(shape_label(crosshair.shape))
+            .show_ui(ui, |ui| {
+                for shape in [Shape::Cross, Shape::T, Shape::Chevron] {
+                    ui.selectable_value(&mut crosshair.shape, shape, shape_label(shape));
+                }
+            });
+        ui.end_row();
+
+        ui.label("arm length");
+        ui.add(Slider::new(&mut crosshair.arm_length, 0.0..=50.0));
+        ui.end_row();
+
+        ui.label("arm thickness");
+        ui.add(Slider::new(&mut crosshair.arm_thickness, 1.0..=20.0));
+        ui.end_row();
+
+        ui.label("gap");
+        ui.add(Slider::new(&mut crosshair.gap, 0.0..=50.0));
+        ui.end_row();
+
+        ui.label("color");
+        color_edit(ui, &mut crosshair.color);
+        ui.end_row();
+
+        ui.checkbox(&mut crosshair.outline, "outline");
+        ui.add_enabled_ui(crosshair.outline, |ui| {
+            ui.add(Slider::new(&mut crosshair.outline_thickness, 1.0..=5.0));
+            color_edit(ui, &mut crosshair.outline_color);
+        });
+        ui.end_row();
+
+        ui.checkbox(&mut crosshair.dot, "dot");
+        ui.add_enabled(crosshair.dot, Slider::new(&mut crosshair.dot_size, 1.0..=20.0));
+        ui.end_row();
+
+        ui.checkbox(&mut crosshair.circle, "circle");
+        ui.add_enabled(crosshair.circle, Slider::new(&mut crosshair.circle_radius, 1.0..=50.0));
+        ui.end_row();
+
+        ui.label("offset");
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut crosshair.offset_x).prefix("x ").speed(1.0));
+            ui.add(DragValue::new(&mut crosshair.offset_y).prefix("y ").speed(1.0));
+        });
+        ui.end_row();
+
+        ui.label("rotation");
+        ui.add(Slider::new(&mut crosshair.rotation, 0.0..=360.0).suffix("°"));
+        ui.end_row();
+
+        ui.label("opacity");
+        ui.add(Slider::new(&mut crosshair.opacity, 0.0..=1.0));
+        ui.end_row();
+
+        ui.label("hue shift");
+        ui.add(Slider::new(&mut crosshair.hue_shift, 0.0..=360.0).suffix("°"));
+        ui.end_row();
+
+        ui.label("render mode");
+        ComboBox::from_id_salt("render mode")
+            .selected_text(render_mode_label(crosshair.render_mode))
+            .show_ui(ui, |ui| {
+                for render_mode in [
+                    RenderMode::Smooth,
+                    RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::Before },
+                    RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::After }
+                ] {
+                    ui.selectable_value(&mut crosshair.render_mode, render_mode, render_mode_label(render_mode));
+                }
+            });
+        ui.end_row();
+    });
+}
+
+//thumbnails applying their preset on click (returns the preset to clone)
+fn presets_browser(ui: &mut Ui, presets: &[PresetTile], atlas: Option<&TextureHandle>, crosshair: &mut Crosshair) -> Option<String> {
+    const COLUMNS: usize = 4;
+    //thumbnail texels map to physical pixels
+    let thumbnail_size = THUMBNAIL_SIZE as f32 / ui.ctx().pixels_per_point();
+    let mut clone_preset = None;
+
+    ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+        egui::Grid::new("presets").num_columns(COLUMNS).show(ui, |ui| {
+            for (index, tile) in presets.iter().enumerate() {
+                ui.vertical(|ui| {
+                    let button = match (tile.thumbnail, atlas) {
+                        (Some(uv), Some(atlas)) => Button::image(Image::from_texture(SizedTexture::new(atlas.id(), [thumbnail_size; 2])).uv(uv)),
+                        _ => Button::new("no preview").min_size([thumbnail_size; 2].into())
+                    };
+                    let source = match tile.preset.source {
+                        PresetSource::Builtin => "built-in",
+                        PresetSource::Profile(_) => "profile"
+                    };
+                    if ui.add(button).on_hover_text(format!("{} ({source})", tile.preset.description)).clicked() {
+                        tile.preset.apply_to(crosshair);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(&tile.preset.name);
+                        if ui.small_button("clone").on_hover_text("copy into the profile directory").clicked() {
+                            clone_preset = Some(tile.preset.name.clone());
+                        }
+                    });
+                });
+                if (index + 1) % COLUMNS == 0 {
+                    ui.end_row();
+                }
+            }
+        });
+    });
+    clone_preset
+}
+
+//crosshair colors are linear with straight alpha, like egui's rgba
+fn color_edit(ui: &mut Ui, color: &mut Color) {
+    let mut rgba = color.to_array();
+    if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+        let [r, g, b, a] = rgba;
+        *color = Color::rgba(r, g, b, a);
+    }
+}
+
+fn shape_label(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Cross => "cross",
+        Shape::T => "t",
+        Shape::Chevron => "chevron"
+    }
+}
+
+fn render_mode_label(render_mode: RenderMode) -> &'static str {
+    match render_mode {
+        RenderMode::Smooth => "smooth",
+        RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::Before } => "pixel perfect (extra pixel left / top)",
+        RenderMode::PixelPerfect { extra_pixel: ExtraPixelSide::After } => "pixel perfect (extra pixel right / bottom)"
+    }
+}
@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+use ash::Device;
+use ash::vk::{
+    self, AccessFlags, Buffer, BufferCreateInfo, BufferImageCopy, BufferUsageFlags, CommandBuffer, ComponentMapping, DependencyFlags, DescriptorImageInfo, DescriptorPool, DescriptorPoolCreateFlags, DescriptorPoolCreateInfo, DescriptorPoolSize, DescriptorSet, DescriptorSetAllocateInfo, DescriptorSetLayout, DescriptorType, DeviceSize, Extent2D, Filter, Format, Image, ImageAspectFlags, ImageCreateInfo, ImageLayout, ImageMemoryBarrier, ImageSubresourceLayers, ImageSubresourceRange, ImageTiling, ImageType, ImageUsageFlags, ImageView, ImageViewCreateInfo, ImageViewType, IndexType, Offset2D, PipelineBindPoint, PipelineStageFlags, Rect2D, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode, ShaderStageFlags, SharingMode, WriteDescriptorSet, QUEUE_FAMILY_IGNORED
+};
+use egui::epaint::{ImageData, ImageDelta, Primitive, Vertex};
+use egui::{ClippedPrimitive, TextureFilter, TextureId, TexturesDelta};
+
+use crate::allocator::{Allocation, Allocator, MemoryUsage};
+use crate::deletion::{DestroyContext, DeviceResource};
+use crate::helper;
+use crate::pipeline::PipelineData;
+
+//most ui textures alive at once (egui itself only uses the font atlas)
+const MAX_TEXTURES: u32 = 64;
+//smallest vertex / index buffer allocation (bytes)
+const MIN_BUFFER_SIZE: DeviceSize = 64 * 1024;
+
+//tessellated user interface handed from the app to the renderer
+#[derive(Default)]
+pub struct UiFrame {
+    pub primitives: Vec<ClippedPrimitive>,
+    pub textures_delta: TexturesDelta,
+    //physical pixels per egui point
+    pub pixels_per_point: f32
+}
+
+//sampled R8G8B8A8_SRGB image with its own descriptor set
+struct UiTexture {
+    image: Image,
+    allocation: Allocation,
+    image_view: ImageView,
+    descriptor_pool: DescriptorPool,
+    descriptor_set: DescriptorSet
+}
+impl DeviceResource for UiTexture {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe {
+            //the pool is created with FREE_DESCRIPTOR_SET, freeing can't fail
+            let _ = ctx.logical_device.free_descriptor_sets(self.descriptor_pool, &[self.descriptor_set]);
+            ctx.logical_device.destroy_image_view(self.image_view, None);
+            ctx.logical_device.destroy_image(self.image, None);
+        }
+        ctx.allocator.free(ctx.logical_device, self.allocation);
+    }
+}
+
+//host visible buffer (vertices, indices, staging)
+struct UiBuffer {
+    buffer: Buffer,
+    allocation: Allocation,
+    capacity: DeviceSize
+}
+impl UiBuffer {
+    //nothing is left behind on failure
+    fn new(logical_device: &Device, allocator: &mut Allocator, capacity: DeviceSize, usage: BufferUsageFlags, name: &'static str) -> Result<Self, vk::Result> {
+        let buffer_create_info = BufferCreateInfo {
+            size: capacity,
+            usage,
+            sharing_mode: SharingMode::EXCLUSIVE,
+            ..Default::default()
+        };
+        let buffer = unsafe { logical_device.create_buffer(&buffer_create_info, None)? };
+        let allocation = match allocator.allocate_buffer(logical_device, buffer, MemoryUsage::Upload, name) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                unsafe { logical_device.destroy_buffer(buffer, None) };
+                return Err(err)
+            }
+        };
+        Ok(Self { buffer, allocation, capacity })
+    }
+
+    fn write<T: Copy>(&mut self, logical_device: &Device, allocator: &Allocator, data: &[T]) -> Result<(), vk::Result> {
+        self.allocation.write(data);
+        allocator.flush(logical_device, &self.allocation)
+    }
+}
+impl DeviceResource for UiBuffer {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        unsafe { ctx.logical_device.destroy_buffer(self.buffer, None) };
+        ctx.allocator.free(ctx.logical_device, self.allocation);
+    }
+}
+
+//copy of a staging buffer into (part of) a texture, recorded with the next frame
+struct TextureUpload {
+    staging: UiBuffer,
+    image: Image,
+    //layout the image is in before the copy
+    old_layout: ImageLayout,
+    offset: [usize; 2],
+    size: [usize; 2]
+}
+
+//resources the painter stopped using, to be retired by the renderer
+#[derive(Default)]
+pub(crate) struct UiGarbage {
+    textures: Vec<UiTexture>,
+    buffers: Vec<UiBuffer>,
+    samplers: Vec<Sampler>,
+    descriptor_pools: Vec<DescriptorPool>
+}
+impl DeviceResource for UiGarbage {
+    fn destroy(self, ctx: &mut DestroyContext) {
+        //descriptor sets are freed before their pool
+        self.textures.destroy(ctx);
+        self.buffers.destroy(ctx);
+        self.samplers.destroy(ctx);
+        self.descriptor_pools.destroy(ctx);
+    }
+}
+
+//draws egui meshes with PipelineDesc::UI
+//
+//textures are uploaded through staging buffers before the scene pass,
+//geometry lives in one host visible vertex / index buffer per frame in flight
+//(the default painter owns no device objects yet)
+#[derive(Default)]
+pub(crate) struct UiPainter {
+    descriptor_pool: DescriptorPool,
+    linear_sampler: Sampler,
+    nearest_sampler: Sampler,
+    textures: HashMap<TextureId, UiTexture>,
+    //per frame in flight
+    vertex_buffers: Vec<Option<UiBuffer>>,
+    index_buffers: Vec<Option<UiBuffer>>,
+    uploads: Vec<TextureUpload>,
+    //latest ui, drawn every frame until replaced
+    primitives: Vec<ClippedPrimitive>,
+    pixels_per_point: f32,
+    //texture changes not applied yet
+    pending_set: Vec<(TextureId, ImageDelta)>,
+    //textures to free once the current frame was submitted
+    pending_free: Vec<TextureId>
+}
+impl UiPainter {
+    //nothing is left behind on failure
+    pub(crate) fn new(logical_device: &Device, frames_in_flight: usize) -> Result<Self, vk::Result> {
+        let pool_sizes = [
+            DescriptorPoolSize { ty: DescriptorType::SAMPLED_IMAGE, descriptor_count: MAX_TEXTURES },
+            DescriptorPoolSize { ty: DescriptorType::SAMPLER, descriptor_count: MAX_TEXTURES }
+        ];
+        let descriptor_pool_create_info = DescriptorPoolCreateInfo::default()
+            .flags(DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+            .max_sets(MAX_TEXTURES)
+            .pool_sizes(&pool_sizes);
+        let descriptor_pool = unsafe {
+            logical_device.create_descriptor_pool(&descriptor_pool_create_info, None)?
+        };
+        let linear_sampler = match create_sampler(logical_device, Filter::LINEAR) {
+            Ok(sampler) => sampler,
+            Err(err) => {
+                unsafe { logical_device.destroy_descriptor_pool(descriptor_pool, None) };
+                return Err(err)
+            }
+        };
+        let nearest_sampler = match create_sampler(logical_device, Filter::NEAREST) {
+            Ok(sampler) => sampler,
+            Err(err) => unsafe {
+                logical_device.destroy_sampler(linear_sampler, None);
+                logical_device.destroy_descriptor_pool(descriptor_pool, None);
+                return Err(err)
+            }
+        };
+
+        Ok(Self {
+            descriptor_pool,
+            linear_sampler,
+            nearest_sampler,
+            textures: HashMap::new(),
+            vertex_buffers: (0..frames_in_flight).map(|_| None).collect(),
+            index_buffers: (0..frames_in_flight).map(|_| None).collect(),
+            uploads: Vec::new(),
+            primitives: Vec::new(),
+            pixels_per_point: 1.0,
+            pending_set: Vec::new(),
+            pending_free: Vec::new()
+        })
+    }
+
+    //replaces the drawn ui, texture changes accumulate until the next frame
+    pub(crate) fn set_frame(&mut self, frame: UiFrame) {
+        self.primitives = frame.primitives;
+        self.pixels_per_point = frame.pixels_per_point;
+        self.pending_set.extend(frame.textures_delta.set);
+        self.pending_free.extend(frame.textures_delta.free);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.primitives.clear();
+    }
+
+    pub(crate) fn has_primitives(&self) -> bool {
+        !self.primitives.is_empty()
+    }
+
+    //applies texture changes and fills the geometry buffers of `frame_index`
+    //(replaced resources are added to `garbage`, none of them is used by this
+    //frame; on failure `garbage` still holds everything that was replaced)
+    pub(crate) fn prepare(&mut self, logical_device: &Device, allocator: &mut Allocator, texture_set_layout: DescriptorSetLayout, frame_index: usize, garbage: &mut UiGarbage) -> Result<(), vk::Result> {
+        for (id, delta) in std::mem::take(&mut self.pending_set) {
+            let ImageData::Color(image) = &delta.image;
+            let (texture_image, old_layout) = match delta.pos {
+                Some(_) => match self.textures.get(&id) {
+                    Some(texture) => (texture.image, ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+                    None => {
+                        println!("ui texture {id:?} updated before it was created");
+                        continue
+                    }
+                },
+                None => {
+                    let sampler = match delta.options.magnification {
+                        TextureFilter::Nearest => self.nearest_sampler,
+                        TextureFilter::Linear => self.linear_sampler
+                    };
+                    let texture = match self.create_texture(logical_device, allocator, texture_set_layout, sampler, image.size) {
+                        Ok(texture) => texture,
+                        //out of descriptor sets: this texture is not drawn,
+                        //the rest of the ui still is
+                        Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL) => {
+                            println!("no descriptor set left for ui texture {id:?} (limit is {MAX_TEXTURES}); it is not drawn");
+                            //the previous contents do not match this delta anymore
+                            if let Some(removed) = self.textures.remove(&id) {
+                                self.discard_uploads(removed.image, garbage);
+                                garbage.textures.push(removed);
+                            }
+                            continue
+                        }
+                        Err(err) => return Err(err)
+                    };
+                    let image_handle = texture.image;
+                    if let Some(replaced) = self.textures.insert(id, texture) {
+                        self.discard_uploads(replaced.image, garbage);
+                        garbage.textures.push(replaced);
+                    }
+                    (image_handle, ImageLayout::UNDEFINED)
+                }
+            };
+
+            let size = std::mem::size_of_val(image.pixels.as_slice()) as DeviceSize;
+            let mut staging = UiBuffer::new(logical_device, allocator, size, BufferUsageFlags::TRANSFER_SRC, "ui texture staging")?;
+            if let Err(err) = staging.write(logical_device, allocator, &image.pixels) {
+                garbage.buffers.push(staging);
+                return Err(err)
+            }
+            self.uploads.push(TextureUpload {
+                staging,
+                image: texture_image,
+                old_layout,
+                offset: delta.pos.unwrap_or([0, 0]),
+                size: image.size
+            });
+        }
+
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        for primitive in &self.primitives {
+            if let Primitive::Mesh(mesh) = &primitive.primitive {
+                vertices.extend_from_slice(&mesh.vertices);
+                indices.extend_from_slice(&mesh.indices);
+            }
+        }
+        if !vertices.is_empty() {
+            let vertex_buffer = Self::buffer_with_capacity(
+                &mut self.vertex_buffers[frame_index],
+                garbage,
+                logical_device,
+                allocator,
+                std::mem::size_of_val(vertices.as_slice()) as DeviceSize,
+                BufferUsageFlags::VERTEX_BUFFER,
+                "ui vertices"
+            )?;
+            vertex_buffer.write(logical_device, allocator, &vertices)?;
+            let index_buffer = Self::buffer_with_capacity(
+                &mut self.index_buffers[frame_index],
+                garbage,
+                logical_device,
+                allocator,
+                std::mem::size_of_val(indices.as_slice()) as DeviceSize,
+                BufferUsageFlags::INDEX_BUFFER,
+                "ui indices"
+            )?;
+            index_buffer.write(logical_device, allocator, &indices)?;
+        }
+
+        Ok(())
+    }
+
+    //grows `slot` to hold `size` bytes (the old buffer goes to the garbage)
+    //uploads into a replaced or removed image are pointless
+    fn discard_uploads(&mut self, image: Image, garbage: &mut UiGarbage) {
+        let (obsolete, uploads) = std::mem::take(&mut self.uploads)
+            .into_iter()
+            .partition(|upload| upload.image == image);
+        self.uploads = uploads;
+        garbage.buffers.extend(obsolete.into_iter().map(|upload: TextureUpload| upload.staging));
+    }
+
+    fn buffer_with_capacity<'a>(
+        slot: &'a mut Option<UiBuffer>,
+        garbage: &mut UiGarbage,
+        logical_device: &Device,
+        allocator: &mut Allocator,
+        size: DeviceSize,
+        usage: BufferUsageFlags,
+        name: &'static str
+    ) -> Result<&'a mut UiBuffer, vk::Result> {
+        if slot.as_ref().is_some_and(|buffer| buffer.capacity < size) {
+            garbage.buffers.extend(slot.take());
+        }
+        let buffer = match slot.take() {
+            Some(buffer) => buffer,
+            None => {
+                let capacity = size.next_power_of_two().max(MIN_BUFFER_SIZE);
+                UiBuffer::new(logical_device, allocator, capacity, usage, name)?
+            }
+        };
+        Ok(slot.insert(buffer))
+    }
+
+    //nothing is left behind on failure
+    fn create_texture(&self, logical_device: &Device, allocator: &mut Allocator, texture_set_layout: DescriptorSetLayout, sampler: Sampler, size: [usize; 2]) -> Result<UiTexture, vk::Result> {
+        //egui colors are srgb, sampling converts them to linear
+        let format = Format::R8G8B8A8_SRGB;
+        let image_create_info = ImageCreateInfo {
+            image_type: ImageType::TYPE_2D,
+            format,
+            extent: vk::Extent3D { width: helper::usize_into_u32(size[0]), height: helper::usize_into_u32(size[1]), depth: 1 },
+            mip_levels: 1,
+            array_layers: 1,
+            samples: vk::SampleCountFlags::TYPE_1,
+            tiling: ImageTiling::OPTIMAL,
+            usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::TRANSFER_DST,
+            sharing_mode: SharingMode::EXCLUSIVE,
+            initial_layout: ImageLayout::UNDEFINED,
+            ..Default::default()
+        };
+
+        unsafe {
+            let image = logical_device.create_image(&image_create_info, None)?;
+            let allocation = match allocator.allocate_image(logical_device, image, MemoryUsage::GpuOnly, "ui texture") {
+                Ok(allocation) => allocation,
+                Err(err) => {
+                    logical_device.destroy_image(image, None);
+                    return Err(err)
+                }
+            };
+
+            let image_view_create_info = ImageViewCreateInfo {
+                image,
+                view_type: ImageViewType::TYPE_2D,
+                format,
+                components: ComponentMapping::default(),
+                subresource_range: color_subresource_range(),
+                ..Default::default()
+            };
+            let image_view = match logical_device.create_image_view(&image_view_create_info, None) {
+                Ok(image_view) => image_view,
+                Err(err) => {
+                    logical_device.destroy_image(image, None);
+                    allocator.free(logical_device, allocation);
+                    return Err(err)
+                }
+            };
+
+            let set_layouts = [texture_set_layout];
+            let descriptor_set_allocate_info = DescriptorSetAllocateInfo::default()
+                .descriptor_pool(self.descriptor_pool)
+                .set_layouts(&set_layouts);
+            let descriptor_set = match logical_device.allocate_descriptor_sets(&descriptor_set_allocate_info) {
+                Ok(descriptor_sets) => descriptor_sets[0],
+                Err(err) => {
+                    logical_device.destroy_image_view(image_view, None);
+                    logical_device.destroy_image(image, None);
+                    allocator.free(logical_device, allocation);
+                    return Err(err)
+                }
+            };
+
+            let image_info = [DescriptorImageInfo { sampler: vk::Sampler::null(), image_view, image_layout: ImageLayout::SHADER_READ_ONLY_OPTIMAL }];
+            let sampler_info = [DescriptorImageInfo { sampler, image_view: vk::ImageView::null(), image_layout: ImageLayout::UNDEFINED }];
+            let writes = [
+                WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(0)
+                    .descriptor_type(DescriptorType::SAMPLED_IMAGE)
+                    .image_info(&image_info),
+                WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(1)
+                    .descriptor_type(DescriptorType::SAMPLER)
+                    .image_info(&sampler_info)
+            ];
+            logical_device.update_descriptor_sets(&writes, &[]);
+
+            Ok(UiTexture { image, allocation, image_view, descriptor_pool: self.descriptor_pool, descriptor_set })
+        }
+    }
+
+    //records the pending texture uploads (outside of a render pass)
+    pub(crate) fn cmd_upload(&self, logical_device: &Device, command_buffer: CommandBuffer) {
+        for upload in &self.uploads {
+            let to_transfer = ImageMemoryBarrier {
+                src_access_mask: AccessFlags::SHADER_READ,
+                dst_access_mask: AccessFlags::TRANSFER_WRITE,
+                old_layout: upload.old_layout,
+                new_layout: ImageLayout::TRANSFER_DST_OPTIMAL,
+                src_queue_family_index: QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: QUEUE_FAMILY_IGNORED,
+                image: upload.image,
+                subresource_range: color_subresource_range(),
+                ..Default::default()
+            };
+            let to_shader_read = ImageMemoryBarrier {
+                src_access_mask: AccessFlags::TRANSFER_WRITE,
+                dst_access_mask: AccessFlags::SHADER_READ,
+                old_layout: ImageLayout::TRANSFER_DST_OPTIMAL,
+                new_layout: ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..to_transfer
+            };
+            let region = BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: ImageSubresourceLayers { aspect_mask: ImageAspectFlags::COLOR, mip_level: 0, base_array_layer: 0, layer_count: 1 },
+                image_offset: vk::Offset3D { x: upload.offset[0] as i32, y: upload.offset[1] as i32, z: 0 },
+                image_extent: vk::Extent3D { width: helper::usize_into_u32(upload.size[0]), height: helper::usize_into_u32(upload.size[1]), depth: 1 }
+            };
+
+            unsafe {
+                logical_device.cmd_pipeline_barrier(command_buffer, PipelineStageFlags::FRAGMENT_SHADER, PipelineStageFlags::TRANSFER, DependencyFlags::empty(), &[], &[], &[to_transfer]);
+                logical_device.cmd_copy_buffer_to_image(command_buffer, upload.staging.buffer, upload.image, ImageLayout::TRANSFER_DST_OPTIMAL, &[region]);
+                logical_device.cmd_pipeline_barrier(command_buffer, PipelineStageFlags::TRANSFER, PipelineStageFlags::FRAGMENT_SHADER, DependencyFlags::empty(), &[], &[], &[to_shader_read]);
+            }
+        }
+    }
+
+    //records the ui meshes (inside the ui render pass)
+    pub(crate) fn cmd_draw(&self, logical_device: &Device, command_buffer: CommandBuffer, pipeline: &PipelineData, extent: Extent2D, frame_index: usize) {
+        let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffers[frame_index], &self.index_buffers[frame_index]) else {
+            return
+        };
+        let screen_size = [extent.width as f32 / self.pixels_per_point, extent.height as f32 / self.pixels_per_point];
+        let screen_size_bytes: Vec<u8> = screen_size.iter().flat_map(|value| value.to_ne_bytes()).collect();
+
+        unsafe {
+            logical_device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipeline.pipeline);
+            logical_device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer.buffer], &[0]);
+            logical_device.cmd_bind_index_buffer(command_buffer, index_buffer.buffer, 0, IndexType::UINT32);
+            logical_device.cmd_push_constants(
+                command_buffer,
+                pipeline.pipeline_layout,
+                ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+                0,
+                &screen_size_bytes
+            );
+        }
+
+        let mut first_index = 0;
+        let mut vertex_offset = 0;
+        for primitive in &self.primitives {
+            let Primitive::Mesh(mesh) = &primitive.primitive else {
+                continue
+            };
+            let index_count = helper::usize_into_u32(mesh.indices.len());
+            let draw = (first_index, vertex_offset);
+            first_index += index_count;
+            vertex_offset += helper::usize_into_u32(mesh.vertices.len());
+
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                continue
+            };
+            let Some(scissor) = self.scissor(primitive.clip_rect, extent) else {
+                continue
+            };
+            unsafe {
+                logical_device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+                logical_device.cmd_bind_descriptor_sets(command_buffer, PipelineBindPoint::GRAPHICS, pipeline.pipeline_layout, 0, &[texture.descriptor_set], &[]);
+                logical_device.cmd_draw_indexed(command_buffer, index_count, 1, draw.0, draw.1 as i32, 0);
+            }
+        }
+    }
+
+    //clip rect in points -> scissor in pixels (none if nothing is visible)
+    fn scissor(&self, clip_rect: egui::Rect, extent: Extent2D) -> Option<Rect2D> {
+        let min_x = (clip_rect.min.x * self.pixels_per_point).round().clamp(0.0, extent.width as f32) as u32;
+        let min_y = (clip_rect.min.y * self.pixels_per_point).round().clamp(0.0, extent.height as f32) as u32;
+        let max_x = (clip_rect.max.x * self.pixels_per_point).round().clamp(min_x as f32, extent.width as f32) as u32;
+        let max_y = (clip_rect.max.y * self.pixels_per_point).round().clamp(min_y as f32, extent.height as f32) as u32;
+        if max_x == min_x || max_y == min_y {
+            return None
+        }
+        Some(Rect2D {
+            offset: Offset2D { x: min_x as i32, y: min_y as i32 },
+            extent: Extent2D { width: max_x - min_x, height: max_y - min_y }
+        })
+    }
+
+    //to be called after the frame was submitted: finished uploads and
+    //freed textures are handed back (still used by the submitted frame)
+    pub(crate) fn finish_frame(&mut self) -> UiGarbage {
+        let mut garbage = UiGarbage::default();
+        garbage.buffers.extend(self.uploads.drain(..).map(|upload| upload.staging));
+        for id in std::mem::take(&mut self.pending_free) {
+            garbage.textures.extend(self.textures.remove(&id));
+        }
+        garbage
+    }
+
+    //hands back every resource (renderer teardown)
+    pub(crate) fn take_all(&mut self) -> UiGarbage {
+        let mut garbage = self.finish_frame();
+        garbage.textures.extend(self.textures.drain().map(|(_, texture)| texture));
+        garbage.buffers.extend(self.vertex_buffers.iter_mut().chain(self.index_buffers.iter_mut()).filter_map(Option::take));
+        garbage.samplers.extend([self.linear_sampler, self.nearest_sampler]);
+        garbage.descriptor_pools.push(self.descriptor_pool);
+        garbage
+    }
+}
+
+fn create_sampler(logical_device: &Device, filter: Filter) -> Result<Sampler, vk::Result> {
+    let sampler_create_info = SamplerCreateInfo {
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_mode: SamplerMipmapMode::NEAREST,
+        address_mode_u: SamplerAddressMode::CLAMP_TO_EDGE,
+        address_mode_v: SamplerAddressMode::CLAMP_TO_EDGE,
+        address_mode_w: SamplerAddressMode::CLAMP_TO_EDGE,
+        max_lod: vk::LOD_CLAMP_NONE,
+        ..Default::default()
+    };
+    unsafe {
+        logical_device.create_sampler(&sampler_create_info, None)
+    }
+}
+
+fn color_subresource_range() -> ImageSubresourceRange {
+    ImageSubresourceRange {
+        aspect_mask: ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: 1
+    }
+}
@@ -0,0 +1,498 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ffi::{self, CStr};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use ash::{Entry, Instance};
+use ash::ext::debug_utils;
+use ash::vk::{
+    self, DebugUtilsMessageSeverityFlagsEXT, DebugUtilsMessageTypeFlagsEXT, DebugUtilsMessengerEXT, ValidationFeatureEnableEXT
+};
+
+pub const VALIDATION_LAYER_NAME: &CStr = c"VK_LAYER_KHRONOS_validation";
+
+//environment variable that toggles validation
+//(e.g. CUSTICLE_VALIDATION=1 or CUSTICLE_VALIDATION=gpu,sync)
+pub const VALIDATION_ENV_VAR: &str = "CUSTICLE_VALIDATION";
+
+//amount of messages kept in the debug message log
+//(oldest messages are discarded first)
+const MAX_LOGGED_MESSAGES: usize = 1024;
+
+//optional validation features (VkValidationFeaturesEXT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationFeature {
+    GpuAssisted,
+    BestPractices,
+    Synchronization
+}
+impl ValidationFeature {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gpu" | "gpu-assisted" => Some(Self::GpuAssisted),
+            "best-practices" | "bp" => Some(Self::BestPractices),
+            "sync" | "synchronization" => Some(Self::Synchronization),
+            _ => None
+        }
+    }
+
+    fn enables(self) -> &'static [ValidationFeatureEnableEXT] {
+        match self {
+            Self::GpuAssisted => &[
+                ValidationFeatureEnableEXT::GPU_ASSISTED,
+                ValidationFeatureEnableEXT::GPU_ASSISTED_RESERVE_BINDING_SLOT
+            ],
+            Self::BestPractices => &[ValidationFeatureEnableEXT::BEST_PRACTICES],
+            Self::Synchronization => &[ValidationFeatureEnableEXT::SYNCHRONIZATION_VALIDATION]
+        }
+    }
+}
+
+//runtime validation settings
+//(independent of the build profile)
+#[derive(Debug, Clone, Default)]
+pub struct ValidationConfig {
+    //request the khronos validation layer and a debug messenger
+    pub enabled: bool,
+    //additional validation features to enable
+    pub features: Vec<ValidationFeature>
+}
+impl ValidationConfig {
+    //parses "1"/"on", "0"/"off" or a comma separated feature list
+    //(a feature list implies enabled validation)
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "1" | "on" | "true" => return Ok(Self { enabled: true, features: Vec::new() }),
+            "0" | "off" | "false" => return Ok(Self::default()),
+            _ => ()
+        }
+
+        let mut features = Vec::new();
+        for name in value.split(',') {
+            let feature = ValidationFeature::parse(name)
+                .ok_or_else(|| format!("unknown validation feature '{}'", name.trim()))?;
+            if !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+
+        Ok(Self { enabled: true, features })
+    }
+
+    pub(crate) fn feature_enables(&self) -> Vec<ValidationFeatureEnableEXT> {
+        self.features
+            .iter()
+            .flat_map(|feature| feature.enables().iter().copied())
+            .collect()
+    }
+}
+
+//severity of a message reported by the validation layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessageSeverity {
+    Verbose,
+    Info,
+    Warning,
+    Error
+}
+impl MessageSeverity {
+    const ALL: [Self; 4] = [Self::Verbose, Self::Info, Self::Warning, Self::Error];
+
+    fn from_flags(flags: DebugUtilsMessageSeverityFlagsEXT) -> Self {
+        if flags.contains(DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            Self::Error
+        } else if flags.contains(DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            Self::Warning
+        } else if flags.contains(DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            Self::Info
+        } else {
+            Self::Verbose
+        }
+    }
+}
+
+//single message received by the debug messenger
+#[derive(Debug, Clone)]
+pub struct DebugMessage {
+    pub severity: MessageSeverity,
+    //e.g. "VALIDATION | PERFORMANCE"
+    pub message_type: String,
+    pub id_name: String,
+    pub id_number: i32,
+    pub message: String
+}
+
+//number of messages received per severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageCounts {
+    pub verbose: usize,
+    pub info: usize,
+    pub warnings: usize,
+    pub errors: usize
+}
+impl MessageCounts {
+    pub fn get(&self, severity: MessageSeverity) -> usize {
+        match severity {
+            MessageSeverity::Verbose => self.verbose,
+            MessageSeverity::Info => self.info,
+            MessageSeverity::Warning => self.warnings,
+            MessageSeverity::Error => self.errors
+        }
+    }
+
+    //messages received after the given checkpoint
+    pub fn since(&self, checkpoint: &MessageCounts) -> MessageCounts {
+        MessageCounts {
+            verbose: self.verbose.saturating_sub(checkpoint.verbose),
+            info: self.info.saturating_sub(checkpoint.info),
+            warnings: self.warnings.saturating_sub(checkpoint.warnings),
+            errors: self.errors.saturating_sub(checkpoint.errors)
+        }
+    }
+}
+
+//returned when validation errors occurred during a checked scenario
+#[derive(Debug, Clone)]
+pub struct ValidationFailure {
+    //number of errors reported during the scenario
+    pub errors: usize,
+    //error messages still present in the log
+    //(may be fewer than `errors` if the log overflowed or was drained)
+    pub messages: Vec<DebugMessage>
+}
+impl fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} vulkan validation error(s) occurred:", self.errors)?;
+        for message in self.messages.iter() {
+            writeln!(f, "\t[{} ({})] {}", message.id_name, message.id_number, message.message)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for ValidationFailure {}
+
+//thread-safe sink for debug messages
+//(handed to the debug callback through its user_data pointer)
+#[derive(Debug, Default)]
+pub struct DebugMessageLog {
+    //total messages received per severity (never reset)
+    counts: [AtomicUsize; 4],
+    //sequence number of the next message
+    next_sequence: AtomicUsize,
+    //most recent messages with their sequence numbers
+    messages: Mutex<VecDeque<(usize, DebugMessage)>>
+}
+impl DebugMessageLog {
+    //every message is counted and numbered, only warnings and errors are
+    //buffered (verbose / info messages would push them out of the log)
+    fn push(&self, message: DebugMessage) {
+        let mut messages = self.messages.lock().unwrap_or_else(|err| err.into_inner());
+        self.counts[message.severity as usize].fetch_add(1, Ordering::SeqCst);
+        let sequence = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        if message.severity < MessageSeverity::Warning {
+            return
+        }
+        if messages.len() == MAX_LOGGED_MESSAGES {
+            messages.pop_front();
+        }
+        messages.push_back((sequence, message));
+    }
+
+    //copy of all currently buffered warnings and errors (oldest first)
+    pub fn messages(&self) -> Vec<DebugMessage> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+
+    //removes and returns all buffered messages
+    //(counts are not affected)
+    pub fn drain(&self) -> Vec<DebugMessage> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .drain(..)
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    //total number of messages received per severity
+    //(use as a checkpoint before running a scenario)
+    pub fn counts(&self) -> MessageCounts {
+        //hold the lock so the counts match the message sequence
+        let _messages = self.messages.lock().unwrap_or_else(|err| err.into_inner());
+        let [verbose, info, warnings, errors] = MessageSeverity::ALL
+            .map(|severity| self.counts[severity as usize].load(Ordering::SeqCst));
+        MessageCounts { verbose, info, warnings, errors }
+    }
+
+    //fails if any validation error was reported after the checkpoint
+    pub fn check_no_errors_since(&self, checkpoint: &MessageCounts) -> Result<(), ValidationFailure> {
+        let errors = self.counts().since(checkpoint).errors;
+        if errors == 0 {
+            return Ok(())
+        }
+
+        //messages are numbered in arrival order, so everything
+        //after the checkpoint has a sequence >= total checkpoint count
+        let first_sequence =
+            checkpoint.verbose + checkpoint.info + checkpoint.warnings + checkpoint.errors;
+        let messages = self.messages
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .filter(|(sequence, message)| {
+                *sequence >= first_sequence && message.severity == MessageSeverity::Error
+            })
+            .map(|(_, message)| message.clone())
+            .collect();
+
+        Err(ValidationFailure { errors, messages })
+    }
+
+    //fails if any validation error was reported at all
+    pub fn check_no_errors(&self) -> Result<(), ValidationFailure> {
+        self.check_no_errors_since(&MessageCounts::default())
+    }
+
+    //panics (listing the messages) if the scenario caused validation errors
+    pub fn assert_no_errors_during<R>(&self, scenario: impl FnOnce() -> R) -> R {
+        let checkpoint = self.counts();
+        let result = scenario();
+        if let Err(failure) = self.check_no_errors_since(&checkpoint) {
+            panic!("{failure}");
+        }
+        result
+    }
+}
+
+//debug callback method
+unsafe extern "system" fn vulkan_debug_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    user_data: *mut std::os::raw::c_void,
+) -> vk::Bool32 {
+    unsafe {
+        let callback_data = *p_callback_data;
+        let message_id_number = callback_data.message_id_number;
+
+        let message_id_name = if callback_data.p_message_id_name.is_null() {
+            Cow::from("")
+        } else {
+            ffi::CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
+        };
+
+        let message = if callback_data.p_message.is_null() {
+            Cow::from("")
+        } else {
+            ffi::CStr::from_ptr(callback_data.p_message).to_string_lossy()
+        };
+
+        let severity = MessageSeverity::from_flags(message_severity);
+        //verbose / info messages are only counted
+        if severity >= MessageSeverity::Warning {
+            println!(
+                "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
+            );
+        }
+
+        //user_data points to the DebugMessageLog owned by the renderer
+        //(which outlives the instance and the messenger)
+        if let Some(log) = (user_data as *const DebugMessageLog).as_ref() {
+            log.push(DebugMessage {
+                severity,
+                message_type: format!("{message_type:?}"),
+                id_name: message_id_name.into_owned(),
+                id_number: message_id_number,
+                message: message.into_owned()
+            });
+        }
+
+        vk::FALSE
+    }
+}
+
+//wrapper around debug information
+//(used to destroy the messenger)
+pub(crate) struct DebugCtx {
+    pub(crate) debug_utils_loader: debug_utils::Instance,
+    pub(crate) debug_call_back: DebugUtilsMessengerEXT
+}
+
+pub(crate) fn get_debug_create_info(log: &Arc<DebugMessageLog>) -> vk::DebugUtilsMessengerCreateInfoEXT<'static> {
+    vk::DebugUtilsMessengerCreateInfoEXT {
+        message_severity:
+            DebugUtilsMessageSeverityFlagsEXT::ERROR |
+            DebugUtilsMessageSeverityFlagsEXT::WARNING |
+            DebugUtilsMessageSeverityFlagsEXT::INFO |
+            DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+        message_type:
+            DebugUtilsMessageTypeFlagsEXT::GENERAL |
+            DebugUtilsMessageTypeFlagsEXT::VALIDATION |
+            DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+        pfn_user_callback: Some(vulkan_debug_callback),
+        p_user_data: Arc::as_ptr(log) as *mut std::os::raw::c_void,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn create_debug_messenger(api_entry: &Entry, instance: &Instance, log: &Arc<DebugMessageLog>) -> Result<DebugCtx, vk::Result> {
+    let debug_messenger_create_info = get_debug_create_info(log);
+
+    //create loader and call_back
+    let (debug_utils_loader, debug_call_back) = unsafe {
+        let debug_utils_loader = debug_utils::Instance::new(api_entry, instance);
+        let debug_call_back =
+            debug_utils_loader
+                .create_debug_utils_messenger(&debug_messenger_create_info, None)?;
+        (debug_utils_loader, debug_call_back)
+    };
+
+    Ok(DebugCtx { debug_utils_loader, debug_call_back })
+}
+
+//checks if the validation layer and the extensions it needs are installed
+//(returns which optional parts are usable)
+pub(crate) struct ValidationSupport {
+    pub(crate) layer: bool,
+    pub(crate) debug_utils: bool,
+    pub(crate) validation_features: bool
+}
+pub(crate) fn query_validation_support(api_entry: &Entry) -> ValidationSupport {
+    let layer = unsafe { api_entry.enumerate_instance_layer_properties() }
+        .unwrap_or_default()
+        .iter()
+        .any(|layer| layer.layer_name_as_c_str() == Ok(VALIDATION_LAYER_NAME));
+
+    let has_extension = |layer_name: Option<&CStr>, extension_name: &CStr| {
+        unsafe { api_entry.enumerate_instance_extension_properties(layer_name) }
+            .unwrap_or_default()
+            .iter()
+            .any(|extension| extension.extension_name_as_c_str() == Ok(extension_name))
+    };
+
+    let debug_utils =
+        has_extension(None, debug_utils::NAME) ||
+        (layer && has_extension(Some(VALIDATION_LAYER_NAME), debug_utils::NAME));
+
+    let validation_features =
+        layer && has_extension(Some(VALIDATION_LAYER_NAME), ash::ext::validation_features::NAME);
+
+    ValidationSupport { layer, debug_utils, validation_features }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(severity: MessageSeverity, text: &str) -> DebugMessage {
+        DebugMessage {
+            severity,
+            message_type: "VALIDATION".to_string(),
+            id_name: "test".to_string(),
+            id_number: 0,
+            message: text.to_string()
+        }
+    }
+
+    fn texts(messages: &[DebugMessage]) -> Vec<&str> {
+        messages.iter().map(|message| message.message.as_str()).collect()
+    }
+
+    #[test]
+    fn counts_every_message_but_buffers_warnings_and_errors() {
+        let log = DebugMessageLog::default();
+        log.push(message(MessageSeverity::Verbose, "verbose"));
+        log.push(message(MessageSeverity::Info, "info"));
+        log.push(message(MessageSeverity::Warning, "warning"));
+        log.push(message(MessageSeverity::Error, "error"));
+        log.push(message(MessageSeverity::Warning, "another warning"));
+
+        assert_eq!(log.counts(), MessageCounts { verbose: 1, info: 1, warnings: 2, errors: 1 });
+        assert_eq!(texts(&log.messages()), ["warning", "error", "another warning"]);
+    }
+
+    #[test]
+    fn severity_is_the_highest_flag() {
+        let flags = DebugUtilsMessageSeverityFlagsEXT::WARNING | DebugUtilsMessageSeverityFlagsEXT::ERROR;
+        assert_eq!(MessageSeverity::from_flags(flags), MessageSeverity::Error);
+        assert_eq!(MessageSeverity::from_flags(DebugUtilsMessageSeverityFlagsEXT::empty()), MessageSeverity::Verbose);
+    }
+
+    #[test]
+    fn checkpoint_only_reports_later_errors() {
+        let log = DebugMessageLog::default();
+        log.push(message(MessageSeverity::Error, "before"));
+        let checkpoint = log.counts();
+        assert!(log.check_no_errors_since(&checkpoint).is_ok());
+
+        //verbose / info messages take sequence numbers without being buffered
+        log.push(message(MessageSeverity::Info, "info"));
+        log.push(message(MessageSeverity::Warning, "warning"));
+        log.push(message(MessageSeverity::Error, "after"));
+
+        let failure = log.check_no_errors_since(&checkpoint).unwrap_err();
+        assert_eq!(failure.errors, 1);
+        assert_eq!(texts(&failure.messages), ["after"]);
+        assert_eq!(log.counts().since(&checkpoint), MessageCounts { verbose: 0, info: 1, warnings: 1, errors: 1 });
+
+        let failure = log.check_no_errors().unwrap_err();
+        assert_eq!(failure.errors, 2);
+        assert_eq!(texts(&failure.messages), ["before", "after"]);
+
+        assert!(log.check_no_errors_since(&log.counts()).is_ok());
+    }
+
+    #[test]
+    fn oldest_messages_are_discarded() {
+        let log = DebugMessageLog::default();
+        for index in 0..MAX_LOGGED_MESSAGES + 10 {
+            log.push(message(MessageSeverity::Error, &index.to_string()));
+        }
+
+        let messages = log.messages();
+        assert_eq!(messages.len(), MAX_LOGGED_MESSAGES);
+        assert_eq!(messages[0].message, "10");
+        //the count includes the discarded errors
+        let failure = log.check_no_errors().unwrap_err();
+        assert_eq!(failure.errors, MAX_LOGGED_MESSAGES + 10);
+        assert_eq!(failure.messages.len(), MAX_LOGGED_MESSAGES);
+    }
+
+    #[test]
+    fn drain_keeps_the_counts() {
+        let log = DebugMessageLog::default();
+        log.push(message(MessageSeverity::Warning, "warning"));
+        log.push(message(MessageSeverity::Error, "error"));
+
+        assert_eq!(texts(&log.drain()), ["warning", "error"]);
+        assert!(log.messages().is_empty());
+        assert_eq!(log.counts(), MessageCounts { verbose: 0, info: 0, warnings: 1, errors: 1 });
+        let failure = log.check_no_errors().unwrap_err();
+        assert_eq!(failure.errors, 1);
+        assert!(failure.messages.is_empty());
+    }
+
+    #[test]
+    fn assert_no_errors_during_passes_the_result_through() {
+        let log = DebugMessageLog::default();
+        log.push(message(MessageSeverity::Error, "earlier"));
+        let result = log.assert_no_errors_during(|| {
+            log.push(message(MessageSeverity::Warning, "warning"));
+            42
+        });
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 vulkan validation error(s) occurred")]
+    fn assert_no_errors_during_panics_on_errors() {
+        let log = DebugMessageLog::default();
+        log.assert_no_errors_during(|| log.push(message(MessageSeverity::Error, "error")));
+    }
+}
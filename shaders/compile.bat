@@ -1,3 +0,0 @@
-C:/VulkanSDK/1.4.304.1/Bin/glslc.exe default.vert -o default_vert.spv
-C:/VulkanSDK/1.4.304.1/Bin/glslc.exe default.frag -o default_frag.spv
-pause
\ No newline at end of file
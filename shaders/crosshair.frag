@@ -0,0 +1,19 @@
+#version 450
+
+//shared with crosshair.vert, `time` is there for animated effects
+layout(push_constant) uniform PushConstants {
+    vec4 rect;
+    vec4 color;
+    vec2 extent;
+    vec2 pivot;
+    float rotation;
+    float time;
+} pc;
+
+layout(location = 0) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = fragColor;
+}
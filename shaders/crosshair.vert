@@ -0,0 +1,38 @@
+#version 450
+
+//one axis aligned rect per draw (6 vertices, no vertex buffer)
+layout(push_constant) uniform PushConstants {
+    //x, y, width, height in physical pixels (origin top-left)
+    vec4 rect;
+    vec4 color;
+    //swapchain extent in physical pixels
+    vec2 extent;
+    //rects turn clockwise by `rotation` radians around this point
+    vec2 pivot;
+    float rotation;
+    //seconds since the animation started
+    float time;
+} pc;
+
+layout(location = 0) out vec4 fragColor;
+
+//two clockwise triangles
+const vec2 corners[6] = vec2[](
+    vec2(0.0, 0.0),
+    vec2(1.0, 0.0),
+    vec2(1.0, 1.0),
+    vec2(0.0, 0.0),
+    vec2(1.0, 1.0),
+    vec2(0.0, 1.0)
+);
+
+void main() {
+    vec2 pixel = pc.rect.xy + corners[gl_VertexIndex] * pc.rect.zw;
+    //clockwise on screen as y points down
+    float s = sin(pc.rotation);
+    float c = cos(pc.rotation);
+    pixel = pc.pivot + mat2(c, s, -s, c) * (pixel - pc.pivot);
+    //pixels -> normalized device coordinates (y points down in vulkan)
+    gl_Position = vec4(pixel / pc.extent * 2.0 - 1.0, 0.0, 1.0);
+    fragColor = pc.color;
+}
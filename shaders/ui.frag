@@ -0,0 +1,14 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D uiTexture;
+layout(set = 0, binding = 1) uniform sampler uiSampler;
+
+layout(location = 0) in vec4 fragColor;
+layout(location = 1) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    //the texture is srgb as well (sampled as linear)
+    outColor = fragColor * texture(sampler2D(uiTexture, uiSampler), fragUv);
+}
@@ -0,0 +1,30 @@
+#version 450
+
+//egui meshes: positions in points (origin top-left)
+layout(push_constant) uniform PushConstants {
+    //screen size in points
+    vec2 screen_size;
+} pc;
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+//premultiplied srgb
+layout(location = 2) in vec4 color;
+
+layout(location = 0) out vec4 fragColor;
+layout(location = 1) out vec2 fragUv;
+
+//the framebuffer is srgb: blending happens on linear colors
+vec3 linear_from_srgb(vec3 srgb) {
+    vec3 cutoff = step(vec3(0.04045), srgb);
+    vec3 lower = srgb / 12.92;
+    vec3 higher = pow((srgb + 0.055) / 1.055, vec3(2.4));
+    return mix(lower, higher, cutoff);
+}
+
+void main() {
+    //points -> normalized device coordinates (y points down in vulkan)
+    gl_Position = vec4(position / pc.screen_size * 2.0 - 1.0, 0.0, 1.0);
+    fragColor = vec4(linear_from_srgb(color.rgb), color.a);
+    fragUv = uv;
+}